@@ -18,7 +18,7 @@ export async function GET() {
       buttonTitle: "Launch Frame",
       splashImageUrl: `${appUrl}/splash.jpg`,
       splashBackgroundColor: "#f7f7f7",
-      webhookUrl: `${appUrl}/api/webhook`,
+      webhookUrl: process.env.NEXT_PUBLIC_WEBHOOK_URL || `${appUrl}/api/webhook`,
     },
   };
 
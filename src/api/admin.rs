@@ -0,0 +1,796 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+use redis::AsyncCommands;
+use std::collections::BTreeMap;
+use std::env;
+use crate::api::auth::verify_admin;
+use crate::api::cdp::nfts::NftManager;
+use crate::api::nft_claim::{deliver_stranded_tokens, stranded_tokens};
+use crate::events::delivery;
+use crate::events::subscriptions::{self, Invalid};
+use crate::farcaster::composer::CastDraft;
+use crate::farcaster::lore::{self, Review, Reviewed};
+use crate::farcaster::CastClient;
+use crate::metrics;
+use crate::twitter::client::TwitterClient;
+use crate::twitter::posts;
+use crate::twitter::queue;
+use crate::twitter::ratelimit::{self, PostBudget};
+use crate::workers::Supervisor;
+use tracing::{info, warn};
+
+// Hash de Redis con los controles pausados: campo = control, valor = PauseInfo en JSON
+const CONTROLS_KEY: &str = "admin:controls";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    TwitterWorker,
+    FarcasterWorker,
+    TwitterReplies,
+    FarcasterReplies,
+    TelegramWorker,
+    TelegramReplies,
+    DiscordWorker,
+    DiscordReplies,
+    Webhooks,
+    Minting,
+}
+
+impl Control {
+    const ALL: [Control; 10] = [
+        Control::TwitterWorker,
+        Control::FarcasterWorker,
+        Control::TwitterReplies,
+        Control::FarcasterReplies,
+        Control::TelegramWorker,
+        Control::TelegramReplies,
+        Control::DiscordWorker,
+        Control::DiscordReplies,
+        Control::Webhooks,
+        Control::Minting,
+    ];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Control::TwitterWorker => "twitter_worker",
+            Control::FarcasterWorker => "farcaster_worker",
+            Control::TwitterReplies => "twitter_replies",
+            Control::FarcasterReplies => "farcaster_replies",
+            Control::TelegramWorker => "telegram_worker",
+            Control::TelegramReplies => "telegram_replies",
+            Control::DiscordWorker => "discord_worker",
+            Control::DiscordReplies => "discord_replies",
+            Control::Webhooks => "webhooks",
+            Control::Minting => "minting",
+        }
+    }
+
+    fn from_str(value: &str) -> Option<Self> {
+        Control::ALL.iter().copied().find(|c| c.as_str() == value)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct PauseInfo {
+    paused_by: String,
+    paused_at: i64,
+    reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct PauseRequest {
+    reason: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct CastReplyTarget {
+    hash: String,
+    fid: u64,
+}
+
+#[derive(Deserialize)]
+pub struct CastRequest {
+    text: String,
+    #[serde(default)]
+    embeds: Vec<String>,
+    // parent_url del canal
+    channel: Option<String>,
+    reply_to: Option<CastReplyTarget>,
+    #[serde(default)]
+    frame: bool,
+    // ipfs://CID, CID o URL del gateway de Pinata
+    nft_image: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct TweetRequest {
+    text: String,
+    reply_to: Option<String>,
+    // Adjunta una imagen de DALL-E generada a partir del texto
+    #[serde(default)]
+    image: bool,
+}
+
+#[derive(Deserialize)]
+pub struct WebhookRequest {
+    url: String,
+    // Tipos de evento; sin ellos se reciben todos
+    #[serde(default)]
+    events: Vec<String>,
+}
+
+#[derive(Deserialize)]
+pub struct UpgradeRequest {
+    // Dirección de la nueva implementación ya desplegada
+    implementation: String,
+}
+
+#[derive(Deserialize)]
+pub struct LoreApproveRequest {
+    text: Option<String>,
+    // Segundos UNIX; por defecto se mantiene la hora del calendario
+    scheduled_at: Option<i64>,
+}
+
+#[derive(Deserialize)]
+pub struct LoreRejectRequest {
+    reason: Option<String>,
+}
+
+// Si Redis no responde se asume que no hay pausa: los workers fallarán por su cuenta
+pub async fn is_paused(redis_client: &redis::Client, control: Control) -> bool {
+    let result: redis::RedisResult<bool> = async {
+        let mut con = redis_client.get_async_connection().await?;
+        con.hexists(CONTROLS_KEY, control.as_str()).await
+    }.await;
+
+    match result {
+        Ok(paused) => paused,
+        Err(e) => {
+            warn!(control = control.as_str(), error = %e, "error reading admin control, assuming not paused");
+            metrics::redis_error("admin");
+            false
+        }
+    }
+}
+
+async fn read_controls(redis_client: &redis::Client) -> redis::RedisResult<BTreeMap<&'static str, Option<PauseInfo>>> {
+    let mut con = redis_client.get_async_connection().await?;
+    let stored: BTreeMap<String, String> = con.hgetall(CONTROLS_KEY).await?;
+
+    Ok(Control::ALL
+        .iter()
+        .map(|c| {
+            let info = stored.get(c.as_str()).and_then(|v| serde_json::from_str(v).ok());
+            (c.as_str(), info)
+        })
+        .collect())
+}
+
+pub async fn handle_controls_get(
+    req: HttpRequest,
+    redis_client: web::Data<redis::Client>,
+) -> impl Responder {
+    if let Err(response) = verify_admin(&req).await {
+        return response;
+    }
+
+    match read_controls(&redis_client).await {
+        Ok(controls) => HttpResponse::Ok().json(controls),
+        Err(_) => {
+            metrics::redis_error("admin");
+            HttpResponse::InternalServerError().body("Redis connection error")
+        }
+    }
+}
+
+pub async fn handle_control_pause(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: Option<web::Json<PauseRequest>>,
+    redis_client: web::Data<redis::Client>,
+) -> impl Responder {
+    let claims = match verify_admin(&req).await {
+        Ok(claims) => claims,
+        Err(response) => return response,
+    };
+
+    let control = match Control::from_str(&path.into_inner()) {
+        Some(control) => control,
+        None => return HttpResponse::NotFound().body("Unknown control"),
+    };
+
+    let info = PauseInfo {
+        paused_by: claims.sub,
+        paused_at: chrono::Utc::now().timestamp(),
+        reason: body.and_then(|b| b.into_inner().reason),
+    };
+
+    let result: redis::RedisResult<()> = async {
+        let mut con = redis_client.get_async_connection().await?;
+        con.hset(CONTROLS_KEY, control.as_str(), serde_json::to_string(&info).unwrap_or_default()).await
+    }.await;
+
+    match result {
+        Ok(_) => {
+            info!(control = control.as_str(), by = %info.paused_by, "control paused");
+            HttpResponse::Ok().json(serde_json::json!({
+                "control": control.as_str(),
+                "paused": true,
+                "info": info,
+            }))
+        },
+        Err(_) => {
+            metrics::redis_error("admin");
+            HttpResponse::InternalServerError().body("Redis connection error")
+        }
+    }
+}
+
+pub async fn handle_control_resume(
+    req: HttpRequest,
+    path: web::Path<String>,
+    redis_client: web::Data<redis::Client>,
+) -> impl Responder {
+    let claims = match verify_admin(&req).await {
+        Ok(claims) => claims,
+        Err(response) => return response,
+    };
+
+    let control = match Control::from_str(&path.into_inner()) {
+        Some(control) => control,
+        None => return HttpResponse::NotFound().body("Unknown control"),
+    };
+
+    let result: redis::RedisResult<()> = async {
+        let mut con = redis_client.get_async_connection().await?;
+        con.hdel(CONTROLS_KEY, control.as_str()).await
+    }.await;
+
+    match result {
+        Ok(_) => {
+            info!(control = control.as_str(), by = %claims.sub, "control resumed");
+            HttpResponse::Ok().json(serde_json::json!({
+                "control": control.as_str(),
+                "paused": false,
+            }))
+        },
+        Err(_) => {
+            metrics::redis_error("admin");
+            HttpResponse::InternalServerError().body("Redis connection error")
+        }
+    }
+}
+
+// Fuerza un poll de menciones sin esperar al intervalo del worker
+pub async fn handle_worker_poll(
+    req: HttpRequest,
+    path: web::Path<String>,
+    redis_client: web::Data<redis::Client>,
+    supervisor: web::Data<Supervisor>,
+) -> impl Responder {
+    let claims = match verify_admin(&req).await {
+        Ok(claims) => claims,
+        Err(response) => return response,
+    };
+
+    let worker = path.into_inner();
+    let control = match worker.as_str() {
+        "twitter" => Control::TwitterWorker,
+        "farcaster" => Control::FarcasterWorker,
+        "telegram" => Control::TelegramWorker,
+        "discord_governance" => Control::DiscordWorker,
+        "events" => Control::Webhooks,
+        _ => return HttpResponse::NotFound().body("Unknown worker"),
+    };
+
+    if is_paused(&redis_client, control).await {
+        return HttpResponse::Conflict().body("Worker is paused");
+    }
+
+    if !supervisor.trigger(&worker) {
+        return HttpResponse::ServiceUnavailable().body("Worker is not running");
+    }
+
+    info!(worker = %worker, by = %claims.sub, "mention poll triggered");
+    HttpResponse::Accepted().json(serde_json::json!({ "worker": worker, "triggered": true }))
+}
+
+fn env_configured(name: &str) -> bool {
+    env::var(name).map(|v| !v.is_empty()).unwrap_or(false)
+}
+
+// Configuración efectiva en runtime; los secretos solo se reportan como presentes o no
+pub async fn handle_runtime_config(
+    req: HttpRequest,
+    redis_client: web::Data<redis::Client>,
+    supervisor: web::Data<Supervisor>,
+) -> impl Responder {
+    if let Err(response) = verify_admin(&req).await {
+        return response;
+    }
+
+    let controls = match read_controls(&redis_client).await {
+        Ok(controls) => controls,
+        Err(_) => {
+            metrics::redis_error("admin");
+            return HttpResponse::InternalServerError().body("Redis connection error");
+        }
+    };
+
+    let secrets: BTreeMap<&str, bool> = [
+        "JWT_SECRET", "ADMIN_TOKEN", "OPENAI_API_KEY", "MNEMONIC", "JWT_SECRET_PINATA",
+        "TWITTER_API_KEY", "TWITTER_API_SECRET", "TWITTER_ACCESS_TOKEN", "TWITTER_ACCESS_SECRET",
+        "TELEGRAM_BOT_TOKEN", "TELEGRAM_WEBHOOK_SECRET", "DISCORD_BOT_TOKEN",
+    ]
+        .iter()
+        .map(|name| (*name, env_configured(name)))
+        .collect();
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "controls": controls,
+        "workers": supervisor.statuses(),
+        "poll_interval_secs": {
+            "twitter": ratelimit::POLL_INTERVAL.as_secs(),
+            "farcaster": crate::farcaster::cast::POLL_INTERVAL.as_secs(),
+        },
+        "contracts": {
+            "nft": env::var("NFT_CONTRACT_ADDRESS").ok(),
+            "proposals": env::var("PROPOSAL_CONTRACT_ADDRESS").ok(),
+        },
+        "log_format": env::var("LOG_FORMAT").unwrap_or_else(|_| "text".to_string()),
+        "rust_log": env::var("RUST_LOG").unwrap_or_else(|_| "info".to_string()),
+        "secrets_configured": secrets,
+    }))
+}
+
+fn build_draft(request: &CastRequest) -> anyhow::Result<CastDraft> {
+    let mut draft = CastDraft::new(&request.text);
+
+    if let Some(target) = &request.reply_to {
+        draft = draft.reply_to(&target.hash, target.fid);
+    } else if let Some(channel) = &request.channel {
+        draft = draft.in_channel(channel);
+    }
+    if request.frame {
+        draft = draft.embed_frame()?;
+    }
+    if let Some(image) = &request.nft_image {
+        draft = draft.embed_nft_image(image)?;
+    }
+    for url in &request.embeds {
+        draft = draft.embed(url)?;
+    }
+    Ok(draft)
+}
+
+// Publica un cast como el bot; si el texto es largo sale como cadena numerada
+pub async fn handle_farcaster_cast(
+    req: HttpRequest,
+    body: web::Json<CastRequest>,
+    cast_client: Option<web::Data<CastClient>>,
+) -> impl Responder {
+    let claims = match verify_admin(&req).await {
+        Ok(claims) => claims,
+        Err(response) => return response,
+    };
+
+    let cast_client = match cast_client {
+        Some(cast_client) => cast_client,
+        None => return HttpResponse::ServiceUnavailable().body("Farcaster is not configured"),
+    };
+
+    let draft = match build_draft(&body) {
+        Ok(draft) => draft,
+        Err(e) => return HttpResponse::BadRequest().body(format!("Invalid cast: {}", e)),
+    };
+
+    match cast_client.publish(&draft, None).await {
+        Ok(casts) => {
+            info!(by = %claims.sub, parts = casts.len(), "cast published from admin API");
+            HttpResponse::Ok().json(casts)
+        },
+        Err(e) => {
+            warn!(error = %e, "error publishing cast from admin API");
+            HttpResponse::BadGateway().body(format!("Error publishing cast: {}", e))
+        }
+    }
+}
+
+// Post programado en X como el bot; cuenta contra la reserva de posts programados
+// y si el texto es largo sale como hilo numerado. La imagen y la publicación van en
+// un job; la cabecera Idempotency-Key evita publicarlo dos veces
+pub async fn handle_twitter_post(
+    req: HttpRequest,
+    body: web::Json<TweetRequest>,
+    redis_client: web::Data<redis::Client>,
+    twitter_client: Option<web::Data<TwitterClient>>,
+) -> impl Responder {
+    let claims = match verify_admin(&req).await {
+        Ok(claims) => claims,
+        Err(response) => return response,
+    };
+
+    if twitter_client.is_none() {
+        return HttpResponse::ServiceUnavailable().body("X is not configured");
+    }
+    if body.text.trim().is_empty() {
+        return HttpResponse::BadRequest().body("Text is required");
+    }
+
+    let idempotency_key = req.headers().get("Idempotency-Key").and_then(|value| value.to_str().ok());
+    match posts::enqueue_post(&redis_client, &body.text, body.reply_to.clone(), body.image, &claims.sub, idempotency_key).await {
+        Ok(enqueued) => {
+            let job = enqueued.job();
+            info!(by = %claims.sub, job_id = %job.id, status = job.status.as_str(), "X post queued from admin API");
+            HttpResponse::Accepted()
+                .insert_header(("Location", format!("/jobs/{}", job.id)))
+                .json(serde_json::json!({ "job_id": job.id, "status": job.status.as_str() }))
+        },
+        Err(e) => {
+            warn!(error = %e, "error queueing X post from admin API");
+            metrics::redis_error("admin");
+            HttpResponse::InternalServerError().body("Error queueing post")
+        }
+    }
+}
+
+pub async fn handle_lore_calendar(
+    req: HttpRequest,
+    redis_client: web::Data<redis::Client>,
+) -> impl Responder {
+    if let Err(response) = verify_admin(&req).await {
+        return response;
+    }
+
+    match lore::calendar(&redis_client).await {
+        Ok(entries) => HttpResponse::Ok().json(entries),
+        Err(_) => {
+            metrics::redis_error("admin");
+            HttpResponse::InternalServerError().body("Redis connection error")
+        }
+    }
+}
+
+async fn review_lore(redis_client: &redis::Client, id: &str, review: Review, by: &str) -> HttpResponse {
+    match lore::review(redis_client, id, review, by).await {
+        Ok(Reviewed::Updated(entry)) => HttpResponse::Ok().json(entry),
+        Ok(Reviewed::NotFound) => HttpResponse::NotFound().body("Unknown lore entry"),
+        Ok(Reviewed::Conflict(status)) => HttpResponse::Conflict().body(format!("Lore entry is {:?}", status)),
+        Err(e) => {
+            warn!(id, error = %e, "error reviewing lore entry");
+            metrics::redis_error("admin");
+            HttpResponse::InternalServerError().body("Error updating lore entry")
+        }
+    }
+}
+
+pub async fn handle_lore_approve(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: Option<web::Json<LoreApproveRequest>>,
+    redis_client: web::Data<redis::Client>,
+) -> impl Responder {
+    let claims = match verify_admin(&req).await {
+        Ok(claims) => claims,
+        Err(response) => return response,
+    };
+
+    let (text, scheduled_at) = match body.map(|b| b.into_inner()) {
+        Some(request) => (request.text, request.scheduled_at),
+        None => (None, None),
+    };
+    if text.as_deref().map(|t| t.trim().is_empty()).unwrap_or(false) {
+        return HttpResponse::BadRequest().body("Lore text cannot be empty");
+    }
+
+    review_lore(&redis_client, &path.into_inner(), Review::Approve { text, scheduled_at }, &claims.sub).await
+}
+
+pub async fn handle_lore_reject(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: Option<web::Json<LoreRejectRequest>>,
+    redis_client: web::Data<redis::Client>,
+) -> impl Responder {
+    let claims = match verify_admin(&req).await {
+        Ok(claims) => claims,
+        Err(response) => return response,
+    };
+
+    let reason = body.and_then(|b| b.into_inner().reason);
+    review_lore(&redis_client, &path.into_inner(), Review::Reject { reason }, &claims.sub).await
+}
+
+// Menciones de X que agotaron los reintentos, la más reciente primero
+pub async fn handle_twitter_dead_letters(
+    req: HttpRequest,
+    redis_client: web::Data<redis::Client>,
+) -> impl Responder {
+    if let Err(response) = verify_admin(&req).await {
+        return response;
+    }
+
+    let result = async {
+        let mut con = redis_client.get_async_connection().await?;
+        queue::dead_letters(&mut con).await
+    }.await;
+
+    match result {
+        Ok(failed) => HttpResponse::Ok().json(failed),
+        Err(_) => {
+            metrics::redis_error("admin");
+            HttpResponse::InternalServerError().body("Redis connection error")
+        }
+    }
+}
+
+pub async fn handle_twitter_dead_letter_retry(
+    req: HttpRequest,
+    redis_client: web::Data<redis::Client>,
+) -> impl Responder {
+    let claims = match verify_admin(&req).await {
+        Ok(claims) => claims,
+        Err(response) => return response,
+    };
+
+    let result = async {
+        let mut con = redis_client.get_async_connection().await?;
+        queue::requeue_dead_letters(&mut con).await
+    }.await;
+
+    match result {
+        Ok(requeued) => {
+            info!(requeued, by = %claims.sub, "X dead letters requeued");
+            HttpResponse::Ok().json(serde_json::json!({ "requeued": requeued }))
+        },
+        Err(_) => {
+            metrics::redis_error("admin");
+            HttpResponse::InternalServerError().body("Redis connection error")
+        }
+    }
+}
+
+// Cupo mensual de posts de X y estado del límite de la API de menciones
+pub async fn handle_twitter_quota(
+    req: HttpRequest,
+    redis_client: web::Data<redis::Client>,
+) -> impl Responder {
+    if let Err(response) = verify_admin(&req).await {
+        return response;
+    }
+
+    let result = async {
+        let mut con = redis_client.get_async_connection().await?;
+        let posts = PostBudget::from_env().quota(&mut con).await?;
+        let polling = ratelimit::schedule(&mut con).await?;
+        Ok::<_, redis::RedisError>((posts, polling))
+    }.await;
+
+    match result {
+        Ok((posts, polling)) => HttpResponse::Ok().json(serde_json::json!({
+            "posts": posts,
+            "mentions_polling": polling,
+        })),
+        Err(_) => {
+            metrics::redis_error("admin");
+            HttpResponse::InternalServerError().body("Redis connection error")
+        }
+    }
+}
+
+// Suscripciones de webhooks, sin sus secretos
+pub async fn handle_webhooks_get(
+    req: HttpRequest,
+    redis_client: web::Data<redis::Client>,
+) -> impl Responder {
+    if let Err(response) = verify_admin(&req).await {
+        return response;
+    }
+
+    let result = async {
+        let mut con = redis_client.get_async_connection().await?;
+        subscriptions::list(&mut con).await
+    }.await;
+
+    match result {
+        Ok(subscriptions) => HttpResponse::Ok().json(subscriptions.iter().map(|s| s.redacted()).collect::<Vec<_>>()),
+        Err(_) => {
+            metrics::redis_error("admin");
+            HttpResponse::InternalServerError().body("Redis connection error")
+        }
+    }
+}
+
+// Crea la suscripción; la respuesta es la única vez que se devuelve el secreto
+pub async fn handle_webhook_create(
+    req: HttpRequest,
+    body: web::Json<WebhookRequest>,
+    redis_client: web::Data<redis::Client>,
+) -> impl Responder {
+    let claims = match verify_admin(&req).await {
+        Ok(claims) => claims,
+        Err(response) => return response,
+    };
+
+    let WebhookRequest { url, events } = body.into_inner();
+    match subscriptions::create(&redis_client, &url, events, &claims.sub).await {
+        Ok(Ok(subscription)) => {
+            info!(id = %subscription.id, url = %subscription.url, events = ?subscription.events, by = %claims.sub, "webhook subscription created");
+            HttpResponse::Created().json(subscription)
+        },
+        Ok(Err(Invalid::Url)) => HttpResponse::BadRequest().body("Invalid webhook URL"),
+        Ok(Err(Invalid::EventType(event))) => HttpResponse::BadRequest().body(format!("Unknown event type: {}", event)),
+        Err(_) => {
+            metrics::redis_error("admin");
+            HttpResponse::InternalServerError().body("Redis connection error")
+        }
+    }
+}
+
+pub async fn handle_webhook_delete(
+    req: HttpRequest,
+    path: web::Path<String>,
+    redis_client: web::Data<redis::Client>,
+) -> impl Responder {
+    let claims = match verify_admin(&req).await {
+        Ok(claims) => claims,
+        Err(response) => return response,
+    };
+
+    let id = path.into_inner();
+    let result = async {
+        let mut con = redis_client.get_async_connection().await?;
+        subscriptions::delete(&mut con, &id).await
+    }.await;
+
+    match result {
+        Ok(true) => {
+            info!(id = %id, by = %claims.sub, "webhook subscription deleted");
+            HttpResponse::NoContent().finish()
+        },
+        Ok(false) => HttpResponse::NotFound().body("Unknown webhook subscription"),
+        Err(_) => {
+            metrics::redis_error("admin");
+            HttpResponse::InternalServerError().body("Redis connection error")
+        }
+    }
+}
+
+// Entregas de webhooks que agotaron los reintentos, la más reciente primero
+pub async fn handle_webhook_dead_letters(
+    req: HttpRequest,
+    redis_client: web::Data<redis::Client>,
+) -> impl Responder {
+    if let Err(response) = verify_admin(&req).await {
+        return response;
+    }
+
+    let result = async {
+        let mut con = redis_client.get_async_connection().await?;
+        delivery::dead_letters(&mut con).await
+    }.await;
+
+    match result {
+        Ok(failed) => HttpResponse::Ok().json(failed),
+        Err(_) => {
+            metrics::redis_error("admin");
+            HttpResponse::InternalServerError().body("Redis connection error")
+        }
+    }
+}
+
+pub async fn handle_webhook_dead_letter_retry(
+    req: HttpRequest,
+    redis_client: web::Data<redis::Client>,
+) -> impl Responder {
+    let claims = match verify_admin(&req).await {
+        Ok(claims) => claims,
+        Err(response) => return response,
+    };
+
+    let result = async {
+        let mut con = redis_client.get_async_connection().await?;
+        delivery::requeue_dead_letters(&mut con).await
+    }.await;
+
+    match result {
+        Ok(requeued) => {
+            info!(requeued, by = %claims.sub, "webhook dead letters requeued");
+            HttpResponse::Ok().json(serde_json::json!({ "requeued": requeued }))
+        },
+        Err(_) => {
+            metrics::redis_error("admin");
+            HttpResponse::InternalServerError().body("Redis connection error")
+        }
+    }
+}
+
+// Versión del contrato NFT: si el proxy ya mintea con mintTo
+pub async fn handle_nft_contract_get(
+    req: HttpRequest,
+    nft_manager: web::Data<NftManager>,
+) -> impl Responder {
+    if let Err(response) = verify_admin(&req).await {
+        return response;
+    }
+
+    HttpResponse::Ok().json(serde_json::json!({
+        "signer": format!("{:?}", nft_manager.signer_address()),
+        "mint_to": nft_manager.supports_mint_to(),
+    }))
+}
+
+// Apunta el proxy del contrato NFT a una implementación ya desplegada
+pub async fn handle_nft_upgrade(
+    req: HttpRequest,
+    body: web::Json<UpgradeRequest>,
+    nft_manager: web::Data<NftManager>,
+) -> impl Responder {
+    let claims = match verify_admin(&req).await {
+        Ok(claims) => claims,
+        Err(response) => return response,
+    };
+
+    let implementation = match body.implementation.parse() {
+        Ok(address) => address,
+        Err(_) => return HttpResponse::BadRequest().body("Invalid implementation address"),
+    };
+
+    match nft_manager.upgrade_implementation(implementation).await {
+        Ok(receipt) => {
+            info!(implementation = %body.implementation, tx_hash = ?receipt.transaction_hash, by = %claims.sub, "NFT contract upgraded");
+            HttpResponse::Ok().json(serde_json::json!({
+                "tx_hash": format!("{:?}", receipt.transaction_hash),
+                "mint_to": nft_manager.supports_mint_to(),
+            }))
+        },
+        Err(e) => {
+            warn!(implementation = %body.implementation, error = %e, "NFT contract upgrade failed");
+            HttpResponse::BadRequest().body(format!("Upgrade failed: {}", e))
+        }
+    }
+}
+
+// NFTs que el flujo de mint y transferencia dejó en la wallet del backend
+pub async fn handle_nft_stranded(
+    req: HttpRequest,
+    redis_client: web::Data<redis::Client>,
+    nft_manager: web::Data<NftManager>,
+) -> impl Responder {
+    if let Err(response) = verify_admin(&req).await {
+        return response;
+    }
+
+    match stranded_tokens(&redis_client, &nft_manager).await {
+        Ok(tokens) => HttpResponse::Ok().json(tokens),
+        Err(e) => {
+            warn!(error = %e, "error looking for stranded NFTs");
+            HttpResponse::InternalServerError().body("Error looking for stranded NFTs")
+        }
+    }
+}
+
+// Encola la entrega de los NFTs varados que tienen destinatario
+pub async fn handle_nft_stranded_deliver(
+    req: HttpRequest,
+    redis_client: web::Data<redis::Client>,
+    nft_manager: web::Data<NftManager>,
+) -> impl Responder {
+    let claims = match verify_admin(&req).await {
+        Ok(claims) => claims,
+        Err(response) => return response,
+    };
+
+    match deliver_stranded_tokens(&redis_client, &nft_manager).await {
+        Ok(deliveries) => {
+            let queued = deliveries.iter().filter(|delivery| delivery.job_id.is_some()).count();
+            info!(queued, skipped = deliveries.len() - queued, by = %claims.sub, "stranded NFT deliveries queued");
+            HttpResponse::Accepted().json(deliveries)
+        },
+        Err(e) => {
+            warn!(error = %e, "error delivering stranded NFTs");
+            HttpResponse::InternalServerError().body("Error delivering stranded NFTs")
+        }
+    }
+}
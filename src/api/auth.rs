@@ -52,3 +52,88 @@ pub async fn verify_token(req: &HttpRequest) -> Result<Claims, HttpResponse> {
 
     Ok(token_data.claims)
 }
+
+// Comparación en tiempo constante para no filtrar el secreto por tiempos de respuesta
+pub fn verify_secret(secret: &str, token: &str) -> bool {
+    let (secret, token) = (secret.as_bytes(), token.as_bytes());
+    secret.len() == token.len() && secret.iter().zip(token).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+// Las rutas /admin usan su propia credencial, ADMIN_TOKEN: el JWT de /api lo
+// reciben también los navegadores del Frame. Sin ADMIN_TOKEN no hay API de admin
+pub async fn verify_admin(req: &HttpRequest) -> Result<Claims, HttpResponse> {
+    let admin_token = match env::var("ADMIN_TOKEN") {
+        Ok(token) if !token.is_empty() => token,
+        _ => return Err(HttpResponse::Forbidden().body("Admin API is disabled")),
+    };
+
+    let token = req.headers()
+        .get("Authorization")
+        .and_then(|header| header.to_str().ok())
+        .and_then(|header| header.strip_prefix("Bearer "));
+    match token {
+        Some(token) if verify_secret(&admin_token, token) => Ok(Claims { sub: "admin".to_string(), exp: 0, iat: 0 }),
+        Some(_) => Err(HttpResponse::Forbidden().body("Invalid admin token")),
+        None => Err(HttpResponse::Unauthorized().body("No authorization header")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::http::StatusCode;
+    use actix_web::test::TestRequest;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+
+    const JWT_SECRET: &str = "test-jwt-secret";
+    const ADMIN_TOKEN: &str = "test-admin-token";
+
+    // Token como el que /login entrega al Frame
+    fn frame_token() -> String {
+        env::set_var("JWT_SECRET", JWT_SECRET);
+        env::set_var("ADMIN_TOKEN", ADMIN_TOKEN);
+        let now = chrono::Utc::now().timestamp() as usize;
+        let claims = Claims { sub: "frame".to_string(), exp: now + 3600, iat: now };
+        encode(&Header::default(), &claims, &EncodingKey::from_secret(JWT_SECRET.as_bytes())).unwrap()
+    }
+
+    fn bearer(token: &str) -> HttpRequest {
+        TestRequest::default()
+            .insert_header(("Authorization", format!("Bearer {}", token)))
+            .to_http_request()
+    }
+
+    #[actix_web::test]
+    async fn frame_token_is_valid_for_api() {
+        let token = frame_token();
+        assert_eq!(verify_token(&bearer(&token)).await.unwrap().sub, "frame");
+    }
+
+    #[actix_web::test]
+    async fn frame_token_is_rejected_by_admin() {
+        let token = frame_token();
+        let response = verify_admin(&bearer(&token)).await.unwrap_err();
+        assert!(matches!(response.status(), StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN));
+    }
+
+    #[actix_web::test]
+    async fn admin_token_is_accepted_by_admin() {
+        frame_token();
+        assert!(verify_admin(&bearer(ADMIN_TOKEN)).await.is_ok());
+    }
+
+    #[actix_web::test]
+    async fn admin_requires_authorization_header() {
+        frame_token();
+        let response = verify_admin(&TestRequest::default().to_http_request()).await.unwrap_err();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn verify_secret_compares_whole_secret() {
+        assert!(verify_secret("secret", "secret"));
+        assert!(!verify_secret("secret", "secreT"));
+        assert!(!verify_secret("secret", "secret2"));
+        assert!(!verify_secret("secret", ""));
+    }
+}
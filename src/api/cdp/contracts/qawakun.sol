@@ -37,6 +37,21 @@ contract Qawakun is ERC721Enumerable, Ownable, Initializable, UUPSUpgradeable {
         nextId++;
     }
 
+    // Mintea directamente en la wallet del usuario, en una sola transacción
+    function mintTo(address _to, string memory _userInfo, string memory _imageUrl) public onlyOwner {
+        require(_to != address(0), "Invalid recipient");
+        uint256 currentId = nextId;
+        qawakuns[currentId] = QawakunData({
+            id: currentId,
+            creationDate: block.timestamp,
+            userInfo: _userInfo,
+            representedAddress: _to,
+            imageUrl: _imageUrl
+        });
+        _mint(_to, currentId);
+        nextId++;
+    }
+
     function updateRepresentedAddress(uint256 _id, address _newAddress) public onlyOwner {
         require(ownerOf(_id) != address(0), "Qawakun does not exist");
         qawakuns[_id].representedAddress = _newAddress;
@@ -64,5 +79,11 @@ contract Qawakun is ERC721Enumerable, Ownable, Initializable, UUPSUpgradeable {
         return string(bstr);
     }
     
+    // Versión de la implementación; la 2 añade mintTo. Sin variables de estado nuevas,
+    // el layout de almacenamiento del proxy no cambia
+    function version() public pure returns (uint256) {
+        return 2;
+    }
+
     function _authorizeUpgrade(address newImplementation) internal override onlyOwner {}
 }
@@ -4,9 +4,11 @@ use ethers::{
     providers::{Http, Provider},
     signers::{LocalWallet, MnemonicBuilder},
 };
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::env;
 use ethers::utils::keccak256;
+use ethers::types::transaction::eip2718::TypedTransaction;
 use ethers::signers::coins_bip39::English;
 use aes_gcm::{
     aead::{Aead, KeyInit},
@@ -17,6 +19,44 @@ use reqwest::multipart::{Form, Part};
 use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 use std::fs;
 use crate::openai_methods::get_image::generate_image;
+use crate::metrics;
+use crate::telemetry;
+use tokio::sync::{Mutex, MutexGuard};
+use tracing::{debug, info, warn};
+
+// Primera versión de la implementación del contrato con mintTo
+pub const MINT_TO_VERSION: u64 = 2;
+// Espera máxima por un recibo; una transacción más lenta se vuelve a esperar en el
+// siguiente intento
+pub const RECEIPT_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(3 * 60);
+
+// Gateway de Pinata donde se sirven las imágenes y metadatos
+pub const PINATA_GATEWAY: &str = "https://beige-fit-hedgehog-619.mypinata.cloud/ipfs/";
+
+// Sube una imagen PNG a Pinata y devuelve su URL en el gateway
+pub async fn pin_image(image_bytes: Vec<u8>, file_name: &str) -> Result<String> {
+    let jwt = env::var("JWT_SECRET_PINATA")
+        .map_err(|_| anyhow::anyhow!("JWT_SECRET_PINATA not configured"))?;
+
+    let client = reqwest::Client::new();
+    let form = Form::new()
+        .text("pinataOptions", r#"{"cidVersion": 1}"#)
+        .part("file", Part::bytes(image_bytes)
+            .file_name(file_name.to_string())
+            .mime_str("image/png")?);
+
+    let response = telemetry::propagate(client.post("https://api.pinata.cloud/pinning/pinFileToIPFS"))
+        .header("Authorization", format!("Bearer {}", jwt))
+        .multipart(form)
+        .send()
+        .await?;
+
+    let json: serde_json::Value = response.json().await?;
+    let ipfs_hash = json["IpfsHash"].as_str()
+        .ok_or_else(|| anyhow::anyhow!("No IPFS hash in response"))?;
+
+    Ok(format!("{}{}", PINATA_GATEWAY, ipfs_hash))
+}
 
 // Generar los bindings para el contrato
 abigen!(
@@ -26,10 +66,43 @@ abigen!(
 );
 
 pub struct NftManager {
-    #[allow(dead_code)]
     provider: Arc<Provider<Http>>,
     wallet: LocalWallet,
     contract: QawakunContract<SignerMiddleware<Arc<Provider<Http>>, LocalWallet>>,
+    // El proxy apunta a una implementación con mintTo; si no, se mintea y transfiere
+    mint_to: AtomicBool,
+    signer_lock: Mutex<()>,
+}
+
+// Lock de la wallet del backend. Toda transacción se firma con él tomado y se suelta
+// después de enviarla, así ninguna otra coge el nonce de una firmada (y guardada)
+// que todavía no ha salido
+pub struct SignerLock<'a> {
+    _guard: MutexGuard<'a, ()>,
+}
+
+fn to_u64(value: U256, what: &str) -> Result<u64> {
+    if value > U256::from(u64::MAX) {
+        return Err(anyhow::anyhow!("{} {} does not fit in u64", what, value));
+    }
+    Ok(value.as_u64())
+}
+
+// Transacción firmada y todavía sin enviar
+#[derive(Debug, Clone)]
+pub struct SignedTx {
+    pub hash: H256,
+    pub nonce: u64,
+    pub raw: Bytes,
+}
+
+// NFT que quedó en la wallet del backend con el flujo de mint y transferencia
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct HeldToken {
+    pub token_id: u64,
+    // Wallet del usuario según los datos cifrados del token, si se pueden leer
+    pub wallet: Option<String>,
+    pub fid: Option<u64>,
 }
 
 impl NftManager {
@@ -45,7 +118,7 @@ impl NftManager {
 
         let provider = Provider::<Http>::try_from(rpc_url)?;
         let chain_id = provider.get_chainid().await?;
-        println!("🔗 Chain ID: {}", chain_id);
+        info!(%chain_id, "connected to chain");
         
         let provider = Arc::new(provider);
 
@@ -55,7 +128,7 @@ impl NftManager {
             .build()?
             .with_chain_id(chain_id.as_u64());
 
-        println!("👛 Wallet address: {}", wallet.address());
+        info!(wallet = %telemetry::redact_wallet(&format!("{:?}", wallet.address())), "NFT signer loaded");
 
         let middleware = SignerMiddleware::new(
             provider.clone(),
@@ -75,13 +148,72 @@ impl NftManager {
 
         // Verificar balance de ETH
         let balance = provider.get_balance(wallet.address(), None).await?;
-        println!("💰 Balance de ETH: {} wei", balance);
+        info!(balance_wei = %balance, "signer ETH balance");
 
-        Ok(Self {
+        let manager = Self {
             provider,
             wallet,
             contract,
-        })
+            mint_to: AtomicBool::new(false),
+            signer_lock: Mutex::new(()),
+        };
+        manager.detect_mint_to().await;
+        Ok(manager)
+    }
+
+    // Versión de la implementación detrás de una dirección. Las anteriores a mintTo no
+    // tienen version() y la llamada revierte
+    async fn implementation_version(&self, address: Address) -> Result<u64> {
+        let contract = QawakunContract::new(address, self.contract.client());
+        let version = contract.version().call().await?;
+        to_u64(version, "version")
+    }
+
+    async fn detect_mint_to(&self) -> bool {
+        let supported = match self.implementation_version(self.contract.address()).await {
+            Ok(version) => version >= MINT_TO_VERSION,
+            Err(e) => {
+                debug!(error = %e, "contract has no version(), assuming implementation without mintTo");
+                false
+            },
+        };
+        if !supported {
+            warn!("NFT contract has no mintTo, claims will mint to the signer and transfer");
+        }
+        self.mint_to.store(supported, Ordering::Relaxed);
+        supported
+    }
+
+    pub fn supports_mint_to(&self) -> bool {
+        self.mint_to.load(Ordering::Relaxed)
+    }
+
+    // Apunta el proxy UUPS a una nueva implementación. Antes comprueba que la
+    // implementación es de una versión con mintTo para no dejar el proxy en algo ajeno
+    #[tracing::instrument(skip_all, fields(correlation_id = %telemetry::correlation_label()))]
+    pub async fn upgrade_implementation(&self, implementation: Address) -> Result<TransactionReceipt> {
+        let code = self.provider.get_code(implementation, None).await?;
+        if code.is_empty() {
+            return Err(anyhow::anyhow!("No contract deployed at {:?}", implementation));
+        }
+        let version = self.implementation_version(implementation).await
+            .map_err(|e| anyhow::anyhow!("Implementation has no version(): {}", e))?;
+        if version < MINT_TO_VERSION {
+            return Err(anyhow::anyhow!("Implementation version {} has no mintTo", version));
+        }
+
+        info!(?implementation, version, "upgrading NFT contract implementation");
+        let tx = self.contract.upgrade_to_and_call(implementation, Bytes::new());
+        let tx_hash = self.sign_and_send(tx.tx).await?;
+        let receipt = self.wait_for_receipt(tx_hash).await?
+            .ok_or_else(|| anyhow::anyhow!("Upgrade transaction dropped"))?;
+        if receipt.status != Some(U64::from(1)) {
+            return Err(anyhow::anyhow!("Upgrade transaction reverted: {:?}", receipt.transaction_hash));
+        }
+
+        info!(tx_hash = ?receipt.transaction_hash, "NFT contract upgraded");
+        self.detect_mint_to().await;
+        Ok(receipt)
     }
 
     // Hacer privados los métodos que son internos
@@ -96,8 +228,7 @@ impl NftManager {
                 .file_name(file_name.to_string())
                 .mime_str("application/json")?);
 
-        let response = client
-            .post("https://api.pinata.cloud/pinning/pinFileToIPFS")
+        let response = telemetry::propagate(client.post("https://api.pinata.cloud/pinning/pinFileToIPFS"))
             .header("Authorization", format!("Bearer {}", jwt))
             .multipart(form)
             .send()
@@ -108,44 +239,22 @@ impl NftManager {
             .ok_or_else(|| anyhow::anyhow!("No IPFS hash in response"))?;
 
         // Construir la URL completa de Pinata
-        Ok(format!("https://beige-fit-hedgehog-619.mypinata.cloud/ipfs/{}", ipfs_hash))
+        Ok(format!("{}{}", PINATA_GATEWAY, ipfs_hash))
     }
 
     async fn generate_and_upload_image(&self, context: &str) -> Result<String> {
-        println!("🎨 Generando imagen con IA...");
+        info!("generating AI image");
         let image_data = generate_image(context).await?;
         
         let image_bytes = BASE64.decode(image_data)?;
         
-        let jwt = env::var("JWT_SECRET_PINATA")
-            .expect("JWT_SECRET_PINATA debe estar configurado en .env");
-
-        let client = reqwest::Client::new();
-        let form = Form::new()
-            .text("pinataOptions", r#"{"cidVersion": 1}"#)
-            .part("file", Part::bytes(image_bytes)
-                .file_name("nft_image.png")
-                .mime_str("image/png")?);
-
-        let response = client
-            .post("https://api.pinata.cloud/pinning/pinFileToIPFS")
-            .header("Authorization", format!("Bearer {}", jwt))
-            .multipart(form)
-            .send()
-            .await?;
-
-        let json: serde_json::Value = response.json().await?;
-        let ipfs_hash = json["IpfsHash"].as_str()
-            .ok_or_else(|| anyhow::anyhow!("No IPFS hash in response"))?;
-
-        // Construir la URL completa de Pinata
-        Ok(format!("https://beige-fit-hedgehog-619.mypinata.cloud/ipfs/{}", ipfs_hash))
+        pin_image(image_bytes, "nft_image.png").await
     }
 
     // Eliminar o marcar como deprecated los métodos no usados
-    #[deprecated(note = "Use mint_nft_with_encrypted_data instead")]
+    #[deprecated(note = "Use send_mint instead")]
     pub async fn mint_nft(&self, to: Address, metadata_uri: String) -> Result<TransactionReceipt> {
-        println!("🔄 Intentando mintear NFT para {} con URI: {}", to, metadata_uri);
+        info!(to = %telemetry::redact_wallet(&format!("{:?}", to)), "minting NFT from metadata URI");
         
         // Extraer la información encriptada y la URL de la imagen de los metadatos
         let metadata_response = reqwest::get(&metadata_uri).await?;
@@ -157,18 +266,15 @@ impl NftManager {
                 metadata.image,                // _imageUrl
             ))?;
         
-        println!("📬 Enviando transacción...");
         let pending_tx = tx.send().await?;
-        
-        println!("⏳ Esperando confirmación...");
+        debug!(tx_hash = ?*pending_tx, "mint transaction sent");
         let receipt = pending_tx.await?
             .ok_or_else(|| anyhow::anyhow!("Transaction failed"))?;
         
-        println!("✅ Transacción completada: {:?}", receipt.transaction_hash);
+        info!(tx_hash = ?receipt.transaction_hash, "mint transaction confirmed");
         Ok(receipt)
     }
 
-    #[allow(dead_code)]
     pub async fn get_owner(&self, token_id: U256) -> Result<Address> {
         let owner: Address = self.contract
             .method("ownerOf", token_id)?
@@ -230,126 +336,196 @@ impl NftManager {
         Ok(hex::encode(final_data))
     }
 
-    pub async fn mint_nft_with_encrypted_data(
-        &self,
-        to: Address,
-        user_data: UserData,
-    ) -> Result<TransactionReceipt> {
-        // Verificar si el usuario ya tiene NFTs
-        let balance = self.get_balance(to).await?;
-        if !balance.is_zero() {
-            println!("👛 Usuario ya tiene {} NFTs", balance);
-            return Err(anyhow::anyhow!("User already has an NFT"));
-        }
-
+    // Comprueba el gas, cifra los datos y sube la imagen a Pinata: los argumentos
+    // userInfo e imageUrl de mint y mintTo
+    async fn prepare_mint(&self, user_data: &UserData) -> Result<(String, String)> {
         // Verificar que la wallet del contrato tiene fondos para el gas
         let contract_balance = self.provider.get_balance(self.wallet.address(), None).await?;
         if contract_balance.is_zero() {
             return Err(anyhow::anyhow!("La wallet no tiene fondos para pagar gas"));
         }
 
-        println!("🔐 Encriptando datos de usuario...");
-        let encrypted_data = self.encrypt_user_data(&user_data).await?;
-        
+        let encrypted_data = self.encrypt_user_data(user_data).await?;
+
         // Leer y subir la imagen estática a Pinata
-        println!("📤 Subiendo imagen estática a Pinata...");
+        debug!("uploading static image to Pinata");
         let image_bytes = fs::read("src/img/image09.png")?;
-        
-        let jwt = env::var("JWT_SECRET_PINATA")
-            .expect("JWT_SECRET_PINATA debe estar configurado en .env");
+        let image_uri = pin_image(image_bytes, "nft_image.png").await?;
+        info!(image_uri = %image_uri, "image uploaded to Pinata");
 
-        let client = reqwest::Client::new();
-        let form = Form::new()
-            .text("pinataOptions", r#"{"cidVersion": 1}"#)
-            .part("file", Part::bytes(image_bytes)
-                .file_name("nft_image.png")
-                .mime_str("image/png")?);
+        Ok((encrypted_data, image_uri))
+    }
 
-        let response = client
-            .post("https://api.pinata.cloud/pinning/pinFileToIPFS")
-            .header("Authorization", format!("Bearer {}", jwt))
-            .multipart(form)
-            .send()
+    pub async fn lock_signer(&self) -> SignerLock<'_> {
+        SignerLock { _guard: self.signer_lock.lock().await }
+    }
+
+    // Mint a la wallet del usuario en una sola transacción, con representedAddress = to.
+    // Solo la firma: el claim la guarda antes de enviarla con `broadcast`
+    #[tracing::instrument(skip_all, fields(correlation_id = %telemetry::correlation_label()))]
+    pub async fn sign_mint_to(&self, lock: &SignerLock<'_>, to: Address, user_data: &UserData) -> Result<SignedTx> {
+        let (encrypted_data, image_uri) = self.prepare_mint(user_data).await?;
+        let tx = self.contract.mint_to(to, encrypted_data, image_uri);
+        let signed = self.sign(lock, tx.tx).await?;
+        info!(tx_hash = ?signed.hash, nonce = signed.nonce, to = %telemetry::redact_wallet(&format!("{:?}", to)), "mintTo transaction signed");
+        Ok(signed)
+    }
+
+    // Mint a la wallet del backend, para implementaciones sin mintTo; después hace
+    // falta send_transfer. Solo la firma, como sign_mint_to
+    #[tracing::instrument(skip_all, fields(correlation_id = %telemetry::correlation_label()))]
+    pub async fn sign_mint(&self, lock: &SignerLock<'_>, user_data: &UserData) -> Result<SignedTx> {
+        let (encrypted_data, image_uri) = self.prepare_mint(user_data).await?;
+        let tx = self.contract.mint(encrypted_data, image_uri);
+        let signed = self.sign(lock, tx.tx).await?;
+        info!(tx_hash = ?signed.hash, nonce = signed.nonce, "mint transaction signed");
+        Ok(signed)
+    }
+
+    // Rellena nonce (contando las pendientes), gas y precio, y firma con la wallet
+    async fn sign(&self, _lock: &SignerLock<'_>, mut tx: TypedTransaction) -> Result<SignedTx> {
+        let client = self.contract.client();
+        client.fill_transaction(&mut tx, Some(BlockNumber::Pending.into())).await?;
+        debug!(gas = ?tx.gas(), "transaction gas estimate");
+        let nonce = tx.nonce()
+            .copied()
+            .ok_or_else(|| anyhow::anyhow!("transaction has no nonce"))?;
+        let nonce = to_u64(nonce, "nonce")?;
+
+        let signature = self.wallet.sign_transaction(&tx).await?;
+        Ok(SignedTx {
+            hash: tx.hash(&signature),
+            nonce,
+            raw: tx.rlp_signed(&signature),
+        })
+    }
+
+    // Envía una transacción ya firmada. Reenviarla es inocuo: tiene el mismo hash
+    #[tracing::instrument(skip_all, fields(correlation_id = %telemetry::correlation_label()))]
+    pub async fn broadcast(&self, raw: Bytes) -> Result<H256> {
+        let pending = self.provider.send_raw_transaction(raw).await?;
+        let tx_hash = pending.tx_hash();
+        info!(?tx_hash, "signed transaction sent");
+        Ok(tx_hash)
+    }
+
+    // Para las transacciones que no se guardan antes de enviarlas: firma y envía con
+    // el lock tomado
+    async fn sign_and_send(&self, tx: TypedTransaction) -> Result<H256> {
+        let lock = self.lock_signer().await;
+        let signed = self.sign(&lock, tx).await?;
+        self.broadcast(signed.raw).await
+    }
+
+    // Siguiente nonce de la wallet del backend según el último bloque: cualquier
+    // transacción con un nonce menor ya está minada
+    pub async fn confirmed_nonce(&self) -> Result<u64> {
+        let nonce = self.provider
+            .get_transaction_count(self.wallet.address(), Some(BlockNumber::Latest.into()))
             .await?;
+        to_u64(nonce, "nonce")
+    }
 
-        let json: serde_json::Value = response.json().await?;
-        let ipfs_hash = json["IpfsHash"].as_str()
-            .ok_or_else(|| anyhow::anyhow!("No IPFS hash in response"))?;
+    // Recibo de una transacción, sin esperar si todavía no está minada
+    pub async fn receipt(&self, tx_hash: H256) -> Result<Option<TransactionReceipt>> {
+        Ok(self.provider.get_transaction_receipt(tx_hash).await?)
+    }
 
-        let image_uri = format!("https://beige-fit-hedgehog-619.mypinata.cloud/ipfs/{}", ipfs_hash);
-        println!("✅ Imagen subida a Pinata: {}", image_uri);
-        
-        println!("🚀 Iniciando proceso de minteo con datos:");
-        println!("  🖼️ URL de imagen: {}", image_uri);
-        
-        // Mintear el NFT
-        let tx = self.contract
-            .method::<_, ()>("mint", (
-                encrypted_data.clone(),
-                image_uri.clone(),
-            ))?;
+    // Transferencia del token desde la wallet del backend. Solo la firma, como el mint
+    #[tracing::instrument(skip_all, fields(correlation_id = %telemetry::correlation_label()))]
+    pub async fn sign_transfer(&self, lock: &SignerLock<'_>, to: Address, token_id: u64) -> Result<SignedTx> {
+        let tx = self.contract.transfer_from(self.wallet.address(), to, U256::from(token_id));
+        let signed = self.sign(lock, tx.tx).await?;
+        info!(tx_hash = ?signed.hash, nonce = signed.nonce, token_id, to = %telemetry::redact_wallet(&format!("{:?}", to)), "transfer transaction signed");
+        Ok(signed)
+    }
 
-        let gas_estimate = tx.estimate_gas().await?;
-        println!("⛽ Estimación de gas para mint: {}", gas_estimate);
+    // Dirección que representa el token según el contrato
+    pub async fn represented_address(&self, token_id: u64) -> Result<Address> {
+        let (_, _, _, represented, _) = self.contract.qawakuns(U256::from(token_id)).call().await?;
+        Ok(represented)
+    }
 
-        let tx = tx.gas(gas_estimate);
-        
-        println!("📬 Enviando transacción de mint desde {}", self.wallet.address());
-        let pending_tx = tx.send().await?;
-        
-        println!("⏳ Esperando confirmación del mint...");
-        let mint_receipt = pending_tx.await?
-            .ok_or_else(|| anyhow::anyhow!("Mint transaction failed"))?;
+    // Apunta representedAddress a la wallet que recibió el token: el mint antiguo la
+    // deja en la wallet del backend. Espera el recibo
+    #[tracing::instrument(skip_all, fields(correlation_id = %telemetry::correlation_label()))]
+    pub async fn update_represented_address(&self, token_id: u64, to: Address) -> Result<()> {
+        let tx = self.contract.update_represented_address(U256::from(token_id), to);
+        let tx_hash = self.sign_and_send(tx.tx).await?;
+        match self.wait_for_receipt(tx_hash).await? {
+            Some(receipt) if receipt.status == Some(U64::from(1)) => {
+                info!(?tx_hash, token_id, "representedAddress updated");
+                Ok(())
+            },
+            Some(_) => Err(anyhow::anyhow!("updateRepresentedAddress transaction {:?} reverted", tx_hash)),
+            None => Err(anyhow::anyhow!("updateRepresentedAddress transaction {:?} dropped", tx_hash)),
+        }
+    }
 
-        println!("✅ NFT minteado exitosamente: {:?}", mint_receipt.transaction_hash);
+    // Espera el recibo de una transacción ya enviada, como mucho RECEIPT_TIMEOUT. None
+    // si el nodo la descartó; un error (del RPC o por tiempo) se puede volver a esperar
+    #[tracing::instrument(skip_all, fields(correlation_id = %telemetry::correlation_label()))]
+    pub async fn wait_for_receipt(&self, tx_hash: H256) -> Result<Option<TransactionReceipt>> {
+        let pending = PendingTransaction::new(tx_hash, self.provider.as_ref());
+        match tokio::time::timeout(RECEIPT_TIMEOUT, pending).await {
+            Ok(receipt) => Ok(receipt?),
+            Err(_) => Err(anyhow::anyhow!("transaction {:?} still pending after {}s", tx_hash, RECEIPT_TIMEOUT.as_secs())),
+        }
+    }
 
-        // Obtener el token ID del evento Transfer
-        let token_id = mint_receipt
+    // Token ID y destinatario del evento Transfer del mint (from = 0x0) emitido por
+    // este contrato
+    pub fn minted_token(&self, receipt: &TransactionReceipt) -> Result<(u64, Address)> {
+        let transfer_topic = H256::from(keccak256("Transfer(address,address,uint256)"));
+        let (token_id, to) = receipt
             .logs
             .iter()
-            .find(|log| log.topics[0] == H256::from(keccak256("Transfer(address,address,uint256)")))
-            .and_then(|log| {
-                if log.topics.len() >= 4 {
-                    Some(U256::from_big_endian(&log.topics[3].as_bytes()))
-                } else {
-                    None
-                }
-            })
-            .ok_or_else(|| anyhow::anyhow!("No se pudo obtener el token ID"))?;
-
-        println!("🎫 Token ID minteado: {}", token_id);
-
-        // Esperar un poco antes de la transferencia
-        tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-
-        // Transferir el NFT al usuario
-        println!("🔄 Transfiriendo NFT {} a {}...", token_id, to);
-        let transfer_tx = self.contract
-            .method::<_, ()>("transferFrom", (
-                self.wallet.address(),  // from
-                to,                     // to
-                token_id,               // tokenId
-            ))?;
+            .filter(|log| log.address == self.contract.address() && log.topics.len() == 4)
+            .find(|log| log.topics[0] == transfer_topic && log.topics[1] == H256::zero())
+            .map(|log| (U256::from_big_endian(log.topics[3].as_bytes()), Address::from(log.topics[2])))
+            .ok_or_else(|| anyhow::anyhow!("No mint Transfer event in receipt {:?}", receipt.transaction_hash))?;
 
-        let transfer_gas = transfer_tx.estimate_gas().await?;
-        println!("⛽ Estimación de gas para transferencia: {}", transfer_gas);
+        Ok((to_u64(token_id, "Token ID")?, to))
+    }
 
-        let transfer_tx = transfer_tx.gas(transfer_gas);
-        
-        println!("📬 Enviando transacción de transferencia...");
-        let pending_transfer = transfer_tx.send().await?;
-        
-        println!("⏳ Esperando confirmación de la transferencia...");
-        let transfer_receipt = pending_transfer.await?
-            .ok_or_else(|| anyhow::anyhow!("Transfer transaction failed"))?;
+    // NFTs en la wallet del backend. Con mintTo no debería haber ninguno: son del
+    // flujo antiguo, minteados y sin transferir
+    #[tracing::instrument(skip_all, fields(correlation_id = %telemetry::correlation_label()))]
+    pub async fn held_tokens(&self) -> Result<Vec<HeldToken>> {
+        let signer = self.wallet.address();
+        let balance = self.get_balance(signer).await?;
+
+        let mut tokens = Vec::new();
+        for index in 0..to_u64(balance, "balance")? {
+            let token_id = self.contract.token_of_owner_by_index(signer, U256::from(index)).call().await?;
+            let (_, _, user_info, _, _) = self.contract.qawakuns(token_id).call().await?;
+
+            let user_data = self.decrypt_user_data(&user_info).await.ok();
+            tokens.push(HeldToken {
+                token_id: to_u64(token_id, "Token ID")?,
+                wallet: user_data.as_ref().map(|data| data.wallet_address.clone()),
+                fid: user_data
+                    .and_then(|data| data.additional_data)
+                    .and_then(|extra| extra["fid"].as_u64()),
+            });
+        }
+        Ok(tokens)
+    }
 
-        println!("✅ NFT transferido exitosamente: {:?}", transfer_receipt.transaction_hash);
+    pub fn record_mint(&self, receipt: &TransactionReceipt) -> bool {
+        let success = receipt.status == Some(U64::from(1));
+        metrics::NFT_MINTS.with_label_values(&[if success { "success" } else { "failed" }]).inc();
+        metrics::observe_gas("mint", receipt.gas_used);
+        success
+    }
 
-        // Devolver el recibo de la transferencia
-        Ok(transfer_receipt)
+    pub fn record_transfer(&self, receipt: &TransactionReceipt) -> bool {
+        let success = receipt.status == Some(U64::from(1));
+        metrics::NFT_TRANSFERS.with_label_values(&[if success { "success" } else { "failed" }]).inc();
+        metrics::observe_gas("transfer", receipt.gas_used);
+        success
     }
 
-    #[allow(dead_code)]
     pub async fn decrypt_user_data(&self, encrypted_data: &str) -> Result<UserData> {
         let encrypted_bytes = hex::decode(encrypted_data)?;
         if encrypted_bytes.len() < 12 {
@@ -368,6 +544,36 @@ impl NftManager {
         Ok(user_data)
     }
 
+    pub fn signer_address(&self) -> Address {
+        self.wallet.address()
+    }
+
+    pub async fn signer_balance(&self) -> Result<U256> {
+        let balance = self.provider.get_balance(self.wallet.address(), None).await?;
+        Ok(balance)
+    }
+
+    pub async fn chain_status(&self) -> Result<(U256, U64)> {
+        let chain_id = self.provider.get_chainid().await?;
+        let block_number = self.provider.get_block_number().await?;
+        Ok((chain_id, block_number))
+    }
+
+    pub async fn check_pinata_auth(&self) -> Result<()> {
+        let jwt = env::var("JWT_SECRET_PINATA")?;
+
+        let response = telemetry::propagate(reqwest::Client::new().get("https://api.pinata.cloud/data/testAuthentication"))
+            .header("Authorization", format!("Bearer {}", jwt))
+            .send()
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("Pinata auth failed: {}", response.status()));
+        }
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all, fields(correlation_id = %telemetry::correlation_label()))]
     pub async fn get_balance(&self, address: Address) -> Result<U256> {
         let balance: U256 = self.contract
             .method("balanceOf", address)?
@@ -2,15 +2,50 @@ use actix_web::{web, HttpResponse, Responder, HttpRequest};
 use serde::{Deserialize, Serialize};
 use std::env;
 use jsonwebtoken::{encode, EncodingKey, Header};
-use chrono::{Utc, Duration};
-use crate::openai_methods::get_text::handle_conversation;
-use crate::api::auth::{verify_token, Claims};
+use chrono::{Utc, Duration, Datelike};
+use crate::social::{AgentPipeline, Author, Batch, InboundMessage, Outcome, SocialChannel};
+use async_trait::async_trait;
+use crate::api::auth::{verify_admin, verify_token, Claims};
 use super::nft_claim::{handle_nft_claim_post, handle_nft_claim_get};
+use super::health::{health_live, health_ready, health_workers};
+use super::jobs::handle_job_get;
+use super::webhooks::{handle_farcaster_webhook, handle_frame_webhook, handle_telegram_webhook};
+use super::admin::{
+    handle_controls_get,
+    handle_control_pause,
+    handle_control_resume,
+    handle_worker_poll,
+    handle_runtime_config,
+    handle_farcaster_cast,
+    handle_lore_calendar,
+    handle_lore_approve,
+    handle_lore_reject,
+    handle_twitter_dead_letters,
+    handle_twitter_dead_letter_retry,
+    handle_twitter_quota,
+    handle_twitter_post,
+    handle_webhooks_get,
+    handle_webhook_create,
+    handle_webhook_delete,
+    handle_webhook_dead_letters,
+    handle_webhook_dead_letter_retry,
+    handle_nft_contract_get,
+    handle_nft_upgrade,
+    handle_nft_stranded,
+    handle_nft_stranded_deliver,
+};
+use crate::events::{self, Event};
+use crate::metrics::{self, metrics_endpoint};
+use crate::telemetry;
+use tracing::{debug, error, info, warn};
 use super::proposals::{
     handle_proposal_by_wallet_get,
     handle_proposal_status_update,
+    notify_monthly_winners,
+    notify_status_change,
     ProposalManager,
     Proposal,
+    ProposalStatus,
 };
 use redis::AsyncCommands;
 use ethers::types::U256;
@@ -64,101 +99,61 @@ pub async fn protected_api(
     }
 }
 
-async fn process_message(data: serde_json::Value) -> HttpResponse {
-    let api_key = match env::var("OPENAI_API_KEY") {
-        Ok(key) => key,
-        Err(e) => {
-            println!("❌ OPENAI_API_KEY: {}", e);
-            return HttpResponse::InternalServerError().body("OPENAI_API_KEY not found");
-        }
-    };
+// Chat del Frame: canal privado cuya respuesta vuelve en el cuerpo HTTP. El
+// historial mantiene la clave conversation:{author} de antes del pipeline
+struct FrameChannel;
+
+#[async_trait]
+impl SocialChannel for FrameChannel {
+    type Event = ();
+
+    fn name(&self) -> &'static str {
+        "api"
+    }
+
+    fn channel_prompt(&self) -> String {
+        "Stay in character and maintain narrative consistency.".to_string()
+    }
+
+    fn is_public(&self) -> bool {
+        false
+    }
+
+    fn history_key(&self, author: &Author) -> String {
+        format!("conversation:{}", author.id)
+    }
+
+    // Los mensajes llegan por la API, no hay nada que leer
+    async fn fetch(&self) -> anyhow::Result<Batch<()>> {
+        Ok(Batch { messages: Vec::new(), cursor: None })
+    }
+
+    async fn commit(&self, _cursor: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+
+    async fn send_reply(&self, _message: &InboundMessage<()>, _text: &str) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
 
+async fn process_message(data: serde_json::Value) -> HttpResponse {
     let redis_url = match env::var("REDIS_URL") {
         Ok(url) => url,
         Err(e) => {
-            println!("❌ REDIS_URL: {}", e);
+            error!(error = %e, "REDIS_URL not configured");
             return HttpResponse::InternalServerError().body("REDIS_URL not found");
         }
     };
 
-    println!("🔌 Connected: {}", redis_url);
     let redis_client = match redis::Client::open(redis_url) {
         Ok(client) => client,
         Err(e) => {
-            println!("❌ Redis connection: {}", e);
-            return HttpResponse::InternalServerError().body(format!("Redis error: {}", e));
-        }
-    };
-
-    let mut con = match redis_client.get_async_connection().await {
-        Ok(con) => {
-            println!("✅ Conexión Redis establecida");
-            con
-        },
-        Err(e) => {
-            println!("❌ Error de conexión Redis: {:?}", e);
+            error!(error = %e, "invalid Redis URL");
             return HttpResponse::InternalServerError().body(format!("Redis error: {}", e));
         }
     };
 
-    let context_content = match con.exists::<_, bool>("context-text").await {
-        Ok(true) => {
-            match con.get::<_, String>("context-text").await {
-                Ok(content) => {
-                    format!(
-                        "You are Qawakun, a narrative guide in this interactive experience.\n{}\n\
-                         Stay in character and maintain narrative consistency.",
-                        content
-                    )
-                },
-                Err(e) => {
-                    println!("❌ Error leyendo contexto de Redis: {}", e);
-                    println!("⚠️ Cambiando a archivo context.md como respaldo");
-                    match std::fs::read_to_string("context.md") {
-                        Ok(content) => format!(
-                            "You are Qawakun, a narrative guide in this interactive experience.\n{}\n\
-                             Stay in character and maintain narrative consistency.",
-                            content
-                        ),
-                        Err(e) => {
-                            println!("❌ Error leyendo context.md: {}", e);
-                            return HttpResponse::InternalServerError().body("Narrative context error");
-                        }
-                    }
-                }
-            }
-        },
-        Ok(false) => {
-            println!("⚠️ Usando archivo context.md (clave context-text no existe en Redis)");
-            match std::fs::read_to_string("context.md") {
-                Ok(content) => format!(
-                    "You are Qawakun, a narrative guide in this interactive experience.\n{}\n\
-                     Stay in character and maintain narrative consistency.",
-                    content
-                ),
-                Err(e) => {
-                    println!("❌ Error leyendo context.md: {}", e);
-                    return HttpResponse::InternalServerError().body("Narrative context error");
-                }
-            }
-        },
-        Err(e) => {
-            println!("❌ Error verificando existencia en Redis: {}", e);
-            println!("⚠️ Cambiando a archivo context.md como respaldo");
-            match std::fs::read_to_string("context.md") {
-                Ok(content) => format!(
-                    "You are Qawakun, a narrative guide in this interactive experience.\n{}\n\
-                     Stay in character and maintain narrative consistency.",
-                    content
-                ),
-                Err(e) => {
-                    println!("❌ Error leyendo context.md: {}", e);
-                    return HttpResponse::InternalServerError().body("Narrative context error");
-                }
-            }
-        }
-    };
-
     let user_content = data.get("message").and_then(|c| c.as_str()).unwrap_or("").to_string();
     let user_author = data.get("author").and_then(|c| c.as_str()).unwrap_or("").to_string();
 
@@ -166,64 +161,57 @@ async fn process_message(data: serde_json::Value) -> HttpResponse {
         return HttpResponse::BadRequest().body("Empty message");
     }
 
-    println!("🤖 Processing message from {}", user_author);
-    match handle_conversation(&redis_client, &api_key, &user_author, &context_content, &user_content).await {
-        Ok(response) => {
-            let message = response["choices"][0]["message"]["content"].as_str().unwrap_or("");
-            println!("✅ Response sent");
-            HttpResponse::Ok().json(message)
+    let message = InboundMessage {
+        id: Utc::now().timestamp_millis().to_string(),
+        author: Author { id: user_author, handle: None },
+        text: user_content,
+        event: (),
+    };
+
+    info!(channel = "api", "processing message");
+    match AgentPipeline::new(redis_client).respond(&FrameChannel, &message).await {
+        Ok(Outcome::Replied(reply)) => {
+            info!(channel = "api", "response sent");
+            HttpResponse::Ok().json(reply)
         },
+        Ok(Outcome::Spam) => HttpResponse::BadRequest().body("Message rejected"),
         Err(e) => {
-            println!("❌ Error: {}", e);
+            error!(error = %e, "conversation failed");
             HttpResponse::InternalServerError().body(format!("Error: {}", e))
         },
     }
 }
 
 pub async fn login(login_data: web::Json<serde_json::Value>) -> impl Responder {
-    println!("Starting login process...");
     let username = login_data.get("user").and_then(|u| u.as_str()).unwrap_or("");
     let password = login_data.get("password").and_then(|p| p.as_str()).unwrap_or("");
-    println!("Attempting to load environment variables...");
     
     let env_username = match env::var("APP_USER") {
-        Ok(val) => {
-            println!("APP_USER found: {}", val);
-            val
-        },
+        Ok(val) => val,
         Err(e) => {
-            println!("Error loading APP_USER: {:?}", e);
+            error!(error = %e, "APP_USER not configured");
             return HttpResponse::InternalServerError().body("Environment variable APP_USER not found");
         }
     };
 
     let env_password = match env::var("APP_PASSWORD") {
-        Ok(val) => {
-            println!("APP_PASSWORD found");
-            val
-        },
+        Ok(val) => val,
         Err(e) => {
-            println!("Error loading APP_PASSWORD: {:?}", e);
+            error!(error = %e, "APP_PASSWORD not configured");
             return HttpResponse::InternalServerError().body("Environment variable APP_PASSWORD not found");
         }
     };
 
     let jwt_secret = match env::var("JWT_SECRET") {
-        Ok(val) => {
-            println!("JWT_SECRET found");
-            val
-        },
+        Ok(val) => val,
         Err(e) => {
-            println!("Error loading JWT_SECRET: {:?}", e);
+            error!(error = %e, "JWT_SECRET not configured");
             return HttpResponse::InternalServerError().body("Environment variable JWT_SECRET not found");
         }
     };
 
-    println!("Credentials received - User: {}", username);
-    println!("Expected credentials - User: {}", env_username);
-
     if username == env_username && password == env_password {
-        println!("Valid credentials, generating token...");
+        info!("valid credentials, issuing token");
         let expiration = Utc::now()
             .checked_add_signed(Duration::hours(1))
             .expect("Error calculating expiration date")
@@ -248,11 +236,12 @@ pub async fn login(login_data: web::Json<serde_json::Value>) -> impl Responder {
                 HttpResponse::Ok().json(response)
             }
             Err(e) => {
-                println!("Error generating token: {:?}", e);
+                error!(error = %e, "error generating token");
                 HttpResponse::InternalServerError().body("Error generating token")
             }
         }
     } else {
+        warn!("invalid login attempt");
         HttpResponse::Unauthorized().body("Invalid credentials")
     }
 }
@@ -280,13 +269,31 @@ pub async fn handle_proposal_vote(
 pub async fn handle_monthly_execution(
     req: HttpRequest,
     proposal_manager: web::Data<ProposalManager>,
+    redis_client: web::Data<redis::Client>,
 ) -> impl Responder {
-    if let Err(response) = verify_token(&req).await {
+    if let Err(response) = verify_admin(&req).await {
         return response;
     }
 
     match proposal_manager.execute_monthly_selection().await {
-        Ok(receipt) => HttpResponse::Ok().json(receipt),
+        Ok(receipt) => {
+            let month = Utc::now().month() as u64;
+            let winners = proposal_manager.get_monthly_winners(month).await
+                .map(|winners| winners.iter().map(|w| w.proposer).collect::<Vec<_>>())
+                .map_err(|e| e.to_string());
+            match winners {
+                Ok(winners) => {
+                    info!(month, winners = winners.len(), "monthly selection executed");
+                    events::publish_in_background(redis_client.get_ref().clone(), Event::MonthlyWinnersSelected {
+                        month,
+                        winners: winners.iter().map(|w| format!("{:?}", w)).collect(),
+                    });
+                    tokio::spawn(notify_monthly_winners(redis_client.get_ref().clone(), winners));
+                },
+                Err(e) => warn!(error = %e, "error getting monthly winners, notifications skipped"),
+            }
+            HttpResponse::Ok().json(receipt)
+        },
         Err(e) => HttpResponse::InternalServerError().body(format!("Error executing: {}", e)),
     }
 }
@@ -296,21 +303,16 @@ pub async fn handle_proposal_post(
     json_data: web::Json<Proposal>,
     redis_client: web::Data<redis::Client>,
 ) -> impl Responder {
-    println!("\n📝 POST /proposals - Guardando nueva propuesta");
-    println!("📦 Datos recibidos: {:?}", json_data);
-
     if let Err(response) = verify_token(&req).await {
-        println!("❌ Token verification failed");
+        warn!("token verification failed");
         return response;
     }
 
     let mut con = match redis_client.get_async_connection().await {
-        Ok(con) => {
-            println!("✅ Conexión Redis establecida");
-            con
-        },
+        Ok(con) => con,
         Err(e) => {
-            println!("❌ Error de conexión Redis: {:?}", e);
+            error!(error = %e, "Redis connection error");
+            metrics::redis_error("api");
             return HttpResponse::InternalServerError().body("Redis connection error");
         }
     };
@@ -318,8 +320,7 @@ pub async fn handle_proposal_post(
     let proposal = json_data.into_inner();
     let proposal_json = serde_json::to_string(&proposal).unwrap();
     
-    println!("💾 Guardando propuesta - Wallet: {}", proposal.wallet);
-    println!("   Datos: {}", proposal_json);
+    debug!(wallet = %telemetry::redact_wallet(&proposal.wallet), proposal_type = %proposal.proposal_type, "saving proposal");
 
     // Usar wallet como clave
     match con.hset::<_, _, _, ()>(
@@ -328,11 +329,17 @@ pub async fn handle_proposal_post(
         &proposal_json,
     ).await {
         Ok(_) => {
-            println!("✅ Propuesta guardada exitosamente");
+            info!(wallet = %telemetry::redact_wallet(&proposal.wallet), "proposal saved");
+            events::publish_in_background(redis_client.get_ref().clone(), Event::ProposalSubmitted {
+                wallet: proposal.wallet.clone(),
+                fid: proposal.fid,
+                proposal_type: proposal.proposal_type.clone(),
+                channel: "api".to_string(),
+            });
             HttpResponse::Ok().json(proposal)
         },
         Err(e) => {
-            println!("❌ Error guardando propuesta: {:?}", e);
+            error!(error = %e, "error saving proposal");
             HttpResponse::InternalServerError().body("Error saving proposal")
         }
     }
@@ -377,20 +384,16 @@ pub async fn handle_context_update(
     context_parts: web::Json<Vec<ContextPart>>,
     redis_client: web::Data<redis::Client>,
 ) -> impl Responder {
-    println!("\n📝 POST /context - Actualizando contexto");
-
     if let Err(response) = verify_token(&req).await {
-        println!("❌ Token verification failed");
+        warn!("token verification failed");
         return response;
     }
 
     let mut con = match redis_client.get_async_connection().await {
-        Ok(con) => {
-            println!("✅ Conexión Redis establecida");
-            con
-        },
+        Ok(con) => con,
         Err(e) => {
-            println!("❌ Error de conexión Redis: {:?}", e);
+            error!(error = %e, "Redis connection error");
+            metrics::redis_error("api");
             return HttpResponse::InternalServerError().body("Redis connection error");
         }
     };
@@ -418,11 +421,12 @@ pub async fn handle_context_update(
 
     match con.set::<_, _, ()>("context-text", &combined_context).await {
         Ok(_) => {
-            println!("✅ Contexto actualizado exitosamente en Redis");
+            info!(chars = combined_context.len(), "narrative context updated");
+            events::publish_in_background(redis_client.get_ref().clone(), Event::ContextUpdated { chars: combined_context.len() });
             HttpResponse::Ok().json("Context updated successfully")
         },
         Err(e) => {
-            println!("❌ Error actualizando contexto en Redis: {:?}", e);
+            error!(error = %e, "error updating context in Redis");
             HttpResponse::InternalServerError().body("Error updating context")
         }
     }
@@ -435,6 +439,7 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .route("/api", web::post().to(protected_api))
             .route("/nft-claim", web::post().to(handle_nft_claim_post))
             .route("/nft-claim", web::get().to(handle_nft_claim_get))
+            .route("/jobs/{id}", web::get().to(handle_job_get))
             .route("/proposals", web::get().to(handle_proposals_get))
             .route("/proposals", web::post().to(handle_proposal_post))
             .route("/proposals", web::put().to(handle_proposal_update))
@@ -442,7 +447,37 @@ pub fn config(cfg: &mut web::ServiceConfig) {
             .route("/proposalssc", web::post().to(handle_proposal_elevate))
             .route("/proposalsw", web::get().to(handle_proposals_winners))
             .route("/health", web::get().to(health_check))
+            .route("/health/live", web::get().to(health_live))
+            .route("/health/ready", web::get().to(health_ready))
+            .route("/health/workers", web::get().to(health_workers))
+            .route("/metrics", web::get().to(metrics_endpoint))
             .route("/context", web::post().to(handle_context_update))
+            .route("/admin/controls", web::get().to(handle_controls_get))
+            .route("/admin/controls/{control}/pause", web::post().to(handle_control_pause))
+            .route("/admin/controls/{control}/resume", web::post().to(handle_control_resume))
+            .route("/admin/workers/{worker}/poll", web::post().to(handle_worker_poll))
+            .route("/admin/config", web::get().to(handle_runtime_config))
+            .route("/admin/farcaster/casts", web::post().to(handle_farcaster_cast))
+            .route("/admin/farcaster/lore", web::get().to(handle_lore_calendar))
+            .route("/admin/farcaster/lore/{id}/approve", web::post().to(handle_lore_approve))
+            .route("/admin/farcaster/lore/{id}/reject", web::post().to(handle_lore_reject))
+            .route("/admin/twitter/dead-letter", web::get().to(handle_twitter_dead_letters))
+            .route("/admin/twitter/dead-letter/retry", web::post().to(handle_twitter_dead_letter_retry))
+            .route("/admin/twitter/quota", web::get().to(handle_twitter_quota))
+            .route("/admin/twitter/posts", web::post().to(handle_twitter_post))
+            .route("/admin/proposals/monthly-selection", web::post().to(handle_monthly_execution))
+            .route("/admin/webhooks", web::get().to(handle_webhooks_get))
+            .route("/admin/webhooks", web::post().to(handle_webhook_create))
+            .route("/admin/webhooks/dead-letter", web::get().to(handle_webhook_dead_letters))
+            .route("/admin/webhooks/dead-letter/retry", web::post().to(handle_webhook_dead_letter_retry))
+            .route("/admin/webhooks/{id}", web::delete().to(handle_webhook_delete))
+            .route("/admin/nft/contract", web::get().to(handle_nft_contract_get))
+            .route("/admin/nft/upgrade", web::post().to(handle_nft_upgrade))
+            .route("/admin/nft/stranded", web::get().to(handle_nft_stranded))
+            .route("/admin/nft/stranded/deliver", web::post().to(handle_nft_stranded_deliver))
+            .route("/webhooks/farcaster", web::post().to(handle_farcaster_webhook))
+            .route("/webhooks/frame", web::post().to(handle_frame_webhook))
+            .route("/webhooks/telegram", web::post().to(handle_telegram_webhook))
     );
 }
 
@@ -450,35 +485,25 @@ pub async fn handle_pending_proposals(
     req: HttpRequest,
     redis_client: web::Data<redis::Client>,
 ) -> impl Responder {
-    println!("\n📥 [PENDING_PROPOSALS] Iniciando búsqueda de todas las propuestas pendientes");
-    println!("🔑 [PENDING_PROPOSALS] Auth header: {:?}", req.headers().get("Authorization"));
-    
     if let Err(response) = verify_token(&req).await {
-        println!("❌ [PENDING_PROPOSALS] Token verification failed");
+        warn!("token verification failed");
         return response;
     }
-    println!("✅ [PENDING_PROPOSALS] Token verificado correctamente");
 
     let mut con = match redis_client.get_async_connection().await {
-        Ok(con) => {
-            println!("✅ [PENDING_PROPOSALS] Conexión Redis establecida");
-            con
-        },
+        Ok(con) => con,
         Err(e) => {
-            println!("❌ [PENDING_PROPOSALS] Error de conexión Redis: {:?}", e);
+            error!(error = %e, "Redis connection error");
+            metrics::redis_error("api");
             return HttpResponse::InternalServerError().body(format!("Redis connection error: {}", e));
         }
     };
 
     // Obtener todas las propuestas de Redis usando HKEYS primero
-    println!("📝 [PENDING_PROPOSALS] Obteniendo todas las keys de Redis");
     let keys: Vec<String> = match con.hkeys::<_, Vec<String>>("proposals").await {
-        Ok(k) => {
-            println!("✅ [PENDING_PROPOSALS] Encontradas {} keys en total", k.len());
-            k
-        },
+        Ok(k) => k,
         Err(e) => {
-            println!("❌ [PENDING_PROPOSALS] Error obteniendo keys: {:?}", e);
+            error!(error = %e, "error listing proposal keys");
             return HttpResponse::InternalServerError().body(format!("Redis error: {}", e));
         }
     };
@@ -486,34 +511,23 @@ pub async fn handle_pending_proposals(
     // Obtener cada propuesta individualmente
     let mut pending_proposals = Vec::new();
     for key in &keys {
-        println!("🔍 [PENDING_PROPOSALS] Revisando propuesta con key: {}", key);
         match con.hget::<_, _, Option<String>>("proposals", key).await {
             Ok(Some(value)) => {
-                println!("📋 [PENDING_PROPOSALS] Valor encontrado para key {}", key);
                 match serde_json::from_str::<Proposal>(&value) {
                     Ok(proposal) => {
-                        println!("✅ [PENDING_PROPOSALS] Propuesta parseada - Wallet: {} - Status: {}", 
-                            proposal.wallet, proposal.status);
                         if proposal.status == 1 || proposal.status == 2 {
-                            println!("➕ [PENDING_PROPOSALS] Añadiendo propuesta pendiente de wallet {}", 
-                                proposal.wallet);
                             pending_proposals.push(proposal);
-                        } else {
-                            println!("ℹ️ [PENDING_PROPOSALS] Ignorando propuesta de {} - Status {} no es pendiente", 
-                                proposal.wallet, proposal.status);
                         }
                     },
-                    Err(e) => println!("❌ [PENDING_PROPOSALS] Error parseando propuesta: {:?}\nValor: {}", e, value),
+                    Err(e) => warn!(wallet = %telemetry::redact_wallet(key), error = %e, "error parsing proposal"),
                 }
             },
-            Ok(None) => println!("⚠️ [PENDING_PROPOSALS] No se encontró valor para key: {}", key),
-            Err(e) => println!("❌ [PENDING_PROPOSALS] Error obteniendo valor de key {}: {:?}", key, e),
+            Ok(None) => warn!(wallet = %telemetry::redact_wallet(key), "proposal key has no value"),
+            Err(e) => error!(wallet = %telemetry::redact_wallet(key), error = %e, "error reading proposal"),
         }
     }
 
-    println!("\n🔍 [PENDING_PROPOSALS] Resumen:");
-    println!("   Total keys en Redis: {}", keys.len());
-    println!("   Total propuestas pendientes encontradas: {}", pending_proposals.len());
+    info!(total = keys.len(), pending = pending_proposals.len(), "pending proposals listed");
     HttpResponse::Ok().json(pending_proposals)
 }
 
@@ -536,19 +550,18 @@ pub async fn handle_winners_by_month(
     path: web::Path<String>,  // month
     proposal_manager: web::Data<ProposalManager>,
 ) -> impl Responder {
-    println!("📥 Processing winners request for month: {}", path.into_inner());
-    println!("🔑 Auth header: {:?}", req.headers().get("Authorization"));
-    
+    debug!(month = %path.into_inner(), "processing winners request");
+
     if let Err(response) = verify_token(&req).await {
-        println!("❌ Token verification failed");
+        warn!("token verification failed");
         return response;
     }
 
     // Verificar que el ProposalManager está configurado correctamente
     match proposal_manager.check_configuration().await {
-        Ok(_) => println!("✅ ProposalManager configuration verified"),
+        Ok(_) => debug!("proposal manager configuration verified"),
         Err(e) => {
-            println!("❌ ProposalManager configuration error: {:?}", e);
+            error!(error = %e, "proposal manager configuration error");
             return HttpResponse::InternalServerError()
                 .body("ProposalManager not configured correctly");
         }
@@ -556,11 +569,11 @@ pub async fn handle_winners_by_month(
 
     match proposal_manager.get_active_proposals().await {
         Ok(proposals) => {
-            println!("✅ Found {} active proposals", proposals.len());
+            info!(count = proposals.len(), "active proposals found");
             HttpResponse::Ok().json(proposals)
         },
         Err(e) => {
-            println!("❌ Error getting active proposals: {:?}", e);
+            error!(error = %e, "error getting active proposals");
             HttpResponse::InternalServerError()
                 .body(format!("Error getting active proposals: {}", e))
         }
@@ -582,7 +595,7 @@ pub async fn handle_proposals_voting(
     match proposal_manager.get_current_month_proposals().await {
         Ok(proposals) => HttpResponse::Ok().json(proposals),
         Err(e) => {
-            println!("❌ Error obteniendo propuestas en votación: {}", e);
+            error!(error = %e, "error getting proposals in voting");
             HttpResponse::InternalServerError().body(format!("Error: {}", e))
         }
     }
@@ -592,11 +605,10 @@ pub async fn handle_proposal_elevate(
     req: HttpRequest,
     proposal: web::Json<Proposal>,
     proposal_manager: web::Data<ProposalManager>,
+    redis_client: web::Data<redis::Client>,
 ) -> impl Responder {
-    println!("📥 [ELEVATE] Recibiendo propuesta para elevar al blockchain");
-    
     if let Err(response) = verify_token(&req).await {
-        println!("❌ [ELEVATE] Error de autenticación");
+        warn!("token verification failed");
         return HttpResponse::BadRequest().json(serde_json::json!({
             "success": false,
             "message": "Authentication failed",
@@ -605,12 +617,18 @@ pub async fn handle_proposal_elevate(
     }
 
     let proposal_data = proposal.into_inner();
-    println!("📋 [ELEVATE] Datos de la propuesta: {:?}", proposal_data);
+    debug!(wallet = %telemetry::redact_wallet(&proposal_data.wallet), proposal_type = %proposal_data.proposal_type, "elevating proposal on-chain");
     
     match proposal_manager.index_proposal_from_backend(&proposal_data).await {
         Ok(tx_receipt) => {
-            println!("✅ [ELEVATE] Propuesta elevada exitosamente");
-            println!("📝 [ELEVATE] Transaction receipt: {:?}", tx_receipt);
+            info!(tx_hash = ?tx_receipt.transaction_hash, "proposal elevated on-chain");
+            events::publish_in_background(redis_client.get_ref().clone(), Event::ProposalElevated {
+                wallet: proposal_data.wallet.clone(),
+                proposal_type: proposal_data.proposal_type.clone(),
+                tx_hash: format!("{:?}", tx_receipt.transaction_hash),
+                block_number: tx_receipt.block_number.map(|n| n.as_u64()),
+            });
+            notify_status_change(&redis_client, &Proposal { status: ProposalStatus::Voting as i32, ..proposal_data });
             
             HttpResponse::Ok().json(serde_json::json!({
                 "success": true,
@@ -623,8 +641,7 @@ pub async fn handle_proposal_elevate(
             }))
         },
         Err(e) => {
-            println!("❌ [ELEVATE] Error al elevar propuesta: {}", e);
-            println!("🔍 [ELEVATE] Detalles del error: {:?}", e);
+            error!(error = ?e, "failed to elevate proposal");
             
             HttpResponse::BadRequest().json(serde_json::json!({
                 "success": false,
@@ -645,11 +662,11 @@ pub async fn handle_proposals_winners(
 
     match proposal_manager.get_all_winning_proposals().await {
         Ok(winners) => {
-            println!("✅ Propuestas ganadoras obtenidas exitosamente");
+            info!(count = winners.len(), "winning proposals fetched");
             HttpResponse::Ok().json(winners)
         },
         Err(e) => {
-            println!("❌ Error obteniendo propuestas ganadoras: {}", e);
+            error!(error = %e, "error getting winning proposals");
             HttpResponse::InternalServerError().body(format!("Error: {}", e))
         }
     }
@@ -666,7 +683,10 @@ pub async fn handle_proposal_update(
 
     let mut con = match redis_client.get_async_connection().await {
         Ok(con) => con,
-        Err(_) => return HttpResponse::InternalServerError().body("Redis connection error"),
+        Err(_) => {
+            metrics::redis_error("api");
+            return HttpResponse::InternalServerError().body("Redis connection error");
+        },
     };
 
     let wallet = update_data.get("wallet").and_then(|w| w.as_str());
@@ -686,6 +706,7 @@ pub async fn handle_proposal_update(
             };
 
             // Actualizar el estado
+            let changed = proposal.status != status as i32;
             proposal.status = status as i32;
 
             // Guardar la propuesta actualizada
@@ -694,6 +715,9 @@ pub async fn handle_proposal_update(
                 return HttpResponse::InternalServerError().body("Error updating proposal");
             }
 
+            if changed {
+                notify_status_change(&redis_client, &proposal);
+            }
             return HttpResponse::Ok().json(proposal);
         }
         
@@ -713,7 +737,10 @@ pub async fn handle_proposals_get(
 
     let mut con = match redis_client.get_async_connection().await {
         Ok(con) => con,
-        Err(_) => return HttpResponse::InternalServerError().body("Redis connection error"),
+        Err(_) => {
+            metrics::redis_error("api");
+            return HttpResponse::InternalServerError().body("Redis connection error");
+        },
     };
 
     // Obtener todas las propuestas
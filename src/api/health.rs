@@ -0,0 +1,205 @@
+use actix_web::{web, HttpResponse, Responder};
+use serde::Serialize;
+use redis::AsyncCommands;
+use std::collections::BTreeMap;
+use std::env;
+use std::future::Future;
+use std::time::Instant;
+use tokio::time::{timeout, Duration};
+use crate::api::cdp::nfts::NftManager;
+use crate::api::proposals::ProposalManager;
+use crate::telemetry;
+use crate::workers::{Supervisor, WorkerState, WorkerStatus};
+
+// Claves donde los workers sociales guardan su último poll exitoso (unix secs)
+pub const TWITTER_LAST_POLL_KEY: &str = "twitter:last_poll_ok";
+pub const FARCASTER_LAST_POLL_KEY: &str = "farcaster:last_poll_ok";
+
+// Ambos workers hacen poll cada 15 minutos; dos ciclos perdidos = degradado
+const WORKER_STALE_AFTER_SECS: i64 = 2 * 15 * 60 + 60;
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckStatus {
+    Ok,
+    Degraded,
+    Unknown,
+}
+
+#[derive(Serialize, Debug)]
+pub struct CheckResult {
+    status: CheckStatus,
+    required: bool,
+    latency_ms: u128,
+    detail: serde_json::Value,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ReadinessReport {
+    status: CheckStatus,
+    checks: BTreeMap<&'static str, CheckResult>,
+}
+
+async fn run_check<F, Fut>(required: bool, check: F) -> CheckResult
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = anyhow::Result<(CheckStatus, serde_json::Value)>>,
+{
+    let started = Instant::now();
+    let (status, detail) = match timeout(CHECK_TIMEOUT, check()).await {
+        Ok(Ok(result)) => result,
+        Ok(Err(e)) => (CheckStatus::Degraded, serde_json::json!({ "error": e.to_string() })),
+        Err(_) => (CheckStatus::Degraded, serde_json::json!({ "error": "timed out" })),
+    };
+
+    CheckResult {
+        status,
+        required,
+        latency_ms: started.elapsed().as_millis(),
+        detail,
+    }
+}
+
+async fn check_redis(redis_client: &redis::Client) -> anyhow::Result<(CheckStatus, serde_json::Value)> {
+    let mut con = redis_client.get_async_connection().await?;
+    let pong: String = redis::cmd("PING").query_async(&mut con).await?;
+    Ok((CheckStatus::Ok, serde_json::json!({ "reply": pong })))
+}
+
+async fn check_rpc(nft_manager: &NftManager) -> anyhow::Result<(CheckStatus, serde_json::Value)> {
+    let (chain_id, block_number) = nft_manager.chain_status().await?;
+    let chain_id = u64::try_from(chain_id).map_err(|_| anyhow::anyhow!("chain ID {} does not fit in u64", chain_id))?;
+    Ok((CheckStatus::Ok, serde_json::json!({
+        "chain_id": chain_id,
+        "block_number": block_number.as_u64(),
+    })))
+}
+
+async fn check_signer_balance(nft_manager: &NftManager) -> anyhow::Result<(CheckStatus, serde_json::Value)> {
+    let balance = nft_manager.signer_balance().await?;
+    let status = if balance.is_zero() { CheckStatus::Degraded } else { CheckStatus::Ok };
+    Ok((status, serde_json::json!({
+        "address": format!("{:?}", nft_manager.signer_address()),
+        "balance_wei": balance.to_string(),
+    })))
+}
+
+async fn check_proposal_manager(proposal_manager: &ProposalManager) -> anyhow::Result<(CheckStatus, serde_json::Value)> {
+    proposal_manager.check_configuration().await?;
+    Ok((CheckStatus::Ok, serde_json::Value::Null))
+}
+
+async fn check_pinata(nft_manager: &NftManager) -> anyhow::Result<(CheckStatus, serde_json::Value)> {
+    nft_manager.check_pinata_auth().await?;
+    Ok((CheckStatus::Ok, serde_json::Value::Null))
+}
+
+async fn check_openai() -> anyhow::Result<(CheckStatus, serde_json::Value)> {
+    let api_key = env::var("OPENAI_API_KEY")?;
+    let response = telemetry::propagate(reqwest::Client::new().get("https://api.openai.com/v1/models"))
+        .header("Authorization", format!("Bearer {}", api_key))
+        .send()
+        .await?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(anyhow::anyhow!("OpenAI returned {}", status));
+    }
+    Ok((CheckStatus::Ok, serde_json::json!({ "http_status": status.as_u16() })))
+}
+
+async fn check_worker(
+    redis_client: &redis::Client,
+    key: &str,
+    supervised: Option<WorkerStatus>,
+) -> anyhow::Result<(CheckStatus, serde_json::Value)> {
+    let mut con = redis_client.get_async_connection().await?;
+    let last_poll: Option<i64> = con.get(key).await?;
+
+    let (mut status, mut detail) = match last_poll {
+        Some(ts) => {
+            let age = chrono::Utc::now().timestamp() - ts;
+            let status = if age > WORKER_STALE_AFTER_SECS { CheckStatus::Degraded } else { CheckStatus::Ok };
+            (status, serde_json::json!({
+                "last_successful_poll": ts,
+                "age_secs": age,
+            }))
+        },
+        None => (CheckStatus::Unknown, serde_json::json!({ "last_successful_poll": null })),
+    };
+
+    // Un worker en backoff o detenido está degradado aunque su último poll sea reciente
+    if let Some(worker) = supervised {
+        if worker.state != WorkerState::Running {
+            status = CheckStatus::Degraded;
+        }
+        detail["supervisor"] = serde_json::to_value(worker)?;
+    }
+
+    Ok((status, detail))
+}
+
+pub async fn health_workers(supervisor: web::Data<Supervisor>) -> impl Responder {
+    HttpResponse::Ok().json(supervisor.statuses())
+}
+
+pub async fn health_live() -> impl Responder {
+    HttpResponse::Ok().json(serde_json::json!({ "status": CheckStatus::Ok }))
+}
+
+pub async fn health_ready(
+    redis_client: web::Data<redis::Client>,
+    nft_manager: web::Data<NftManager>,
+    proposal_manager: Option<web::Data<ProposalManager>>,
+    supervisor: Option<web::Data<Supervisor>>,
+) -> impl Responder {
+    let workers = supervisor.map(|s| s.statuses()).unwrap_or_default();
+    let (redis, rpc, signer, pinata, openai, twitter, farcaster) = tokio::join!(
+        run_check(true, || check_redis(&redis_client)),
+        run_check(true, || check_rpc(&nft_manager)),
+        run_check(false, || check_signer_balance(&nft_manager)),
+        run_check(false, || check_pinata(&nft_manager)),
+        run_check(true, check_openai),
+        run_check(false, || check_worker(&redis_client, TWITTER_LAST_POLL_KEY, workers.get("twitter").cloned())),
+        run_check(false, || check_worker(&redis_client, FARCASTER_LAST_POLL_KEY, workers.get("farcaster").cloned())),
+    );
+
+    let mut checks = BTreeMap::new();
+    checks.insert("redis", redis);
+    checks.insert("rpc", rpc);
+    checks.insert("signer_balance", signer);
+    checks.insert("pinata", pinata);
+    checks.insert("openai", openai);
+    checks.insert("twitter_worker", twitter);
+    checks.insert("farcaster_worker", farcaster);
+
+    // Sin ProposalManager (falló al arrancar) el contrato de propuestas cuenta como caído
+    checks.insert(
+        "proposal_contract",
+        run_check(true, || async {
+            match &proposal_manager {
+                Some(proposal_manager) => check_proposal_manager(proposal_manager).await,
+                None => Err(anyhow::anyhow!("proposal manager failed to initialize")),
+            }
+        }).await,
+    );
+
+    let required_degraded = checks
+        .values()
+        .any(|check| check.required && check.status != CheckStatus::Ok);
+    let any_degraded = checks
+        .values()
+        .any(|check| check.status == CheckStatus::Degraded);
+
+    let report = ReadinessReport {
+        status: if required_degraded || any_degraded { CheckStatus::Degraded } else { CheckStatus::Ok },
+        checks,
+    };
+
+    if required_degraded {
+        HttpResponse::ServiceUnavailable().json(report)
+    } else {
+        HttpResponse::Ok().json(report)
+    }
+}
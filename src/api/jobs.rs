@@ -0,0 +1,29 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use crate::api::auth::verify_token;
+use crate::jobs;
+use crate::metrics;
+
+// Estado de un job en segundo plano (p. ej. el devuelto por POST /nft-claim)
+pub async fn handle_job_get(
+    req: HttpRequest,
+    path: web::Path<String>,
+    redis_client: web::Data<redis::Client>,
+) -> impl Responder {
+    if let Err(response) = verify_token(&req).await {
+        return response;
+    }
+
+    let result = async {
+        let mut con = redis_client.get_async_connection().await?;
+        jobs::get(&mut con, &path.into_inner()).await
+    }.await;
+
+    match result {
+        Ok(Some(job)) => HttpResponse::Ok().json(job.summary()),
+        Ok(None) => HttpResponse::NotFound().body("Job not found"),
+        Err(_) => {
+            metrics::redis_error("api");
+            HttpResponse::InternalServerError().body("Redis connection error")
+        }
+    }
+}
@@ -2,4 +2,8 @@ pub mod cdp;
 pub mod handlers;
 pub mod nft_claim;
 pub mod auth;
+pub mod admin;
+pub mod health;
+pub mod jobs;
+pub mod webhooks;
 pub mod proposals;
\ No newline at end of file
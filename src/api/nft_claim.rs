@@ -3,9 +3,20 @@ use serde::{Deserialize, Serialize};
 use redis::AsyncCommands;
 use chrono::{DateTime, Utc};
 use crate::api::auth::verify_token;
+use crate::api::admin::{is_paused, Control};
+use crate::events::{self, Event};
+use crate::farcaster::notifications::{self, Notification};
+use crate::metrics;
+use crate::telemetry;
+use tracing::{debug, error, info, warn};
 use crate::api::cdp::nfts::{NftManager, UserData};
-use ethers::types::{Address, U256, H256};
+use crate::jobs::{self, Enqueued, JobError, JobHandler};
+use ethers::types::{Address, Bytes, TransactionReceipt, U256, H256};
 use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::time::Duration;
 
 #[derive(Deserialize)]
 pub struct NFTClaimRequest {
@@ -23,6 +34,13 @@ pub struct NFTClaimResponse {
     token_id: Option<u64>,
 }
 
+#[derive(Serialize)]
+pub struct NFTClaimJobResponse {
+    job_id: String,
+    status: &'static str,
+    message: String,
+}
+
 #[derive(Serialize, Deserialize)]
 pub struct Claim {
     fid: u64,
@@ -31,7 +49,664 @@ pub struct Claim {
     token_id: u64,
 }
 
-async fn check_wallet_has_nft(nft_manager: &NftManager, wallet: &str) -> Result<bool> {
+// Mensajes del usuario necesarios en la conversación para reclamar el NFT
+pub const REQUIRED_INTERACTIONS: usize = 6;
+
+pub enum Minted {
+    Token(u64),
+    AlreadyClaimed,
+    // Otro proceso tiene el lock de la wallet
+    InProgress,
+}
+
+pub const CLAIM_JOB: &str = "nft_claim";
+// Con minting pausado el job espera en la cola en lugar de gastar intentos
+const PAUSED_DEFER: Duration = Duration::from_secs(60);
+// Con otro claim de la misma wallet en curso se espera a que suelte el lock
+const CLAIM_BUSY_DEFER: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Deserialize)]
+struct ClaimPayload {
+    fid: u64,
+    wallet: String,
+    timestamp: DateTime<Utc>,
+    // Sin datos al retomar un claim cuyo mint ya se envió
+    #[serde(default)]
+    user_data: Option<UserData>,
+}
+
+fn claim_idempotency_key(wallet: &str) -> String {
+    format!("nft-claim:{}", wallet.to_lowercase())
+}
+
+// Propia del token: con la de la wallet, un claim anterior ya terminado devolvería
+// ese job y la entrega no se ejecutaría
+fn delivery_idempotency_key(token_id: u64) -> String {
+    format!("nft-deliver:{}", token_id)
+}
+
+// Encola el mint de un claim ya validado. La clave es la wallet: mientras su claim
+// anterior no haya fallado, repetir la petición devuelve el mismo job
+pub async fn enqueue_claim(
+    redis_client: &redis::Client,
+    fid: u64,
+    user_data: UserData,
+    timestamp: DateTime<Utc>,
+) -> Result<Enqueued> {
+    let wallet = user_data.wallet_address.clone();
+    let payload = serde_json::to_value(ClaimPayload { fid, wallet: wallet.clone(), timestamp, user_data: Some(user_data) })?;
+    jobs::enqueue(redis_client, CLAIM_JOB, payload, Some(claim_idempotency_key(&wallet))).await
+}
+
+// Sube la imagen y mintea (y transfiere, sin mintTo) fuera de la petición HTTP
+pub struct NftClaimJob {
+    redis_client: redis::Client,
+    nft_manager: Arc<NftManager>,
+}
+
+impl NftClaimJob {
+    pub fn new(redis_client: redis::Client, nft_manager: Arc<NftManager>) -> Self {
+        Self { redis_client, nft_manager }
+    }
+}
+
+#[async_trait]
+impl JobHandler for NftClaimJob {
+    fn kind(&self) -> &'static str {
+        CLAIM_JOB
+    }
+
+    async fn run(&self, payload: &serde_json::Value) -> std::result::Result<serde_json::Value, JobError> {
+        let claim: ClaimPayload = serde_json::from_value(payload.clone())
+            .map_err(|e| JobError::Fatal(anyhow::anyhow!("invalid claim payload: {}", e)))?;
+        if is_paused(&self.redis_client, Control::Minting).await {
+            return Err(JobError::Defer(PAUSED_DEFER));
+        }
+        if claim.wallet.parse::<Address>().is_err() {
+            return Err(JobError::Fatal(anyhow::anyhow!("invalid wallet address")));
+        }
+
+        // El claim guarda cada transacción firmada antes de enviarla, así que un
+        // reintento retoma la que ya salió en lugar de mandar otra
+        match mint_claim(&self.redis_client, &self.nft_manager, claim.fid, &claim.wallet, claim.user_data, claim.timestamp).await {
+            Ok(Minted::Token(token_id)) => Ok(serde_json::json!({ "token_id": token_id })),
+            Ok(Minted::AlreadyClaimed) => Err(JobError::Fatal(anyhow::anyhow!("User already has an NFT"))),
+            Ok(Minted::InProgress) => Err(JobError::Defer(CLAIM_BUSY_DEFER)),
+            Err(e) => Err(JobError::Retry(e)),
+        }
+    }
+}
+
+// Estado del claim de cada wallet: hash wallet (minúsculas) -> ClaimProgress en JSON
+const CLAIM_STATES_KEY: &str = "nft:claim_states";
+// Lock por wallet mientras se avanza su claim. Se renueva antes de cada paso y cada
+// paso espera como mucho un recibo (RECEIPT_TIMEOUT), así que no vence con el claim
+// en marcha aunque la ejecución espere varios
+const CLAIM_LOCK_PREFIX: &str = "nft:claim_lock:";
+const CLAIM_LOCK_TTL_SECS: u64 = 10 * 60;
+const RENEW_LOCK_SCRIPT: &str = "if redis.call('GET', KEYS[1]) == ARGV[1] then return redis.call('EXPIRE', KEYS[1], ARGV[2]) else return 0 end";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClaimState {
+    Requested,
+    // Mint enviado, esperando el recibo
+    Minting,
+    // NFT en la wallet del backend, falta transferirlo. Con mintTo no se pasa por aquí
+    Minted,
+    // Transferencia enviada, esperando el recibo
+    Transferring,
+    Completed,
+    // El mint no llegó a la cadena; un nuevo claim empieza de cero
+    Failed,
+}
+
+impl ClaimState {
+    pub fn is_finished(&self) -> bool {
+        matches!(self, ClaimState::Completed | ClaimState::Failed)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClaimProgress {
+    pub wallet: String,
+    pub fid: u64,
+    pub state: ClaimState,
+    #[serde(default)]
+    pub mint_tx: Option<String>,
+    // Nonce y transacción firmada del mint, para reenviarla mientras su nonce siga libre
+    #[serde(default)]
+    pub mint_nonce: Option<u64>,
+    #[serde(default)]
+    pub mint_raw: Option<String>,
+    #[serde(default)]
+    pub token_id: Option<u64>,
+    #[serde(default)]
+    pub transfer_tx: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+    pub requested_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+pub async fn claim_progress(con: &mut redis::aio::Connection, wallet: &str) -> redis::RedisResult<Option<ClaimProgress>> {
+    let stored: Option<String> = con.hget(CLAIM_STATES_KEY, wallet.to_lowercase()).await?;
+    Ok(stored.and_then(|s| serde_json::from_str(&s).ok()))
+}
+
+async fn save_progress(con: &mut redis::aio::Connection, progress: &mut ClaimProgress) -> Result<()> {
+    progress.updated_at = Utc::now();
+    let _: () = con.hset(CLAIM_STATES_KEY, progress.wallet.to_lowercase(), serde_json::to_string(progress)?).await?;
+    debug!(wallet = %telemetry::redact_wallet(&progress.wallet), state = ?progress.state, "claim state saved");
+    Ok(())
+}
+
+// SET NX con un token propio; solo quien lo tiene puede liberarlo
+async fn acquire_claim_lock(con: &mut redis::aio::Connection, wallet: &str) -> redis::RedisResult<Option<String>> {
+    let token = format!("{:016x}", rand::random::<u64>());
+    let acquired: Option<String> = redis::cmd("SET")
+        .arg(format!("{}{}", CLAIM_LOCK_PREFIX, wallet.to_lowercase()))
+        .arg(&token)
+        .arg("NX")
+        .arg("EX")
+        .arg(CLAIM_LOCK_TTL_SECS)
+        .query_async(con)
+        .await?;
+    Ok(acquired.map(|_| token))
+}
+
+async fn release_claim_lock(con: &mut redis::aio::Connection, wallet: &str, token: &str) -> redis::RedisResult<()> {
+    redis::Script::new(
+        "if redis.call('GET', KEYS[1]) == ARGV[1] then return redis.call('DEL', KEYS[1]) else return 0 end",
+    )
+        .key(format!("{}{}", CLAIM_LOCK_PREFIX, wallet.to_lowercase()))
+        .arg(token)
+        .invoke_async::<_, i32>(con)
+        .await?;
+    Ok(())
+}
+
+// Falso si el lock ya no es nuestro (venció y lo tomó otro)
+async fn renew_claim_lock(con: &mut redis::aio::Connection, wallet: &str, token: &str) -> redis::RedisResult<bool> {
+    let renewed: i32 = redis::Script::new(RENEW_LOCK_SCRIPT)
+        .key(format!("{}{}", CLAIM_LOCK_PREFIX, wallet.to_lowercase()))
+        .arg(token)
+        .arg(CLAIM_LOCK_TTL_SECS)
+        .invoke_async(con)
+        .await?;
+    Ok(renewed == 1)
+}
+
+fn tx_hash(hash: &Option<String>) -> Result<H256> {
+    hash.as_deref()
+        .and_then(|hash| hash.parse::<H256>().ok())
+        .ok_or_else(|| anyhow::anyhow!("claim state has no valid transaction hash"))
+}
+
+// Avanza el claim de la wallet hasta completarlo, retomando desde el estado guardado:
+// un mint o una transferencia ya enviados se esperan en lugar de repetirse. Con mintTo
+// el NFT sale del mint ya en la wallet del usuario y no hay transferencia. Con el
+// lock por wallet dos peticiones a la vez no pueden mintear dos veces. `user_data`
+// solo hace falta si todavía no se envió el mint
+pub async fn mint_claim(
+    redis_client: &redis::Client,
+    nft_manager: &NftManager,
+    fid: u64,
+    wallet: &str,
+    user_data: Option<UserData>,
+    timestamp: DateTime<Utc>,
+) -> Result<Minted> {
+    let to_address = wallet.parse::<Address>()?;
+    let mut con = redis_client.get_async_connection().await?;
+    let lock = match acquire_claim_lock(&mut con, wallet).await? {
+        Some(lock) => lock,
+        None => return Ok(Minted::InProgress),
+    };
+
+    let result = advance_claim(&mut con, redis_client, nft_manager, &lock, fid, wallet, to_address, user_data, timestamp).await;
+
+    if let Err(e) = release_claim_lock(&mut con, wallet, &lock).await {
+        warn!(wallet = %telemetry::redact_wallet(wallet), error = %e, "error releasing claim lock");
+    }
+    result
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn advance_claim(
+    con: &mut redis::aio::Connection,
+    redis_client: &redis::Client,
+    nft_manager: &NftManager,
+    lock: &str,
+    fid: u64,
+    wallet: &str,
+    to_address: Address,
+    user_data: Option<UserData>,
+    timestamp: DateTime<Utc>,
+) -> Result<Minted> {
+    let mut progress = match claim_progress(con, wallet).await? {
+        Some(progress) if progress.state != ClaimState::Failed => progress,
+        _ => {
+            if !nft_manager.get_balance(to_address).await?.is_zero() {
+                info!(wallet = %telemetry::redact_wallet(wallet), "user already owns an NFT");
+                return Ok(Minted::AlreadyClaimed);
+            }
+            let now = Utc::now();
+            let mut progress = ClaimProgress {
+                wallet: wallet.to_string(),
+                fid,
+                state: ClaimState::Requested,
+                mint_tx: None,
+                mint_nonce: None,
+                mint_raw: None,
+                token_id: None,
+                transfer_tx: None,
+                error: None,
+                requested_at: timestamp,
+                updated_at: now,
+            };
+            save_progress(con, &mut progress).await?;
+            progress
+        },
+    };
+
+    loop {
+        if !renew_claim_lock(con, wallet, lock).await? {
+            return Err(anyhow::anyhow!("claim lock expired before the claim finished"));
+        }
+
+        match progress.state {
+            ClaimState::Requested => {
+                // Sin los datos del usuario (un claim retomado al arrancar) no se salió
+                // de aquí: no hay nada enviado y el claim se puede volver a pedir
+                let user_data = match user_data.as_ref() {
+                    Some(user_data) => user_data,
+                    None => {
+                        progress.state = ClaimState::Failed;
+                        progress.error = Some("claim interrupted before minting".to_string());
+                        save_progress(con, &mut progress).await?;
+                        return Err(anyhow::anyhow!("claim has no user data to mint"));
+                    },
+                };
+                let lock = nft_manager.lock_signer().await;
+                let signed = if nft_manager.supports_mint_to() {
+                    nft_manager.sign_mint_to(&lock, to_address, user_data).await?
+                } else {
+                    nft_manager.sign_mint(&lock, user_data).await?
+                };
+
+                // El mint queda guardado antes de salir: si el proceso cae entre medias,
+                // el siguiente intento reenvía la misma transacción firmada
+                progress.mint_tx = Some(format!("{:?}", signed.hash));
+                progress.mint_nonce = Some(signed.nonce);
+                progress.mint_raw = Some(signed.raw.to_string());
+                progress.state = ClaimState::Minting;
+                progress.error = None;
+                save_progress(con, &mut progress).await?;
+                nft_manager.broadcast(signed.raw).await?;
+                drop(lock);
+                continue;
+            },
+            ClaimState::Minting => {
+                let hash = tx_hash(&progress.mint_tx)?;
+                let receipt = match nft_manager.wait_for_receipt(hash).await? {
+                    Some(receipt) => Some(receipt),
+                    None => mint_receipt_or_resend(nft_manager, &progress, hash).await?,
+                };
+                let minted = match &receipt {
+                    Some(receipt) if nft_manager.record_mint(receipt) => {
+                        let (token_id, minted_to) = nft_manager.minted_token(receipt)?;
+                        info!(token_id, tx_hash = ?receipt.transaction_hash, "NFT minted");
+                        TxResult::Succeeded((token_id, minted_to))
+                    },
+                    Some(_) => TxResult::Reverted,
+                    None => TxResult::Dropped,
+                };
+                if let Err(error) = mint_landed(&mut progress, minted, to_address) {
+                    save_progress(con, &mut progress).await?;
+                    return Err(anyhow::anyhow!("{}", error));
+                }
+            },
+            ClaimState::Minted => {
+                let token_id = progress.token_id.ok_or_else(|| anyhow::anyhow!("claim state has no token ID"))?;
+                // La transferencia pudo salir justo antes de una caída sin quedar guardada
+                if nft_manager.get_owner(U256::from(token_id)).await? == to_address {
+                    represent(nft_manager, token_id, to_address).await?;
+                    progress.state = ClaimState::Completed;
+                } else {
+                    // Como el mint: se guarda firmada antes de enviarla
+                    let lock = nft_manager.lock_signer().await;
+                    let signed = nft_manager.sign_transfer(&lock, to_address, token_id).await?;
+                    progress.transfer_tx = Some(format!("{:?}", signed.hash));
+                    progress.state = ClaimState::Transferring;
+                    progress.error = None;
+                    save_progress(con, &mut progress).await?;
+                    nft_manager.broadcast(signed.raw).await?;
+                    drop(lock);
+                    continue;
+                }
+            },
+            ClaimState::Transferring => {
+                let transferred = match nft_manager.wait_for_receipt(tx_hash(&progress.transfer_tx)?).await? {
+                    Some(receipt) if nft_manager.record_transfer(&receipt) => {
+                        info!(tx_hash = ?receipt.transaction_hash, "NFT transferred");
+                        TxResult::Succeeded(())
+                    },
+                    Some(_) => TxResult::Reverted,
+                    None => TxResult::Dropped,
+                };
+                if let Err(error) = transfer_landed(&mut progress, transferred) {
+                    save_progress(con, &mut progress).await?;
+                    return Err(anyhow::anyhow!("{}", error));
+                }
+                // Si falla el claim sigue en Transferring y el reintento, con el recibo
+                // ya disponible, vuelve aquí
+                let token_id = progress.token_id.ok_or_else(|| anyhow::anyhow!("claim state has no token ID"))?;
+                represent(nft_manager, token_id, to_address).await?;
+            },
+            ClaimState::Completed => {
+                let token_id = progress.token_id.ok_or_else(|| anyhow::anyhow!("claim state has no token ID"))?;
+                return Ok(Minted::Token(token_id));
+            },
+            ClaimState::Failed => return Err(anyhow::anyhow!("claim failed")),
+        }
+
+        progress.error = None;
+        save_progress(con, &mut progress).await?;
+        if progress.state == ClaimState::Completed {
+            complete_claim(con, redis_client, &progress).await?;
+        }
+    }
+}
+
+// Resultado de una transacción enviada del claim
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TxResult<T> {
+    Succeeded(T),
+    Reverted,
+    // Sin recibo y sin posibilidad de llegar: el nodo la descartó o su nonce ya lo usó
+    // otra transacción
+    Dropped,
+}
+
+// Estado tras el recibo del mint, con el token minteado y su destinatario. Con mintTo
+// el token ya está en la wallet del usuario. Revertido o descartado no hay NFT y el
+// siguiente claim empieza de cero
+fn mint_landed(progress: &mut ClaimProgress, minted: TxResult<(u64, Address)>, to_address: Address) -> std::result::Result<(), &'static str> {
+    let error = match minted {
+        TxResult::Succeeded((token_id, minted_to)) => {
+            progress.token_id = Some(token_id);
+            progress.state = if minted_to == to_address { ClaimState::Completed } else { ClaimState::Minted };
+            return Ok(());
+        },
+        TxResult::Reverted => "mint transaction reverted",
+        TxResult::Dropped => "mint transaction dropped",
+    };
+    progress.state = ClaimState::Failed;
+    progress.error = Some(error.to_string());
+    Err(error)
+}
+
+// Estado tras el recibo de la transferencia. Si no llegó, el NFT sigue en la wallet
+// del backend y se vuelve a transferir
+fn transfer_landed(progress: &mut ClaimProgress, transferred: TxResult<()>) -> std::result::Result<(), &'static str> {
+    let error = match transferred {
+        TxResult::Succeeded(()) => {
+            progress.state = ClaimState::Completed;
+            return Ok(());
+        },
+        TxResult::Reverted => "transfer transaction reverted",
+        TxResult::Dropped => "transfer transaction dropped",
+    };
+    progress.state = ClaimState::Minted;
+    progress.transfer_tx = None;
+    progress.error = Some(error.to_string());
+    Err(error)
+}
+
+// Tras transferir un token del mint antiguo, representedAddress sigue siendo la
+// wallet del backend. Se comprueba antes para que un reintento no la actualice dos veces
+async fn represent(nft_manager: &NftManager, token_id: u64, to_address: Address) -> Result<()> {
+    if nft_manager.represented_address(token_id).await? != to_address {
+        nft_manager.update_represented_address(token_id, to_address).await?;
+    }
+    Ok(())
+}
+
+// Mint sin recibo: puede que no llegara a enviarse (caída entre guardarlo y enviarlo)
+// o que el nodo lo descartara. Mientras su nonce siga libre se reenvía la misma
+// transacción firmada y se vuelve a esperar; None solo si el nonce ya lo usó otra
+// transacción y el mint sigue sin recibo
+async fn mint_receipt_or_resend(
+    nft_manager: &NftManager,
+    progress: &ClaimProgress,
+    hash: H256,
+) -> Result<Option<TransactionReceipt>> {
+    let nonce = match progress.mint_nonce {
+        Some(nonce) => nonce,
+        // Claims enviados antes de guardar el nonce: sin él no hay forma de saber si
+        // el mint puede llegar todavía
+        None => return nft_manager.receipt(hash).await,
+    };
+
+    // El nonce se lee antes que el recibo: si el mint se mina entre las dos lecturas
+    // se ve su recibo en lugar de darlo por perdido
+    let nonce_used = nft_manager.confirmed_nonce().await? > nonce;
+    if let Some(receipt) = nft_manager.receipt(hash).await? {
+        return Ok(Some(receipt));
+    }
+    if nonce_used {
+        warn!(tx_hash = ?hash, nonce, "mint nonce used by another transaction");
+        return Ok(None);
+    }
+
+    if let Some(raw) = progress.mint_raw.as_deref().and_then(|raw| raw.parse::<Bytes>().ok()) {
+        if let Err(e) = nft_manager.broadcast(raw).await {
+            warn!(tx_hash = ?hash, error = %e, "error resending mint transaction");
+        }
+    }
+    Err(anyhow::anyhow!("mint transaction {:?} still pending", hash))
+}
+
+// Se ejecuta una sola vez, al pasar a Completed
+async fn complete_claim(con: &mut redis::aio::Connection, redis_client: &redis::Client, progress: &ClaimProgress) -> Result<()> {
+    let token_id = progress.token_id.unwrap_or_default();
+    let (fid, wallet) = (progress.fid, progress.wallet.clone());
+    info!(wallet = %telemetry::redact_wallet(&wallet), token_id, "NFT claim completed");
+
+    let claim = Claim { fid, wallet: wallet.clone(), timestamp: progress.requested_at, token_id };
+    let _: () = con.hset("nft:claims", &wallet, serde_json::to_string(&claim)?).await.unwrap_or_default();
+
+    events::publish_in_background(redis_client.clone(), Event::NftMinted { wallet, fid, token_id });
+
+    // Sin FID (canales fuera de Farcaster) no hay a quién notificar por el Frame
+    if fid != 0 {
+        notifications::notify_in_background(
+            redis_client.clone(),
+            fid,
+            Notification::new(
+                &format!("nft-minted-{}", token_id),
+                "Your NFT is minted",
+                &format!("Qawakun NFT #{} is now in your wallet.", token_id),
+            ),
+        );
+    }
+    Ok(())
+}
+
+// Al arrancar: los claims que se quedaron a medias vuelven a la cola de jobs. Con
+// el job original todavía vivo la idempotency key devuelve ese, que lleva los datos
+// del usuario; si no, un claim que no llegó a firmar el mint se marca como fallido
+pub async fn resume_interrupted_claims(redis_client: &redis::Client) -> Result<usize> {
+    let mut con = redis_client.get_async_connection().await?;
+    let stored: Vec<String> = con.hvals(CLAIM_STATES_KEY).await?;
+    let mut resumed = 0;
+    for progress in stored.iter().filter_map(|s| serde_json::from_str::<ClaimProgress>(s).ok()) {
+        if progress.state.is_finished() {
+            continue;
+        }
+        let payload = serde_json::to_value(ClaimPayload {
+            fid: progress.fid,
+            wallet: progress.wallet.clone(),
+            timestamp: progress.requested_at,
+            user_data: None,
+        })?;
+        let enqueued = jobs::enqueue(redis_client, CLAIM_JOB, payload, Some(claim_idempotency_key(&progress.wallet))).await?;
+        info!(
+            wallet = %telemetry::redact_wallet(&progress.wallet),
+            state = ?progress.state,
+            job_id = %enqueued.job().id,
+            "resuming interrupted NFT claim"
+        );
+        resumed += 1;
+    }
+    Ok(resumed)
+}
+
+// NFT que sigue en la wallet del backend y a quién corresponde
+#[derive(Debug, Serialize)]
+pub struct StrandedToken {
+    pub token_id: u64,
+    pub wallet: Option<String>,
+    pub fid: Option<u64>,
+    // Estado del claim de esa wallet, si hay uno guardado
+    pub claim_state: Option<ClaimState>,
+    // La wallet ya tiene otro NFT (un claim repetido): este no se entrega
+    pub recipient_has_nft: bool,
+    pub deliverable: bool,
+}
+
+// Busca los NFTs que el flujo de mint y transferencia dejó en la wallet del backend
+pub async fn stranded_tokens(redis_client: &redis::Client, nft_manager: &NftManager) -> Result<Vec<StrandedToken>> {
+    let mut con = redis_client.get_async_connection().await?;
+    let mut stranded = Vec::new();
+    for held in nft_manager.held_tokens().await? {
+        let progress = match &held.wallet {
+            Some(wallet) => claim_progress(&mut con, wallet).await?,
+            None => None,
+        };
+        let recipient_has_nft = match held.wallet.as_deref().map(|w| w.parse::<Address>()) {
+            Some(Ok(address)) => !nft_manager.get_balance(address).await?.is_zero(),
+            _ => false,
+        };
+        let deliverable = held.wallet.as_deref().is_some_and(|w| w.parse::<Address>().is_ok()) && !recipient_has_nft;
+        stranded.push(StrandedToken {
+            token_id: held.token_id,
+            fid: held.fid.or(progress.as_ref().map(|p| p.fid)),
+            wallet: held.wallet,
+            claim_state: progress.map(|p| p.state),
+            recipient_has_nft,
+            deliverable,
+        });
+    }
+    Ok(stranded)
+}
+
+// Resultado de entregar un NFT varado: el job que lo transfiere o por qué no se
+// entrega ahora
+#[derive(Debug, Serialize)]
+pub struct StrandedDelivery {
+    pub token_id: u64,
+    pub wallet: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<&'static str>,
+}
+
+// Entrega los NFTs varados por el state machine del claim: se guarda el claim como
+// Minted con su token y se encola el job, que transfiere y completa el claim
+// (registro, evento y notificación) como si nunca se hubiera interrumpido. El
+// estado de cada wallet guarda un solo claim, así que se entrega un token por
+// wallet y el resto queda para otra pasada
+pub async fn deliver_stranded_tokens(redis_client: &redis::Client, nft_manager: &NftManager) -> Result<Vec<StrandedDelivery>> {
+    let mut con = redis_client.get_async_connection().await?;
+    let mut wallets = HashSet::new();
+    let mut deliveries = Vec::new();
+    for token in stranded_tokens(redis_client, nft_manager).await? {
+        let wallet = match (&token.wallet, token.deliverable) {
+            (Some(wallet), true) => wallet.clone(),
+            _ => continue,
+        };
+        let mut delivery = StrandedDelivery { token_id: token.token_id, wallet: wallet.clone(), job_id: None, skipped: None };
+
+        if !wallets.insert(wallet.to_lowercase()) {
+            delivery.skipped = Some("another token is being delivered to this wallet");
+            deliveries.push(delivery);
+            continue;
+        }
+        // El mismo lock que el job: no se pisa un claim que está avanzando
+        let lock = match acquire_claim_lock(&mut con, &wallet).await? {
+            Some(lock) => lock,
+            None => {
+                delivery.skipped = Some("claim in progress for this wallet");
+                deliveries.push(delivery);
+                continue;
+            },
+        };
+
+        let result = queue_stranded(&mut con, redis_client, &token, &wallet).await;
+        if let Err(e) = release_claim_lock(&mut con, &wallet, &lock).await {
+            warn!(wallet = %telemetry::redact_wallet(&wallet), error = %e, "error releasing claim lock");
+        }
+        match result? {
+            Ok(job_id) => {
+                info!(token_id = token.token_id, wallet = %telemetry::redact_wallet(&wallet), job_id = %job_id, "delivering stranded NFT");
+                delivery.job_id = Some(job_id);
+            },
+            Err(reason) => delivery.skipped = Some(reason),
+        }
+        deliveries.push(delivery);
+    }
+    Ok(deliveries)
+}
+
+// Con el lock de la wallet tomado: guarda el token como Minted y encola el job.
+// Devuelve el id del job que lo entrega, o por qué no se encola
+async fn queue_stranded(
+    con: &mut redis::aio::Connection,
+    redis_client: &redis::Client,
+    token: &StrandedToken,
+    wallet: &str,
+) -> Result<std::result::Result<String, &'static str>> {
+    let progress = claim_progress(con, wallet).await?;
+    let active = progress.as_ref().filter(|p| !p.state.is_finished());
+    match active {
+        // Un claim a medias de este mismo token ya sabe terminar; no se pisa
+        Some(p) if p.token_id == Some(token.token_id) && matches!(p.state, ClaimState::Minted | ClaimState::Transferring) => {},
+        Some(_) => return Ok(Err("wallet has another claim in progress")),
+        None => {
+            let now = Utc::now();
+            let mut progress = ClaimProgress {
+                wallet: wallet.to_string(),
+                fid: token.fid.unwrap_or_default(),
+                state: ClaimState::Minted,
+                mint_tx: progress.and_then(|p| p.mint_tx),
+                mint_nonce: None,
+                mint_raw: None,
+                token_id: Some(token.token_id),
+                transfer_tx: None,
+                error: None,
+                requested_at: now,
+                updated_at: now,
+            };
+            save_progress(con, &mut progress).await?;
+        },
+    }
+
+    let payload = serde_json::to_value(ClaimPayload {
+        fid: token.fid.unwrap_or_default(),
+        wallet: wallet.to_string(),
+        timestamp: Utc::now(),
+        user_data: None,
+    })?;
+    // Un job de entrega de este token ya en la cola vale; uno terminado no la repite
+    match jobs::enqueue(redis_client, CLAIM_JOB, payload, Some(delivery_idempotency_key(token.token_id))).await? {
+        Enqueued::Existing(job) if job.is_finished() => Ok(Err("a finished delivery job already exists for this token")),
+        enqueued => Ok(Ok(enqueued.job().id.clone())),
+    }
+}
+
+pub async fn check_wallet_has_nft(nft_manager: &NftManager, wallet: &str) -> Result<bool> {
     let wallet_address = wallet.parse::<Address>()?;
     let balance = nft_manager.get_balance(wallet_address).await?;
     Ok(!balance.is_zero())
@@ -81,6 +756,10 @@ pub async fn handle_nft_claim_post(
         return response;
     }
 
+    if is_paused(&redis_client, Control::Minting).await {
+        return HttpResponse::ServiceUnavailable().body("NFT minting is paused");
+    }
+
     // Verificar si ya tiene un NFT
     match check_wallet_has_nft(&nft_manager, &json_data.wallet).await {
         Ok(has_nft) => {
@@ -97,7 +776,10 @@ pub async fn handle_nft_claim_post(
 
     let mut con = match redis_client.get_async_connection().await {
         Ok(con) => con,
-        Err(_) => return HttpResponse::InternalServerError().body("Redis connection error"),
+        Err(_) => {
+            metrics::redis_error("api");
+            return HttpResponse::InternalServerError().body("Redis connection error");
+        },
     };
 
     // Buscar en todas las conversaciones, no solo en farcaster
@@ -106,8 +788,6 @@ pub async fn handle_nft_claim_post(
         format!("farcaster:conversation:{}", json_data.wallet), // conversación de farcaster
     ];
     
-    println!("🔍 Buscando conversaciones con patrones: {:?}", conversation_patterns);
-    
     let mut all_keys = Vec::new();
     for pattern in conversation_patterns.iter() {
         let keys: Vec<String> = redis::cmd("KEYS")
@@ -118,12 +798,11 @@ pub async fn handle_nft_claim_post(
         all_keys.extend(keys);
     }
 
-    println!("🔑 Claves encontradas: {:?}", all_keys);
+    debug!(keys = all_keys.len(), "conversation keys found");
 
     // Obtener la primera conversación que encontremos
     let conversation: Option<String> = if !all_keys.is_empty() {
         let result = con.get(&all_keys[0]).await.ok();
-        println!("💬 Conversación encontrada en clave: {}", all_keys[0]);
         result
     } else {
         None
@@ -132,7 +811,7 @@ pub async fn handle_nft_claim_post(
     if let Some(conv) = conversation {
         let user_count = conv.matches("user").count();
 
-        if user_count >= 6 {
+        if user_count >= REQUIRED_INTERACTIONS {
 
             let user_data = UserData {
                 username: format!("Farcaster User {}", json_data.fid),
@@ -147,76 +826,153 @@ pub async fn handle_nft_claim_post(
                 })),
             };
 
-            let to_address = match json_data.wallet.parse::<Address>() {
-                Ok(address) => address,
-                Err(_) => return HttpResponse::BadRequest().body("Invalid wallet address"),
-            };
+            if json_data.wallet.parse::<Address>().is_err() {
+                return HttpResponse::BadRequest().body("Invalid wallet address");
+            }
 
-            // Mintear el NFT
-            match nft_manager.mint_nft_with_encrypted_data(to_address, user_data).await {
-                Ok(receipt) => {
-                    println!("✅ NFT minteado y transferido exitosamente");
-                    // Obtener el token ID del evento de mint
-                    let token_id = receipt
-                        .logs
-                        .iter()
-                        .find(|log| log.topics[0] == H256::from(ethers::utils::keccak256("Transfer(address,address,uint256)")))
-                        .and_then(|log| {
-                            let bytes = log.topics[3].as_bytes();
-                            U256::from_big_endian(bytes).to_string().parse::<u64>().ok()
-                        });
-
-                    if let Some(token_id) = token_id {
-                        let claim = Claim {
-                            fid: json_data.fid,
-                            wallet: json_data.wallet.clone(),
-                            timestamp: json_data.timestamp,
-                            token_id,
-                        };
-
-                        let _: () = con.hset(
-                            "nft:claims",
-                            &json_data.wallet,
-                            serde_json::to_string(&claim).unwrap(),
-                        ).await.unwrap_or_default();
-
-                        HttpResponse::Ok().json(NFTClaimResponse {
-                            has_claimed: true,
-                            message: "NFT claim successful".to_string(),
-                            token_id: Some(token_id),
+            match enqueue_claim(&redis_client, json_data.fid, user_data, json_data.timestamp).await {
+                Ok(enqueued) => {
+                    let job = enqueued.job();
+                    let message = match &enqueued {
+                        Enqueued::Created(_) => "NFT claim queued",
+                        Enqueued::Existing(_) => "NFT claim already requested",
+                    };
+                    info!(wallet = %telemetry::redact_wallet(&json_data.wallet), job_id = %job.id, status = job.status.as_str(), "{}", message);
+                    HttpResponse::Accepted()
+                        .insert_header(("Location", format!("/jobs/{}", job.id)))
+                        .json(NFTClaimJobResponse {
+                            job_id: job.id.clone(),
+                            status: job.status.as_str(),
+                            message: message.to_string(),
                         })
-                    } else {
-                        HttpResponse::InternalServerError().body("Failed to get token ID")
-                    }
                 },
                 Err(e) => {
-                    if e.to_string().contains("User already has an NFT") {
-                        HttpResponse::BadRequest().json(NFTClaimResponse {
-                            has_claimed: true,
-                            message: "User already has an NFT".to_string(),
-                            token_id: None,
-                        })
-                    } else {
-                        println!("❌ Error detallado al mintear NFT: {:?}", e);
-                        let error_message = format!("Failed to mint NFT: {}", e);
-                        println!("🔍 Mensaje de error: {}", error_message);
-                        HttpResponse::InternalServerError().body(error_message)
-                    }
+                    error!(wallet = %telemetry::redact_wallet(&json_data.wallet), error = ?e, "failed to queue NFT claim");
+                    metrics::redis_error("api");
+                    HttpResponse::InternalServerError().body("Error queueing NFT claim")
                 }
             }
         } else {
             HttpResponse::BadRequest().json(NFTClaimResponse {
                 has_claimed: false,
-                message: format!("Not enough interactions. Current: {}, Required: 6", user_count),
+                message: format!("Not enough interactions. Current: {}, Required: {}", user_count, REQUIRED_INTERACTIONS),
                 token_id: None,
             })
         }
     } else {
-        println!("❌ No se encontró conversación para la wallet: {}", json_data.wallet);
+        warn!(wallet = %telemetry::redact_wallet(&json_data.wallet), "no conversation found for wallet");
         HttpResponse::BadRequest().json(NFTClaimResponse {
             has_claimed: false,
             message: format!("No conversation found for wallet: {}", json_data.wallet),
             token_id: None,
         })
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user() -> Address {
+        "0x00000000000000000000000000000000000000aa".parse().unwrap()
+    }
+
+    fn signer() -> Address {
+        "0x00000000000000000000000000000000000000bb".parse().unwrap()
+    }
+
+    fn progress(state: ClaimState) -> ClaimProgress {
+        ClaimProgress {
+            wallet: format!("{:?}", user()),
+            fid: 7,
+            state,
+            mint_tx: Some(format!("{:?}", H256::repeat_byte(1))),
+            mint_nonce: Some(3),
+            mint_raw: Some("0x01".to_string()),
+            token_id: None,
+            transfer_tx: None,
+            error: None,
+            requested_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn mint_to_user_completes_the_claim() {
+        let mut claim = progress(ClaimState::Minting);
+        assert!(mint_landed(&mut claim, TxResult::Succeeded((12, user())), user()).is_ok());
+        assert_eq!(claim.state, ClaimState::Completed);
+        assert_eq!(claim.token_id, Some(12));
+    }
+
+    #[test]
+    fn mint_to_signer_still_needs_the_transfer() {
+        let mut claim = progress(ClaimState::Minting);
+        assert!(mint_landed(&mut claim, TxResult::Succeeded((12, signer())), user()).is_ok());
+        assert_eq!(claim.state, ClaimState::Minted);
+        assert_eq!(claim.token_id, Some(12));
+    }
+
+    #[test]
+    fn reverted_or_dropped_mint_fails_the_claim() {
+        for (result, error) in [(TxResult::Reverted, "mint transaction reverted"), (TxResult::Dropped, "mint transaction dropped")] {
+            let mut claim = progress(ClaimState::Minting);
+            assert_eq!(mint_landed(&mut claim, result, user()), Err(error));
+            assert_eq!(claim.state, ClaimState::Failed);
+            assert_eq!(claim.error.as_deref(), Some(error));
+            assert_eq!(claim.token_id, None);
+            assert!(claim.state.is_finished());
+        }
+    }
+
+    #[test]
+    fn transfer_completes_or_goes_back_to_minted() {
+        let mut claim = ClaimProgress {
+            token_id: Some(12),
+            transfer_tx: Some(format!("{:?}", H256::repeat_byte(2))),
+            ..progress(ClaimState::Transferring)
+        };
+        let mut completed = claim.clone();
+        assert!(transfer_landed(&mut completed, TxResult::Succeeded(())).is_ok());
+        assert_eq!(completed.state, ClaimState::Completed);
+
+        // El NFT sigue en la wallet del backend: el reintento firma otra transferencia
+        assert_eq!(transfer_landed(&mut claim, TxResult::Dropped), Err("transfer transaction dropped"));
+        assert_eq!(claim.state, ClaimState::Minted);
+        assert_eq!(claim.transfer_tx, None);
+        assert_eq!(claim.token_id, Some(12));
+        assert!(!claim.state.is_finished());
+    }
+
+    #[test]
+    fn only_completed_and_failed_claims_are_finished() {
+        for state in [ClaimState::Requested, ClaimState::Minting, ClaimState::Minted, ClaimState::Transferring] {
+            assert!(!state.is_finished(), "{:?} should be resumed", state);
+        }
+        assert!(ClaimState::Completed.is_finished());
+        assert!(ClaimState::Failed.is_finished());
+    }
+
+    #[test]
+    fn claims_saved_before_the_signed_mint_still_load() {
+        // Formato anterior a guardar el nonce y la transacción firmada
+        let stored = r#"{
+            "wallet": "0x00000000000000000000000000000000000000aa",
+            "fid": 7,
+            "state": "minting",
+            "mint_tx": "0x0101010101010101010101010101010101010101010101010101010101010101",
+            "requested_at": "2024-05-01T00:00:00Z",
+            "updated_at": "2024-05-01T00:00:00Z"
+        }"#;
+        let claim: ClaimProgress = serde_json::from_str(stored).unwrap();
+        assert_eq!(claim.state, ClaimState::Minting);
+        assert_eq!(claim.mint_nonce, None);
+        assert_eq!(claim.mint_raw, None);
+        assert_eq!(tx_hash(&claim.mint_tx).unwrap(), H256::repeat_byte(1));
+    }
+
+    #[test]
+    fn delivery_key_is_per_token_not_per_wallet() {
+        assert_eq!(delivery_idempotency_key(12), "nft-deliver:12");
+        assert_ne!(delivery_idempotency_key(12), claim_idempotency_key(&format!("{:?}", user())));
+    }
+}
@@ -11,6 +11,11 @@ use ethers::{
 };
 use std::sync::Arc;
 use crate::api::auth::verify_token;
+use crate::events::{self, Event};
+use crate::farcaster::notifications::{self, Notification};
+use crate::metrics;
+use crate::telemetry;
+use tracing::{debug, error, info, warn};
 
 // Generar los bindings para el contrato de propuestas
 abigen!(
@@ -43,7 +48,37 @@ pub struct Proposal {
     pub contact: String,
     pub message_history: Vec<String>,
     pub timestamp: String,      // ISO timestamp
-    pub status: i32,           // 1: nueva, 2: en revisión, 3: en votación, 4: rechazada
+    pub status: i32,           // ProposalStatus como entero
+}
+
+// Estados de una propuesta; en JSON y en Redis se guardan como entero
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProposalStatus {
+    Submitted = 1,
+    UnderReview = 2,
+    Voting = 3,
+    Rejected = 4,
+}
+
+impl ProposalStatus {
+    pub fn from_i32(status: i32) -> Option<Self> {
+        match status {
+            1 => Some(Self::Submitted),
+            2 => Some(Self::UnderReview),
+            3 => Some(Self::Voting),
+            4 => Some(Self::Rejected),
+            _ => None,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Submitted => "submitted",
+            Self::UnderReview => "under review",
+            Self::Voting => "open for voting",
+            Self::Rejected => "rejected",
+        }
+    }
 }
 
 // Implementar manualmente para ContractProposal
@@ -166,6 +201,7 @@ impl ProposalManager {
     }
 
     // Modificar la función de indexación para usar la conversión
+    #[tracing::instrument(skip_all, fields(correlation_id = %telemetry::correlation_label()))]
     pub async fn index_proposal_from_backend(
         &self,
         proposal: &Proposal,
@@ -205,10 +241,11 @@ impl ProposalManager {
                     .collect())
                 .unwrap_or_default(),
             timestamp: contract_proposal.timestamp.to_string(),
-            status: 1, // Este campo podría venir de otra fuente
+            status: ProposalStatus::Submitted as i32, // Este campo podría venir de otra fuente
         })
     }
 
+    #[tracing::instrument(skip_all, fields(correlation_id = %telemetry::correlation_label()))]
     pub async fn index_proposal(
         &self,
         proposer: Address,
@@ -230,6 +267,7 @@ impl ProposalManager {
         Ok(receipt)
     }
 
+    #[tracing::instrument(skip_all, fields(correlation_id = %telemetry::correlation_label()))]
     pub async fn vote_proposal(
         &self,
         proposal_id: U256,
@@ -243,6 +281,7 @@ impl ProposalManager {
         Ok(receipt)
     }
 
+    #[tracing::instrument(skip_all, fields(correlation_id = %telemetry::correlation_label()))]
     pub async fn execute_monthly_selection(&self) -> anyhow::Result<TransactionReceipt> {
         let contract = &self.contract;
         let tx = contract.method::<_, ()>("executeMonthlySelection", ())?;
@@ -270,6 +309,22 @@ impl ProposalManager {
         Ok(proposals)
     }
 
+    pub async fn latest_block(&self) -> anyhow::Result<u64> {
+        Ok(self.provider.get_block_number().await?.as_u64())
+    }
+
+    // Eventos del contrato entre dos bloques, ambos incluidos
+    #[tracing::instrument(skip_all, fields(correlation_id = %telemetry::correlation_label()))]
+    pub async fn events_between(&self, from_block: u64, to_block: u64) -> anyhow::Result<Vec<ProposalContractEvents>> {
+        let events = self.contract
+            .events()
+            .from_block(from_block)
+            .to_block(to_block)
+            .query()
+            .await?;
+        Ok(events)
+    }
+
     pub async fn check_configuration(&self) -> anyhow::Result<()> {
         // Verificar que tenemos un provider válido
         let _ = self.provider.get_chainid().await?;
@@ -317,39 +372,111 @@ impl ProposalManager {
         Ok(response_proposals)
     }
 
-    pub async fn get_all_winning_proposals(&self) -> Result<Vec<ContractProposalResponse>, Box<dyn std::error::Error>> {
+    pub async fn get_monthly_winners(&self, month: u64) -> Result<Vec<ContractProposalResponse>, Box<dyn std::error::Error>> {
         let contract = ProposalContract::new(
             self.contract.address(),
             Arc::clone(&self.provider)
         );
 
+        let mut month_winners = Vec::new();
+        let winners = contract.get_winning_proposals(U256::from(month)).call().await?;
+        for winner_id in winners {
+            if let Ok(proposal) = contract.get_proposal(winner_id).call().await {
+                month_winners.push(ContractProposalResponse {
+                    id: winner_id,
+                    proposer: proposal.proposer,
+                    proposal_type: proposal.proposal_type,
+                    description: proposal.description,
+                    conversation: proposal.conversation,
+                    timestamp: proposal.timestamp,
+                    approval_count: proposal.approval_count,
+                    rejection_count: proposal.rejection_count,
+                    status: proposal.status,
+                });
+            }
+        }
+
+        Ok(month_winners)
+    }
+
+    pub async fn get_all_winning_proposals(&self) -> Result<Vec<ContractProposalResponse>, Box<dyn std::error::Error>> {
         let mut all_winners = Vec::new();
         let current_month = chrono::Utc::now().month() as u64;
         
         for month in 1..=current_month {
-            let month_u256 = U256::from(month);
-            let winners = contract.get_winning_proposals(month_u256).call().await?;
-            for winner_id in winners {
-                if let Ok(proposal) = contract.get_proposal(winner_id).call().await {
-                    all_winners.push(ContractProposalResponse {
-                        id: winner_id,
-                        proposer: proposal.proposer,
-                        proposal_type: proposal.proposal_type,
-                        description: proposal.description,
-                        conversation: proposal.conversation,
-                        timestamp: proposal.timestamp,
-                        approval_count: proposal.approval_count,
-                        rejection_count: proposal.rejection_count,
-                        status: proposal.status,
-                    });
-                }
-            }
+            all_winners.extend(self.get_monthly_winners(month).await?);
         }
         
         Ok(all_winners)
     }
 }
 
+pub fn status_label(status: i32) -> Option<&'static str> {
+    ProposalStatus::from_i32(status).map(ProposalStatus::label)
+}
+
+// Avisa al autor por el Frame; el id incluye el estado para que cada cambio llegue una vez
+pub fn notify_status_change(redis_client: &redis::Client, proposal: &Proposal) {
+    events::publish_in_background(redis_client.clone(), Event::ProposalStatusChanged {
+        wallet: proposal.wallet.clone(),
+        fid: proposal.fid,
+        status: proposal.status,
+        label: status_label(proposal.status).map(str::to_string),
+    });
+    if let Some(label) = status_label(proposal.status) {
+        notifications::notify_in_background(
+            redis_client.clone(),
+            proposal.fid,
+            Notification::new(
+                &format!("proposal-{}-{}", proposal.wallet.to_lowercase(), proposal.status),
+                "Proposal update",
+                &format!("Your {} proposal is now {}.", proposal.proposal_type.to_lowercase(), label),
+            ),
+        );
+    }
+}
+
+// Tras la selección mensual: primero a los autores ganadores (FID según la propuesta
+// guardada con su wallet) y después al resto de suscriptores
+pub async fn notify_monthly_winners(redis_client: redis::Client, winners: Vec<Address>) {
+    let period = Utc::now().format("%Y-%m").to_string();
+    let result: anyhow::Result<()> = async {
+        let mut con = redis_client.get_async_connection().await?;
+        let proposals: Vec<String> = con.hvals("proposals").await?;
+        let winner_fids: Vec<u64> = proposals
+            .iter()
+            .filter_map(|p| serde_json::from_str::<Proposal>(p).ok())
+            .filter(|p| p.fid != 0 && p.wallet.parse::<Address>().map(|w| winners.contains(&w)).unwrap_or(false))
+            .map(|p| p.fid)
+            .collect();
+
+        // Un ganador que falla no corta el aviso a los demás ni el broadcast
+        for fid in &winner_fids {
+            let notification = Notification::new(
+                &format!("winner-{}-{}", period, fid),
+                "Your proposal won!",
+                "Your proposal was selected as one of this month's winners.",
+            );
+            match notifications::notify(&redis_client, *fid, &notification).await {
+                Ok(delivery) => debug!(fid, delivery = delivery.as_str(), "winner notification"),
+                Err(e) => warn!(fid, error = %e, "failed to notify winner"),
+            }
+        }
+
+        let notification = Notification::new(
+            &format!("winners-{}", period),
+            "Monthly winners selected",
+            "This month's winning proposals are in. See what joins the world of Qawakun.",
+        );
+        notifications::broadcast(&redis_client, &notification, &winner_fids).await?;
+        Ok(())
+    }.await;
+
+    if let Err(e) = result {
+        warn!(error = %e, "error sending monthly winner notifications");
+    }
+}
+
 #[derive(Serialize, Debug)]
 pub struct ProposalSummary {
     wallet: String,
@@ -363,23 +490,21 @@ pub async fn handle_proposal_post(
     json_data: web::Json<Proposal>,
     redis_client: web::Data<redis::Client>,
 ) -> impl Responder {
-    println!("📝 Recibiendo propuesta: {:?}", json_data);  // Debug
-
     if let Err(response) = verify_token(&req).await {
-        println!("❌ Error de autenticación");  // Debug
         return response;
     }
 
     let mut con = match redis_client.get_async_connection().await {
         Ok(con) => con,
         Err(e) => {
-            println!("❌ Error de conexión Redis: {:?}", e);  // Debug
+            error!(error = %e, "Redis connection error");
+            metrics::redis_error("api");
             return HttpResponse::InternalServerError().body("Redis connection error");
         }
     };
 
     let proposal = json_data.into_inner();
-    println!("📋 Propuesta procesada: {:?}", proposal);  // Debug
+    debug!(wallet = %telemetry::redact_wallet(&proposal.wallet), proposal_type = %proposal.proposal_type, "saving proposal");
 
     // Usar wallet como clave
     if let Err(e) = con.hset::<_, _, _, ()>(
@@ -387,11 +512,11 @@ pub async fn handle_proposal_post(
         &proposal.wallet,
         serde_json::to_string(&proposal).unwrap(),
     ).await {
-        println!("❌ Error guardando propuesta: {:?}", e);  // Debug
+        error!(error = %e, "error saving proposal");
         return HttpResponse::InternalServerError().body("Error saving proposal");
     }
 
-    println!("✅ Propuesta guardada exitosamente");  // Debug
+    info!(wallet = %telemetry::redact_wallet(&proposal.wallet), "proposal saved");
     HttpResponse::Ok().json(proposal)
 }
 
@@ -405,7 +530,10 @@ pub async fn handle_proposals_get(
 
     let mut con = match redis_client.get_async_connection().await {
         Ok(con) => con,
-        Err(_) => return HttpResponse::InternalServerError().body("Redis connection error"),
+        Err(_) => {
+            metrics::redis_error("api");
+            return HttpResponse::InternalServerError().body("Redis connection error");
+        },
     };
 
     // Obtener todas las propuestas
@@ -441,29 +569,24 @@ pub async fn handle_proposal_by_wallet_get(
     wallet: web::Path<String>,
     redis_client: web::Data<redis::Client>,
 ) -> impl Responder {
-    println!("📥 GET /proposals/{} - Buscando propuestas", wallet.as_ref());
-
     if let Err(response) = verify_token(&req).await {
-        println!("❌ Error de autenticación");
         return response;
     }
 
     let mut con = match redis_client.get_async_connection().await {
         Ok(con) => con,
         Err(e) => {
-            println!("❌ Error de conexión Redis: {:?}", e);
+            error!(error = %e, "Redis connection error");
+            metrics::redis_error("api");
             return HttpResponse::InternalServerError().body("Redis connection error");
         }
     };
 
     // Obtener propuesta directamente por wallet
     let proposal: Option<String> = match con.hget("proposals", wallet.as_ref()).await {
-        Ok(p) => {
-            println!("✅ Propuesta encontrada: {:?}", p);
-            p
-        },
+        Ok(p) => p,
         Err(e) => {
-            println!("❌ Error obteniendo propuesta: {:?}", e);
+            error!(error = %e, "error getting proposal");
             None
         }
     };
@@ -471,20 +594,14 @@ pub async fn handle_proposal_by_wallet_get(
     match proposal {
         Some(proposal_str) => {
             match serde_json::from_str::<Proposal>(&proposal_str) {
-                Ok(proposal) => {
-                    println!("✅ Propuesta parseada: {:?}", proposal);
-                    HttpResponse::Ok().json(vec![proposal])
-                },
+                Ok(proposal) => HttpResponse::Ok().json(vec![proposal]),
                 Err(e) => {
-                    println!("⚠️ Error parseando propuesta: {:?}", e);
+                    warn!(error = %e, "error parsing proposal");
                     HttpResponse::Ok().json(Vec::<Proposal>::new())
                 }
             }
         },
-        None => {
-            println!("ℹ️ No se encontraron propuestas para la wallet");
-            HttpResponse::Ok().json(Vec::<Proposal>::new())
-        }
+        None => HttpResponse::Ok().json(Vec::<Proposal>::new()),
     }
 }
 
@@ -498,13 +615,16 @@ pub async fn handle_proposal_status_update(
     }
 
     let (proposal_id, new_status) = path.into_inner();
-    if !(1..=4).contains(&new_status) {
+    if ProposalStatus::from_i32(new_status).is_none() {
         return HttpResponse::BadRequest().body("Invalid status");
     }
 
     let mut con = match redis_client.get_async_connection().await {
         Ok(con) => con,
-        Err(_) => return HttpResponse::InternalServerError().body("Redis connection error"),
+        Err(_) => {
+            metrics::redis_error("api");
+            return HttpResponse::InternalServerError().body("Redis connection error");
+        },
     };
 
     let proposal_str: Option<String> = match con.hget("proposals", &proposal_id).await {
@@ -519,6 +639,7 @@ pub async fn handle_proposal_status_update(
                 Err(_) => return HttpResponse::InternalServerError().body("Error parsing proposal"),
             };
             
+            let changed = proposal.status != new_status;
             proposal.status = new_status;
 
             let proposal_json = serde_json::to_string(&proposal).unwrap();
@@ -530,6 +651,9 @@ pub async fn handle_proposal_status_update(
                 return HttpResponse::InternalServerError().body("Error updating proposal");
             }
 
+            if changed {
+                notify_status_change(&redis_client, &proposal);
+            }
             HttpResponse::Ok().json(proposal)
         },
         None => HttpResponse::NotFound().body("Proposal not found"),
@@ -0,0 +1,121 @@
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use std::env;
+use crate::api::auth;
+use crate::farcaster::notifications;
+use crate::farcaster::webhook::{self, Ingested};
+use crate::telegram;
+use crate::metrics;
+use tracing::{debug, error, info, warn};
+
+// Webhooks `cast.created` (formato Neynar); las menciones se encolan para el worker
+pub async fn handle_farcaster_webhook(
+    req: HttpRequest,
+    body: web::Bytes,
+    redis_client: web::Data<redis::Client>,
+) -> impl Responder {
+    let secret = match env::var("FARCASTER_WEBHOOK_SECRET") {
+        Ok(secret) if !secret.is_empty() => secret,
+        _ => return HttpResponse::ServiceUnavailable().body("Farcaster webhook not configured"),
+    };
+
+    let signature = req.headers()
+        .get(webhook::SIGNATURE_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    if !webhook::verify_signature(&secret, &body, signature) {
+        warn!("invalid Farcaster webhook signature");
+        return HttpResponse::Unauthorized().body("Invalid signature");
+    }
+
+    match webhook::ingest(&redis_client, &body).await {
+        Ok(Ingested::Queued) => {
+            info!("Farcaster cast queued from webhook");
+            HttpResponse::Ok().json(serde_json::json!({ "status": "queued" }))
+        },
+        Ok(Ingested::Duplicate) => {
+            debug!("duplicate Farcaster webhook");
+            HttpResponse::Ok().json(serde_json::json!({ "status": "duplicate" }))
+        },
+        Ok(Ingested::Ignored) => HttpResponse::Ok().json(serde_json::json!({ "status": "ignored" })),
+        Err(e) => match e.downcast_ref::<redis::RedisError>() {
+            Some(_) => {
+                error!(error = %e, "error queueing Farcaster webhook");
+                metrics::redis_error("webhooks");
+                HttpResponse::InternalServerError().body("Error queueing cast")
+            },
+            None => HttpResponse::BadRequest().body(format!("Invalid payload: {}", e)),
+        },
+    }
+}
+
+// Eventos del Frame (frame_added, notifications_enabled, ...) firmados con una app key del usuario
+pub async fn handle_frame_webhook(
+    body: web::Bytes,
+    redis_client: web::Data<redis::Client>,
+) -> impl Responder {
+    let event = match notifications::parse_event(&body) {
+        Ok(event) => event,
+        Err(e) => {
+            warn!(error = %e, "invalid Frame webhook");
+            return HttpResponse::BadRequest().json(serde_json::json!({ "success": false, "error": e.to_string() }));
+        }
+    };
+
+    match notifications::verify_app_key(event.fid, &event.key).await {
+        Ok(true) => {},
+        Ok(false) => {
+            warn!(fid = event.fid, "Frame webhook signed with an unknown app key");
+            return HttpResponse::Unauthorized().json(serde_json::json!({ "success": false, "error": "Invalid app key" }));
+        },
+        Err(e) => {
+            error!(fid = event.fid, error = %e, "error verifying Frame app key");
+            return HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": "Error verifying app key" }));
+        },
+    }
+
+    match notifications::apply_event(&redis_client, &event).await {
+        Ok(()) => HttpResponse::Ok().json(serde_json::json!({ "success": true })),
+        Err(e) => {
+            error!(fid = event.fid, error = %e, "error storing Frame notification details");
+            metrics::redis_error("webhooks");
+            HttpResponse::InternalServerError().json(serde_json::json!({ "success": false, "error": "Error storing notification details" }))
+        }
+    }
+}
+
+// Updates de la Bot API en modo webhook; se encolan para el worker de Telegram
+pub async fn handle_telegram_webhook(
+    req: HttpRequest,
+    body: web::Bytes,
+    redis_client: web::Data<redis::Client>,
+) -> impl Responder {
+    let secret = match telegram::webhook::webhook_secret() {
+        Some(secret) => secret,
+        None => return HttpResponse::ServiceUnavailable().body("Telegram webhook not configured"),
+    };
+
+    let token = req.headers()
+        .get(telegram::webhook::SECRET_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("");
+    if !auth::verify_secret(&secret, token) {
+        warn!("invalid Telegram webhook secret");
+        return HttpResponse::Unauthorized().body("Invalid secret");
+    }
+
+    match telegram::webhook::ingest(&redis_client, &body).await {
+        Ok(true) => HttpResponse::Ok().json(serde_json::json!({ "status": "queued" })),
+        Ok(false) => {
+            debug!("duplicate Telegram update");
+            HttpResponse::Ok().json(serde_json::json!({ "status": "duplicate" }))
+        },
+        Err(e) => match e.downcast_ref::<redis::RedisError>() {
+            Some(_) => {
+                error!(error = %e, "error queueing Telegram update");
+                metrics::redis_error("webhooks");
+                HttpResponse::InternalServerError().body("Error queueing update")
+            },
+            None => HttpResponse::BadRequest().body(format!("Invalid payload: {}", e)),
+        },
+    }
+}
@@ -0,0 +1,402 @@
+use anyhow::Result;
+use chrono::Utc;
+use ethers::types::{Address, U256};
+use redis::AsyncCommands;
+use serde_json::json;
+use std::env;
+use std::sync::Arc;
+use crate::api::admin::{is_paused, Control};
+use crate::api::proposals::{status_label, Proposal, ProposalManager};
+use crate::events::{self, Event};
+use crate::metrics;
+use crate::social::wallet::{self, Linked};
+use crate::social::{AgentPipeline, Author, InboundMessage, Outcome, SocialChannel};
+use crate::telemetry;
+use crate::workers::Shutdown;
+use tracing::{debug, error, info, warn};
+use super::channel::{DiscordChannel, DiscordEvent};
+use super::client::{CommandOption, DiscordClient, Interaction, Message};
+use super::gateway::{Dispatch, Gateway};
+
+pub const PROPOSAL_TYPES: [&str; 3] = ["WORLD", "CHARACTERS", "LAWS"];
+// Flexibilidad por defecto (1-10) de las propuestas enviadas desde el chat
+const DEFAULT_FLEXIBILITY: i32 = 5;
+const MAX_LISTED_PROPOSALS: usize = 10;
+const MAX_LISTED_DESCRIPTION_CHARS: usize = 120;
+const FAILED_REPLY: &str = "Qawakun lost the thread for a moment. Please try again.";
+
+// Definición de los slash commands (tipos de opción: 1 subcomando, 3 texto, 4 entero, 5 booleano)
+fn command_definitions() -> serde_json::Value {
+    let proposal_types: Vec<serde_json::Value> = PROPOSAL_TYPES
+        .iter()
+        .map(|kind| json!({ "name": kind, "value": kind }))
+        .collect();
+
+    json!([
+        {
+            "name": "qawakun",
+            "description": "Talk to Qawakun",
+            "options": [{
+                "type": 1, "name": "ask", "description": "Ask Qawakun about the story",
+                "options": [{ "type": 3, "name": "question", "description": "What you want to ask", "required": true }],
+            }],
+        },
+        {
+            "name": "proposal",
+            "description": "Proposals to shape the world of Qawakun",
+            "options": [{
+                "type": 1, "name": "submit", "description": "Submit a proposal with your linked wallet",
+                "options": [
+                    { "type": 3, "name": "type", "description": "What it changes", "required": true, "choices": proposal_types },
+                    { "type": 3, "name": "idea", "description": "Your proposal", "required": true },
+                ],
+            }],
+        },
+        {
+            "name": "proposals",
+            "description": "Proposals of this month",
+            "options": [{ "type": 1, "name": "list", "description": "List submitted proposals and those open for voting" }],
+        },
+        {
+            "name": "vote",
+            "description": "Cast the council's on-chain vote on a proposal",
+            "options": [
+                { "type": 4, "name": "proposal", "description": "On-chain proposal id", "required": true, "min_value": 0 },
+                { "type": 5, "name": "support", "description": "Approve (true) or reject (false)", "required": true },
+            ],
+        },
+        {
+            "name": "wallet",
+            "description": "Link your wallet to your Discord account",
+            "options": [
+                {
+                    "type": 1, "name": "link", "description": "Get a message to sign with your wallet",
+                    "options": [{ "type": 3, "name": "address", "description": "Your wallet address", "required": true }],
+                },
+                {
+                    "type": 1, "name": "verify", "description": "Send the signed message",
+                    "options": [{ "type": 3, "name": "signature", "description": "Signature of the message", "required": true }],
+                },
+            ],
+        },
+    ])
+}
+
+fn option<'a>(options: &'a [CommandOption], name: &str) -> Option<&'a serde_json::Value> {
+    options.iter().find(|option| option.name == name).and_then(|option| option.value.as_ref())
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max - 1).collect();
+    truncated.push('…');
+    truncated
+}
+
+// Menciones, hilos y slash commands de Discord; la conversación pasa por el
+// pipeline común y las propuestas por el ProposalManager y el hash `proposals`
+pub struct DiscordBot {
+    channel: DiscordChannel,
+    pipeline: AgentPipeline,
+    redis_client: redis::Client,
+    proposal_manager: Option<Arc<ProposalManager>>,
+    // Rol autorizado a /vote: el voto lo firma la wallet del backend
+    council_role: Option<String>,
+}
+
+impl DiscordBot {
+    pub fn new(client: DiscordClient, redis_client: redis::Client, proposal_manager: Option<Arc<ProposalManager>>) -> Self {
+        Self {
+            channel: DiscordChannel::new(client, redis_client.clone()),
+            pipeline: AgentPipeline::new(redis_client.clone()),
+            redis_client,
+            proposal_manager,
+            council_role: env::var("DISCORD_COUNCIL_ROLE_ID").ok().filter(|role| !role.is_empty()),
+        }
+    }
+
+    pub fn client(&self) -> &DiscordClient {
+        self.channel.client()
+    }
+
+    // Con DISCORD_GUILD_ID los comandos se registran solo en ese servidor y están
+    // disponibles al momento
+    pub async fn register_commands(&self) -> Result<()> {
+        let guild_id = env::var("DISCORD_GUILD_ID").ok().filter(|id| !id.is_empty());
+        self.client().register_commands(guild_id.as_deref(), &command_definitions()).await?;
+        info!(guild = ?guild_id, "Discord slash commands registered");
+        Ok(())
+    }
+
+    async fn handle(&self, dispatch: Dispatch) {
+        let result = match dispatch {
+            Dispatch::MessageCreate(message) => self.handle_message(message).await,
+            Dispatch::InteractionCreate(interaction) => self.handle_interaction(interaction).await,
+        };
+        if let Err(e) = result {
+            error!(error = %e, "failed to handle Discord event");
+        }
+    }
+
+    async fn handle_message(&self, message: Message) -> Result<()> {
+        let message = match self.channel.normalize(message).await? {
+            Some(message) => message,
+            None => return Ok(()),
+        };
+        // Antes de marcarlo: pausado no se consume la marca del mensaje
+        if is_paused(&self.redis_client, Control::DiscordReplies).await {
+            info!(message_id = %message.id, "replies paused, skipping Discord message");
+            metrics::mention("discord", "paused");
+            return Ok(());
+        }
+        if !self.pipeline.claim(&self.channel, &message.id).await? {
+            debug!(message_id = %message.id, "Discord message already handled");
+            return Ok(());
+        }
+
+        info!(message_id = %message.id, "Discord mention received");
+
+        if let Err(e) = self.pipeline.respond(&self.channel, &message).await {
+            error!(message_id = %message.id, error = %e, "failed to answer Discord message");
+            metrics::mention("discord", "failed");
+        }
+        Ok(())
+    }
+
+    async fn handle_interaction(&self, interaction: Interaction) -> Result<()> {
+        let data = match (&interaction.data, interaction.kind) {
+            (Some(data), 2) => data.clone(),
+            _ => return Ok(()),
+        };
+        let user = match interaction.author() {
+            Some(user) => Author { id: user.id.clone(), handle: Some(user.username.clone()) },
+            None => return Ok(()),
+        };
+        // Subcomando ("ask", "submit", ...) y sus opciones
+        let (subcommand, options) = match data.options.first() {
+            Some(first) if !first.options.is_empty() || first.value.is_none() => (first.name.as_str(), first.options.as_slice()),
+            _ => ("", data.options.as_slice()),
+        };
+        debug!(command = %data.name, subcommand, "Discord command");
+        metrics::mention("discord", "command");
+
+        if is_paused(&self.redis_client, Control::DiscordReplies).await {
+            self.client().defer_interaction(&interaction, true).await?;
+            return self.channel.send_interaction(&interaction.token, "Qawakun is resting right now. Please try again later.").await;
+        }
+
+        // Lo relacionado con la wallet solo lo ve quien usa el comando
+        let ephemeral = matches!(data.name.as_str(), "wallet" | "proposal" | "vote");
+        self.client().defer_interaction(&interaction, ephemeral).await?;
+
+        let result = match (data.name.as_str(), subcommand) {
+            ("qawakun", "ask") => {
+                let question = option(options, "question").and_then(|v| v.as_str()).unwrap_or("");
+                return self.ask(&interaction, question).await;
+            },
+            ("proposal", "submit") => {
+                let kind = option(options, "type").and_then(|v| v.as_str()).unwrap_or("");
+                let idea = option(options, "idea").and_then(|v| v.as_str()).unwrap_or("");
+                self.submit_proposal(&user, kind, idea).await
+            },
+            ("proposals", "list") => self.list_proposals().await,
+            ("vote", _) => {
+                let proposal = option(options, "proposal").and_then(|v| v.as_u64());
+                let support = option(options, "support").and_then(|v| v.as_bool());
+                self.vote(&interaction, proposal, support).await
+            },
+            ("wallet", "link") => {
+                let address = option(options, "address").and_then(|v| v.as_str()).unwrap_or("");
+                self.link(&user, address).await
+            },
+            ("wallet", "verify") => {
+                let signature = option(options, "signature").and_then(|v| v.as_str()).unwrap_or("");
+                self.verify(&user, signature).await
+            },
+            _ => Ok("Unknown command.".to_string()),
+        };
+
+        let reply = result.unwrap_or_else(|e| {
+            error!(command = %data.name, error = %e, "Discord command failed");
+            metrics::mention("discord", "failed");
+            FAILED_REPLY.to_string()
+        });
+        self.channel.send_interaction(&interaction.token, &reply).await
+    }
+
+    async fn ask(&self, interaction: &Interaction, question: &str) -> Result<()> {
+        let message: InboundMessage<DiscordEvent> = match self.channel.interaction_message(interaction, question) {
+            Some(message) => message,
+            None => return Ok(()),
+        };
+        match self.pipeline.respond(&self.channel, &message).await {
+            Ok(Outcome::Replied(_)) => Ok(()),
+            Ok(Outcome::Spam) => self.channel.send_interaction(&interaction.token, "Qawakun stays silent.").await,
+            Err(e) => {
+                error!(interaction_id = %interaction.id, error = %e, "failed to answer Discord question");
+                metrics::mention("discord", "failed");
+                self.channel.send_interaction(&interaction.token, FAILED_REPLY).await
+            },
+        }
+    }
+
+    // Se guarda en el mismo hash `proposals` que POST /proposals, con la wallet vinculada
+    async fn submit_proposal(&self, user: &Author, kind: &str, idea: &str) -> Result<String> {
+        let proposal_type = kind.to_uppercase();
+        if !PROPOSAL_TYPES.contains(&proposal_type.as_str()) || idea.trim().is_empty() {
+            return Ok("Choose WORLD, CHARACTERS or LAWS and describe your idea.".to_string());
+        }
+        let wallet = match wallet::linked_wallet(&self.redis_client, self.channel.name(), &user.id).await? {
+            Some(wallet) => wallet,
+            None => return Ok("Link your wallet first with `/wallet link`.".to_string()),
+        };
+
+        let proposal = Proposal {
+            wallet: wallet.clone(),
+            fid: 0,
+            proposal_type,
+            description: idea.trim().to_string(),
+            flexibility: DEFAULT_FLEXIBILITY,
+            contact: format!("discord:{}", user.display()),
+            message_history: Vec::new(),
+            timestamp: Utc::now().to_rfc3339(),
+            status: 1,
+        };
+
+        let mut con = self.redis_client.get_async_connection().await?;
+        let _: () = con.hset("proposals", &wallet, serde_json::to_string(&proposal)?).await?;
+        info!(user = %user.id, wallet = %telemetry::redact_wallet(&wallet), proposal_type = %proposal.proposal_type, "proposal saved from Discord");
+        events::publish_in_background(self.redis_client.clone(), Event::ProposalSubmitted {
+            wallet,
+            fid: 0,
+            proposal_type: proposal.proposal_type,
+            channel: self.channel.name().to_string(),
+        });
+        Ok("Your proposal was submitted. The council will review it soon.".to_string())
+    }
+
+    // Propuestas enviadas (Redis) y las del mes abiertas a votación en el contrato
+    async fn list_proposals(&self) -> Result<String> {
+        let mut con = self.redis_client.get_async_connection().await?;
+        let stored: Vec<String> = con.hvals("proposals").await?;
+        let mut submitted: Vec<Proposal> = stored
+            .iter()
+            .filter_map(|p| serde_json::from_str::<Proposal>(p).ok())
+            .collect();
+        submitted.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+
+        let mut lines = vec!["**Submitted proposals**".to_string()];
+        if submitted.is_empty() {
+            lines.push("None yet.".to_string());
+        }
+        for proposal in submitted.iter().take(MAX_LISTED_PROPOSALS) {
+            lines.push(format!(
+                "• [{}] {} ({})",
+                proposal.proposal_type,
+                truncate(&proposal.description, MAX_LISTED_DESCRIPTION_CHARS),
+                status_label(proposal.status).unwrap_or("unknown"),
+            ));
+        }
+
+        if let Some(proposal_manager) = &self.proposal_manager {
+            lines.push(String::new());
+            lines.push("**Open for voting this month**".to_string());
+            let voting = proposal_manager.get_current_month_proposals().await
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
+            if voting.is_empty() {
+                lines.push("None yet.".to_string());
+            }
+            for proposal in voting.iter().take(MAX_LISTED_PROPOSALS) {
+                lines.push(format!(
+                    "• #{} [{}] {} (👍 {} / 👎 {})",
+                    proposal.id,
+                    PROPOSAL_TYPES.get(proposal.proposal_type as usize).unwrap_or(&"?"),
+                    truncate(&proposal.description, MAX_LISTED_DESCRIPTION_CHARS),
+                    proposal.approval_count,
+                    proposal.rejection_count,
+                ));
+            }
+        }
+
+        Ok(lines.join("\n"))
+    }
+
+    async fn vote(&self, interaction: &Interaction, proposal: Option<u64>, support: Option<bool>) -> Result<String> {
+        let (proposal, support) = match (proposal, support) {
+            (Some(proposal), Some(support)) => (proposal, support),
+            _ => return Ok("Give the proposal id and whether you support it.".to_string()),
+        };
+        let proposal_manager = match &self.proposal_manager {
+            Some(proposal_manager) => proposal_manager,
+            None => return Ok("Voting is not available right now.".to_string()),
+        };
+        match &self.council_role {
+            Some(role) if interaction.roles().contains(role) => {},
+            _ => return Ok("Only council members can vote: the vote is cast on-chain by Qawakun's wallet.".to_string()),
+        }
+
+        match proposal_manager.vote_proposal(U256::from(proposal), support).await {
+            Ok(receipt) => {
+                info!(proposal, support, tx_hash = ?receipt.transaction_hash, "council vote cast from Discord");
+                Ok(format!("Vote cast on proposal #{} (tx {:?}).", proposal, receipt.transaction_hash))
+            },
+            Err(e) => {
+                warn!(proposal, error = %e, "Discord vote failed");
+                Ok(format!("The vote on proposal #{} failed: {}", proposal, e))
+            },
+        }
+    }
+
+    async fn link(&self, user: &Author, address: &str) -> Result<String> {
+        if address.parse::<Address>().is_err() {
+            return Ok("That is not a valid wallet address.".to_string());
+        }
+
+        let challenge = wallet::start_link(&self.redis_client, self.channel.name(), &user.id, address).await?;
+        Ok(format!(
+            "Sign this exact message with your wallet (personal_sign) and send the signature with `/wallet verify` \
+             within 10 minutes:\n```\n{}\n```",
+            challenge
+        ))
+    }
+
+    async fn verify(&self, user: &Author, signature: &str) -> Result<String> {
+        Ok(match wallet::verify_link(&self.redis_client, self.channel.name(), &user.id, signature).await? {
+            Linked::Wallet(wallet) => {
+                info!(user = %user.id, wallet = %telemetry::redact_wallet(&wallet), "Discord wallet linked");
+                format!("Wallet {} linked. You can now submit proposals.", wallet)
+            },
+            Linked::NoChallenge => "There is no pending link. Start with `/wallet link`.".to_string(),
+            Linked::InvalidSignature => "That signature does not match the wallet. Sign the exact message and try again.".to_string(),
+        })
+    }
+}
+
+// Conexión al gateway; cada evento se atiende en su propia tarea para no retrasar
+// los heartbeats mientras responde el LLM
+pub async fn start_gateway(bot: Arc<DiscordBot>, mut shutdown: Shutdown) -> Result<()> {
+    info!("starting Discord gateway");
+
+    while is_paused(&bot.redis_client, Control::DiscordWorker).await {
+        info!("Discord paused, gateway not connected");
+        if shutdown.sleep(tokio::time::Duration::from_secs(30)).await {
+            return Ok(());
+        }
+    }
+
+    let gateway = Gateway::new(bot.client().token());
+    gateway.run(&mut shutdown, |dispatch| {
+        let bot = Arc::clone(&bot);
+        tokio::spawn(async move {
+            if is_paused(&bot.redis_client, Control::DiscordWorker).await {
+                return;
+            }
+            bot.handle(dispatch).await;
+        });
+    }).await?;
+
+    info!("Discord gateway stopped");
+    Ok(())
+}
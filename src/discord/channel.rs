@@ -0,0 +1,170 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use redis::AsyncCommands;
+use crate::social::{Author, Batch, InboundMessage, SocialChannel, ThreadEntry};
+use crate::text::split_numbered;
+use super::client::{DiscordClient, Interaction, Message, User};
+
+// Hilos en los que ya habló el bot: ahí contesta sin que lo mencionen
+const THREADS_KEY: &str = "discord:threads";
+// Mensajes anteriores del canal o hilo que se pasan como contexto
+const MAX_THREAD_MESSAGES: usize = 20;
+// Límite de caracteres de un mensaje de Discord
+const MAX_MESSAGE_CHARS: usize = 2000;
+
+// Dónde va la respuesta: al canal como reply, o a la respuesta diferida de un slash command
+#[derive(Debug, Clone)]
+pub enum DiscordEvent {
+    Message { channel_id: String, message_id: String },
+    Interaction { token: String },
+}
+
+fn author(user: &User) -> Author {
+    Author { id: user.id.clone(), handle: Some(user.username.clone()).filter(|name| !name.is_empty()) }
+}
+
+// Menciones y conversaciones en hilos de los servidores; los DMs se ignoran
+pub struct DiscordChannel {
+    client: DiscordClient,
+    redis_client: redis::Client,
+}
+
+impl DiscordChannel {
+    pub fn new(client: DiscordClient, redis_client: redis::Client) -> Self {
+        Self { client, redis_client }
+    }
+
+    pub fn client(&self) -> &DiscordClient {
+        &self.client
+    }
+
+    fn mentions_bot(&self, message: &Message) -> bool {
+        message.mentions.iter().any(|user| user.id == self.client.bot.id)
+            || message.referenced_message
+                .as_ref()
+                .map(|referenced| referenced.author.id == self.client.bot.id)
+                .unwrap_or(false)
+    }
+
+    // Mensaje a contestar: menciones o replies al bot, y cualquier mensaje de un
+    // hilo en el que el bot ya participa
+    pub async fn normalize(&self, message: Message) -> Result<Option<InboundMessage<DiscordEvent>>> {
+        if message.author.bot || message.guild_id.is_none() {
+            return Ok(None);
+        }
+
+        if !self.mentions_bot(&message) {
+            let mut con = self.redis_client.get_async_connection().await?;
+            let joined: bool = con.sismember(THREADS_KEY, &message.channel_id).await?;
+            if !joined {
+                return Ok(None);
+            }
+        }
+
+        let text = message.content
+            .replace(&format!("<@{}>", self.client.bot.id), "")
+            .replace(&format!("<@!{}>", self.client.bot.id), "")
+            .trim()
+            .to_string();
+        if text.is_empty() {
+            return Ok(None);
+        }
+
+        Ok(Some(InboundMessage {
+            id: message.id.clone(),
+            author: author(&message.author),
+            text,
+            event: DiscordEvent::Message { channel_id: message.channel_id, message_id: message.id },
+        }))
+    }
+
+    // `/qawakun ask` como mensaje del pipeline, contestado en la propia interacción
+    pub fn interaction_message(&self, interaction: &Interaction, question: &str) -> Option<InboundMessage<DiscordEvent>> {
+        Some(InboundMessage {
+            id: interaction.id.clone(),
+            author: author(interaction.author()?),
+            text: question.to_string(),
+            event: DiscordEvent::Interaction { token: interaction.token.clone() },
+        })
+    }
+
+    // Contesta a una interacción diferida; lo que no cabe va en mensajes de seguimiento
+    pub async fn send_interaction(&self, token: &str, text: &str) -> Result<()> {
+        let parts = split_numbered(text, MAX_MESSAGE_CHARS, |text| text.chars().count());
+        for (index, part) in parts.iter().enumerate() {
+            if index == 0 {
+                self.client.edit_interaction_response(token, part).await?;
+            } else {
+                self.client.interaction_followup(token, part).await?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SocialChannel for DiscordChannel {
+    type Event = DiscordEvent;
+
+    fn name(&self) -> &'static str {
+        "discord"
+    }
+
+    fn channel_prompt(&self) -> String {
+        format!(
+            "You are @{} replying inside a Discord server conversation with several participants. \
+             Messages from other people are prefixed with their @username. Reply only to the last message, \
+             staying consistent with what you already said in the conversation.",
+            self.client.bot.username
+        )
+    }
+
+    // Los mensajes llegan por el gateway, no hay nada que leer
+    async fn fetch(&self) -> Result<Batch<DiscordEvent>> {
+        Ok(Batch { messages: Vec::new(), cursor: None })
+    }
+
+    async fn commit(&self, _cursor: &str) -> Result<()> {
+        Ok(())
+    }
+
+    async fn thread(&self, message: &InboundMessage<DiscordEvent>) -> Result<Vec<ThreadEntry>> {
+        let (channel_id, message_id) = match &message.event {
+            DiscordEvent::Message { channel_id, message_id } => (channel_id, message_id),
+            DiscordEvent::Interaction { .. } => return Ok(Vec::new()),
+        };
+
+        let mut previous = self.client.messages_before(channel_id, message_id, MAX_THREAD_MESSAGES).await?;
+        previous.reverse();
+        Ok(previous
+            .into_iter()
+            .filter(|entry| !entry.content.trim().is_empty())
+            .map(|entry| ThreadEntry {
+                from_bot: entry.author.id == self.client.bot.id,
+                author: author(&entry.author),
+                text: entry.content,
+            })
+            .collect())
+    }
+
+    // Tras contestar en un hilo el bot sigue la conversación sin necesitar menciones
+    async fn send_reply(&self, message: &InboundMessage<DiscordEvent>, text: &str) -> Result<()> {
+        match &message.event {
+            DiscordEvent::Interaction { token } => self.send_interaction(token, text).await,
+            DiscordEvent::Message { channel_id, message_id } => {
+                let parts = split_numbered(text, MAX_MESSAGE_CHARS, |text| text.chars().count());
+                for (index, part) in parts.iter().enumerate() {
+                    let reply_to = if index == 0 { Some(message_id.as_str()) } else { None };
+                    self.client.create_message(channel_id, part, reply_to).await?;
+                }
+
+                let mut con = self.redis_client.get_async_connection().await?;
+                let joined: bool = con.sismember(THREADS_KEY, channel_id).await?;
+                if !joined && self.client.is_thread(channel_id).await? {
+                    let _: () = con.sadd(THREADS_KEY, channel_id).await?;
+                }
+                Ok(())
+            },
+        }
+    }
+}
@@ -0,0 +1,220 @@
+use anyhow::Result;
+use reqwest::Method;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::json;
+use std::env;
+use tracing::{debug, info};
+
+const API_ROOT: &str = "https://discord.com/api/v10";
+// Flag de mensaje visible solo para quien usó el comando
+pub const EPHEMERAL: u64 = 1 << 6;
+// Tipos de canal que son hilos: anuncio, público y privado
+const THREAD_CHANNEL_TYPES: [u8; 3] = [10, 11, 12];
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct User {
+    pub id: String,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub bot: bool,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReferencedMessage {
+    pub author: User,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Message {
+    pub id: String,
+    pub channel_id: String,
+    #[serde(default)]
+    pub guild_id: Option<String>,
+    pub author: User,
+    #[serde(default)]
+    pub content: String,
+    #[serde(default)]
+    pub mentions: Vec<User>,
+    #[serde(default)]
+    pub referenced_message: Option<Box<ReferencedMessage>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Member {
+    pub user: Option<User>,
+    #[serde(default)]
+    pub roles: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommandOption {
+    pub name: String,
+    #[serde(default)]
+    pub value: Option<serde_json::Value>,
+    #[serde(default)]
+    pub options: Vec<CommandOption>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CommandData {
+    pub name: String,
+    #[serde(default)]
+    pub options: Vec<CommandOption>,
+}
+
+// Solo se atienden interacciones de tipo 2 (slash commands)
+#[derive(Debug, Clone, Deserialize)]
+pub struct Interaction {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub kind: u8,
+    pub token: String,
+    #[serde(default)]
+    pub member: Option<Member>,
+    #[serde(default)]
+    pub user: Option<User>,
+    #[serde(default)]
+    pub data: Option<CommandData>,
+}
+
+impl Interaction {
+    // En servidores el usuario viene dentro de `member`, en DMs en `user`
+    pub fn author(&self) -> Option<&User> {
+        self.member.as_ref().and_then(|member| member.user.as_ref()).or(self.user.as_ref())
+    }
+
+    pub fn roles(&self) -> &[String] {
+        self.member.as_ref().map(|member| member.roles.as_slice()).unwrap_or(&[])
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Application {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Channel {
+    #[serde(rename = "type")]
+    kind: u8,
+}
+
+#[derive(Clone)]
+pub struct DiscordClient {
+    http: reqwest::Client,
+    token: String,
+    pub bot: User,
+    pub application_id: String,
+}
+
+impl DiscordClient {
+    // Resuelve la cuenta del bot y su aplicación, así que un token inválido falla aquí
+    pub async fn from_env() -> Result<Self> {
+        let token = env::var("DISCORD_BOT_TOKEN")?;
+        let mut client = Self {
+            http: reqwest::Client::new(),
+            token,
+            bot: User { id: String::new(), username: String::new(), bot: true },
+            application_id: String::new(),
+        };
+        client.bot = client.call(Method::GET, "/users/@me", None).await?;
+        let application: Application = client.call(Method::GET, "/oauth2/applications/@me", None).await?;
+        client.application_id = application.id;
+        info!(user_id = %client.bot.id, username = %client.bot.username, "Discord bot identity resolved");
+        Ok(client)
+    }
+
+    pub fn token(&self) -> &str {
+        &self.token
+    }
+
+    async fn send(&self, method: Method, path: &str, body: Option<serde_json::Value>) -> Result<reqwest::Response> {
+        let mut request = self.http
+            .request(method.clone(), format!("{}{}", API_ROOT, path))
+            .header("Authorization", format!("Bot {}", self.token));
+        if let Some(body) = body {
+            request = request.json(&body);
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        debug!(method = %method, path, status = status.as_u16(), "Discord API response");
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow::anyhow!("Discord {} {} failed ({}): {}", method, path, status, body));
+        }
+        Ok(response)
+    }
+
+    async fn call<T: DeserializeOwned>(&self, method: Method, path: &str, body: Option<serde_json::Value>) -> Result<T> {
+        Ok(self.send(method, path, body).await?.json().await?)
+    }
+
+    // Publica en el canal, como respuesta a `reply_to` si se indica. Las menciones
+    // del texto del LLM no notifican a nadie
+    pub async fn create_message(&self, channel_id: &str, content: &str, reply_to: Option<&str>) -> Result<()> {
+        let mut body = json!({ "content": content, "allowed_mentions": { "parse": [] } });
+        if let Some(message_id) = reply_to {
+            body["message_reference"] = json!({ "message_id": message_id, "fail_if_not_exists": false });
+        }
+        self.send(Method::POST, &format!("/channels/{}/messages", channel_id), Some(body)).await?;
+        Ok(())
+    }
+
+    // Mensajes anteriores a `before`, del más reciente al más antiguo
+    pub async fn messages_before(&self, channel_id: &str, before: &str, limit: usize) -> Result<Vec<Message>> {
+        self.call(
+            Method::GET,
+            &format!("/channels/{}/messages?before={}&limit={}", channel_id, before, limit),
+            None,
+        ).await
+    }
+
+    pub async fn is_thread(&self, channel_id: &str) -> Result<bool> {
+        let channel: Channel = self.call(Method::GET, &format!("/channels/{}", channel_id), None).await?;
+        Ok(THREAD_CHANNEL_TYPES.contains(&channel.kind))
+    }
+
+    // Respuesta diferida (tipo 5): Discord exige contestar en 3 s y el LLM tarda más
+    pub async fn defer_interaction(&self, interaction: &Interaction, ephemeral: bool) -> Result<()> {
+        let flags = if ephemeral { EPHEMERAL } else { 0 };
+        self.send(
+            Method::POST,
+            &format!("/interactions/{}/{}/callback", interaction.id, interaction.token),
+            Some(json!({ "type": 5, "data": { "flags": flags } })),
+        ).await?;
+        Ok(())
+    }
+
+    // Sustituye el "pensando..." de la respuesta diferida
+    pub async fn edit_interaction_response(&self, token: &str, content: &str) -> Result<()> {
+        self.send(
+            Method::PATCH,
+            &format!("/webhooks/{}/{}/messages/@original", self.application_id, token),
+            Some(json!({ "content": content, "allowed_mentions": { "parse": [] } })),
+        ).await?;
+        Ok(())
+    }
+
+    pub async fn interaction_followup(&self, token: &str, content: &str) -> Result<()> {
+        self.send(
+            Method::POST,
+            &format!("/webhooks/{}/{}", self.application_id, token),
+            Some(json!({ "content": content, "allowed_mentions": { "parse": [] } })),
+        ).await?;
+        Ok(())
+    }
+
+    // Sobrescribe los comandos del bot: en un servidor se aplican al momento,
+    // los globales pueden tardar en propagarse
+    pub async fn register_commands(&self, guild_id: Option<&str>, commands: &serde_json::Value) -> Result<()> {
+        let path = match guild_id {
+            Some(guild_id) => format!("/applications/{}/guilds/{}/commands", self.application_id, guild_id),
+            None => format!("/applications/{}/commands", self.application_id),
+        };
+        self.send(Method::PUT, &path, Some(commands.clone())).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,220 @@
+use anyhow::Result;
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use tokio::time::{interval, sleep, Duration};
+use tokio_tungstenite::connect_async;
+use tokio_tungstenite::tungstenite::protocol::frame::coding::CloseCode;
+use tokio_tungstenite::tungstenite::Message as WsMessage;
+use crate::workers::Shutdown;
+use tracing::{debug, info, warn};
+use super::client::{Interaction, Message};
+
+const GATEWAY_URL: &str = "wss://gateway.discord.gg";
+const GATEWAY_QUERY: &str = "/?v=10&encoding=json";
+
+// GUILDS, GUILD_MESSAGES y MESSAGE_CONTENT (privilegiado: hay que activarlo en el portal)
+const INTENTS: u64 = (1 << 0) | (1 << 9) | (1 << 15);
+
+const OP_DISPATCH: u8 = 0;
+const OP_HEARTBEAT: u8 = 1;
+const OP_IDENTIFY: u8 = 2;
+const OP_RESUME: u8 = 6;
+const OP_RECONNECT: u8 = 7;
+const OP_INVALID_SESSION: u8 = 9;
+const OP_HELLO: u8 = 10;
+const OP_HEARTBEAT_ACK: u8 = 11;
+
+// Cierres tras los que reconectar no sirve (token, intents o versión inválidos)
+const FATAL_CLOSE_CODES: [u16; 6] = [4004, 4010, 4011, 4012, 4013, 4014];
+
+#[derive(Debug, Deserialize)]
+struct Payload {
+    op: u8,
+    #[serde(default)]
+    d: serde_json::Value,
+    #[serde(default)]
+    s: Option<u64>,
+    #[serde(default)]
+    t: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReadyEvent {
+    session_id: String,
+    resume_gateway_url: String,
+}
+
+// Sesión que se puede reanudar tras una desconexión sin perder eventos
+struct Session {
+    id: String,
+    resume_url: String,
+}
+
+pub enum Dispatch {
+    MessageCreate(Message),
+    InteractionCreate(Interaction),
+}
+
+enum Closed {
+    Shutdown,
+    Reconnect,
+    // La sesión no se puede reanudar: hay que identificarse de nuevo
+    Invalidated,
+}
+
+pub struct Gateway {
+    token: String,
+}
+
+impl Gateway {
+    pub fn new(token: &str) -> Self {
+        Self { token: token.to_string() }
+    }
+
+    // Mantiene la conexión hasta el apagado, reanudando la sesión cuando Discord
+    // lo permite. Los eventos se entregan a `on_dispatch`, que no debe bloquear:
+    // mientras tanto no se envían heartbeats
+    pub async fn run<F>(&self, shutdown: &mut Shutdown, on_dispatch: F) -> Result<()>
+    where
+        F: Fn(Dispatch),
+    {
+        let mut session: Option<Session> = None;
+        let mut seq: Option<u64> = None;
+
+        loop {
+            match self.connect(shutdown, &mut session, &mut seq, &on_dispatch).await? {
+                Closed::Shutdown => return Ok(()),
+                Closed::Reconnect => info!(resume = session.is_some(), "reconnecting to Discord gateway"),
+                Closed::Invalidated => {
+                    session = None;
+                    seq = None;
+                    // Discord pide esperar entre 1 y 5 s antes de volver a identificarse
+                    if shutdown.sleep(Duration::from_secs(1 + rand::random::<u64>() % 5)).await {
+                        return Ok(());
+                    }
+                },
+            }
+        }
+    }
+
+    async fn connect<F>(
+        &self,
+        shutdown: &mut Shutdown,
+        session: &mut Option<Session>,
+        seq: &mut Option<u64>,
+        on_dispatch: &F,
+    ) -> Result<Closed>
+    where
+        F: Fn(Dispatch),
+    {
+        let base = session.as_ref().map(|s| s.resume_url.as_str()).unwrap_or(GATEWAY_URL);
+        let (socket, _) = connect_async(format!("{}{}", base, GATEWAY_QUERY)).await?;
+        let (mut write, mut read) = socket.split();
+
+        let hello: Payload = match read.next().await {
+            Some(Ok(WsMessage::Text(text))) => serde_json::from_str(&text)?,
+            _ => return Ok(Closed::Reconnect),
+        };
+        if hello.op != OP_HELLO {
+            return Err(anyhow::anyhow!("expected Discord HELLO, got op {}", hello.op));
+        }
+        let heartbeat_ms = hello.d["heartbeat_interval"].as_u64().unwrap_or(41_250);
+
+        let handshake = match session.as_ref() {
+            Some(session) => json!({
+                "op": OP_RESUME,
+                "d": { "token": self.token, "session_id": session.id, "seq": *seq },
+            }),
+            None => json!({
+                "op": OP_IDENTIFY,
+                "d": {
+                    "token": self.token,
+                    "intents": INTENTS,
+                    "properties": { "os": std::env::consts::OS, "browser": "qawakun", "device": "qawakun" },
+                },
+            }),
+        };
+        write.send(WsMessage::Text(handshake.to_string())).await?;
+
+        let mut heartbeat = interval(Duration::from_millis(heartbeat_ms));
+        heartbeat.tick().await;
+        let mut acked = true;
+
+        loop {
+            tokio::select! {
+                _ = shutdown.recv() => {
+                    let _ = write.send(WsMessage::Close(None)).await;
+                    return Ok(Closed::Shutdown);
+                },
+                _ = heartbeat.tick() => {
+                    // Sin ACK del heartbeat anterior la conexión está muerta
+                    if !acked {
+                        warn!("Discord heartbeat not acknowledged");
+                        return Ok(Closed::Reconnect);
+                    }
+                    write.send(WsMessage::Text(json!({ "op": OP_HEARTBEAT, "d": *seq }).to_string())).await?;
+                    acked = false;
+                },
+                frame = read.next() => {
+                    let text = match frame {
+                        Some(Ok(WsMessage::Text(text))) => text,
+                        Some(Ok(WsMessage::Close(frame))) => {
+                            let code = frame.map(|f| u16::from(f.code)).unwrap_or(u16::from(CloseCode::Normal));
+                            if FATAL_CLOSE_CODES.contains(&code) {
+                                return Err(anyhow::anyhow!("Discord gateway closed with code {}", code));
+                            }
+                            warn!(code, "Discord gateway closed");
+                            return Ok(Closed::Reconnect);
+                        },
+                        Some(Ok(_)) => continue,
+                        Some(Err(e)) => {
+                            warn!(error = %e, "Discord gateway error");
+                            return Ok(Closed::Reconnect);
+                        },
+                        None => return Ok(Closed::Reconnect),
+                    };
+
+                    let payload: Payload = serde_json::from_str(&text)?;
+                    if payload.s.is_some() {
+                        *seq = payload.s;
+                    }
+
+                    match payload.op {
+                        OP_DISPATCH => match payload.t.as_deref() {
+                            Some("READY") => {
+                                let ready: ReadyEvent = serde_json::from_value(payload.d)?;
+                                info!("Discord gateway session ready");
+                                *session = Some(Session { id: ready.session_id, resume_url: ready.resume_gateway_url });
+                            },
+                            Some("RESUMED") => info!("Discord gateway session resumed"),
+                            Some("MESSAGE_CREATE") => match serde_json::from_value(payload.d) {
+                                Ok(message) => on_dispatch(Dispatch::MessageCreate(message)),
+                                Err(e) => warn!(error = %e, "invalid Discord message payload"),
+                            },
+                            Some("INTERACTION_CREATE") => match serde_json::from_value(payload.d) {
+                                Ok(interaction) => on_dispatch(Dispatch::InteractionCreate(interaction)),
+                                Err(e) => warn!(error = %e, "invalid Discord interaction payload"),
+                            },
+                            other => debug!(event = ?other, "Discord event ignored"),
+                        },
+                        OP_HEARTBEAT => {
+                            write.send(WsMessage::Text(json!({ "op": OP_HEARTBEAT, "d": *seq }).to_string())).await?;
+                        },
+                        OP_HEARTBEAT_ACK => acked = true,
+                        OP_RECONNECT => return Ok(Closed::Reconnect),
+                        OP_INVALID_SESSION => {
+                            // d = true: la sesión aún se puede reanudar
+                            if payload.d.as_bool().unwrap_or(false) {
+                                sleep(Duration::from_secs(1)).await;
+                                return Ok(Closed::Reconnect);
+                            }
+                            return Ok(Closed::Invalidated);
+                        },
+                        op => debug!(op, "Discord gateway op ignored"),
+                    }
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use anyhow::Result;
+use redis::AsyncCommands;
+use std::sync::Arc;
+use crate::api::admin::{is_paused, Control};
+use crate::api::proposals::{ProposalContractEvents, ProposalManager};
+use crate::telemetry;
+use crate::workers::Shutdown;
+use tokio::time::Duration;
+use tracing::{debug, error, info};
+use super::bot::PROPOSAL_TYPES;
+use super::client::DiscordClient;
+
+// Último bloque ya publicado
+const LAST_BLOCK_KEY: &str = "discord:governance:last_block";
+const POLL_INTERVAL: Duration = Duration::from_secs(300);
+// Rango máximo de bloques por consulta de logs (los RPC públicos limitan el rango)
+const MAX_BLOCK_RANGE: u64 = 2_000;
+const MAX_DESCRIPTION_CHARS: usize = 300;
+
+fn announcement(event: &ProposalContractEvents) -> Option<String> {
+    match event {
+        ProposalContractEvents::MonthlyWinnerSelectedFilter(e) => Some(format!(
+            "🏆 Proposal #{} is the winner of {}/{} with {} approvals. It will become part of the story of Qawakun.",
+            e.proposal_id, e.month, e.year, e.approval_count
+        )),
+        ProposalContractEvents::ProposalCreatedFilter(e) => {
+            let mut description: String = e.description.chars().take(MAX_DESCRIPTION_CHARS).collect();
+            if description.len() < e.description.len() {
+                description.push('…');
+            }
+            Some(format!(
+                "📜 New proposal #{} [{}] is open for voting:\n> {}",
+                e.id,
+                PROPOSAL_TYPES.get(e.proposal_type as usize).unwrap_or(&"?"),
+                description
+            ))
+        },
+        ProposalContractEvents::ProposalExecutedFilter(e) => Some(format!(
+            "⚖️ Proposal #{} was executed: {} approvals, {} rejections.",
+            e.proposal_id, e.approval_count, e.rejection_count
+        )),
+        _ => None,
+    }
+}
+
+// Publica los eventos de gobernanza del contrato en DISCORD_GOVERNANCE_CHANNEL_ID.
+// La primera vez empieza en el bloque actual para no repetir el historial
+pub async fn start_governance_feed(
+    client: DiscordClient,
+    proposal_manager: Arc<ProposalManager>,
+    redis_client: redis::Client,
+    channel_id: String,
+    mut shutdown: Shutdown,
+) -> Result<()> {
+    info!(channel_id = %channel_id, "starting Discord governance feed");
+
+    loop {
+        if is_paused(&redis_client, Control::DiscordWorker).await {
+            info!("Discord paused, skipping governance poll");
+        } else if let Err(e) = telemetry::traced(
+            "governance_poll",
+            None,
+            publish_new_events(&client, &proposal_manager, &redis_client, &channel_id),
+        ).await {
+            error!(error = %e, "error publishing governance events to Discord");
+        }
+
+        if shutdown.sleep(POLL_INTERVAL).await {
+            break;
+        }
+    }
+
+    info!("Discord governance feed stopped");
+    Ok(())
+}
+
+async fn publish_new_events(
+    client: &DiscordClient,
+    proposal_manager: &ProposalManager,
+    redis_client: &redis::Client,
+    channel_id: &str,
+) -> Result<()> {
+    let mut con = redis_client.get_async_connection().await?;
+    let latest = proposal_manager.latest_block().await?;
+    let last: Option<u64> = con.get(LAST_BLOCK_KEY).await?;
+    let last = match last {
+        Some(last) => last,
+        None => {
+            let _: () = con.set(LAST_BLOCK_KEY, latest).await?;
+            return Ok(());
+        },
+    };
+
+    let mut from = last + 1;
+    while from <= latest {
+        let to = (from + MAX_BLOCK_RANGE - 1).min(latest);
+        let events = proposal_manager.events_between(from, to).await?;
+        debug!(from, to, events = events.len(), "governance events fetched");
+        for text in events.iter().filter_map(announcement) {
+            client.create_message(channel_id, &text, None).await?;
+        }
+        // El cursor avanza por tramos: si falla una publicación se reintenta solo ese tramo
+        let _: () = con.set(LAST_BLOCK_KEY, to).await?;
+        from = to + 1;
+    }
+    Ok(())
+}
@@ -0,0 +1,7 @@
+pub mod bot;
+pub mod channel;
+pub mod client;
+pub mod gateway;
+pub mod governance;
+
+pub use bot::DiscordBot;
@@ -0,0 +1,348 @@
+use anyhow::Result;
+use chrono::Utc;
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use redis::streams::{StreamReadOptions, StreamReadReply};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use crate::api::admin::{is_paused, Control};
+use crate::metrics;
+use crate::retry_queue::{Leased, RetryQueue};
+use crate::telemetry;
+use crate::workers::Shutdown;
+use tokio::time::Duration;
+use tracing::{debug, error, info, warn};
+use url::Host;
+use super::subscriptions::{self, Subscription};
+use super::{Envelope, Event, STREAM_KEY};
+
+// Grupo de consumidores del stream: lo confirmado (XACK) ya está repartido en entregas
+const GROUP: &str = "webhooks";
+const CONSUMER: &str = "dispatcher";
+const READ_BATCH: usize = 100;
+
+// Entregas pendientes: 8 intentos, de 30 s a ~1 h entre ellos, con un lease de
+// 5 min mientras se hace el POST
+const DELIVERIES: RetryQueue = RetryQueue::new("events:deliveries", "events:dead_letter", 8, 30, 5 * 60);
+// Entregas en vuelo a la vez, y como mucho por suscriptor para que uno lento no
+// ocupe todos los huecos
+const MAX_IN_FLIGHT: usize = 16;
+const MAX_IN_FLIGHT_PER_SUBSCRIPTION: usize = 4;
+// Eventos leídos por un consumidor que no llegó a confirmarlos (XAUTOCLAIM)
+const RECLAIM_MIN_IDLE_MS: u64 = 60_000;
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const PAUSED_RETRY: Duration = Duration::from_secs(30);
+const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Delivery {
+    pub id: String,
+    pub subscription_id: String,
+    pub event_id: String,
+    pub event_type: String,
+    // Cuerpo ya serializado: la firma de cada intento es sobre los mismos bytes
+    pub body: String,
+    pub attempts: u32,
+    #[serde(default)]
+    pub last_error: Option<String>,
+    #[serde(default)]
+    pub failed_at: Option<i64>,
+}
+
+// "sha256=" + HMAC-SHA256 de "{timestamp}.{body}" con el secreto de la suscripción
+fn signature(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts any key length");
+    mac.update(format!("{}.{}", timestamp, body).as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+fn delivery_id() -> String {
+    let mut bytes = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+async fn ensure_group(con: &mut redis::aio::Connection) -> redis::RedisResult<()> {
+    // "$": el grupo empieza con los eventos publicados a partir de ahora
+    let created: redis::RedisResult<()> = con.xgroup_create_mkstream(STREAM_KEY, GROUP, "$").await;
+    match created {
+        Err(e) if e.code() == Some("BUSYGROUP") => Ok(()),
+        result => result,
+    }
+}
+
+// Lee eventos del grupo y crea una entrega por suscripción interesada. Con `pending`
+// relee los que este consumidor leyó sin confirmar (caída entre lectura y XACK)
+async fn fan_out(con: &mut redis::aio::Connection, pending: bool) -> Result<usize> {
+    let start = if pending { "0" } else { ">" };
+    let options = StreamReadOptions::default().group(GROUP, CONSUMER).count(READ_BATCH);
+    let reply: StreamReadReply = con.xread_options(&[STREAM_KEY], &[start], &options).await?;
+
+    let entries: Vec<_> = reply.keys.into_iter().flat_map(|key| key.ids).collect();
+    if entries.is_empty() {
+        return Ok(0);
+    }
+
+    let subscriptions = subscriptions::list(con).await?;
+    for entry in &entries {
+        let event = entry.get::<String>("event").and_then(|e| serde_json::from_str::<Event>(&e).ok());
+        let occurred_at: String = entry.get("occurred_at").unwrap_or_default();
+        match event {
+            Some(event) => {
+                let envelope = Envelope { id: entry.id.clone(), occurred_at, event };
+                let body = serde_json::to_string(&envelope)?;
+                for subscription in subscriptions.iter().filter(|s| s.wants(envelope.event.kind())) {
+                    let delivery = Delivery {
+                        id: delivery_id(),
+                        subscription_id: subscription.id.clone(),
+                        event_id: entry.id.clone(),
+                        event_type: envelope.event.kind().to_string(),
+                        body: body.clone(),
+                        attempts: 0,
+                        last_error: None,
+                        failed_at: None,
+                    };
+                    DELIVERIES.push(con, &delivery).await?;
+                }
+            },
+            None => warn!(id = %entry.id, "invalid event in stream, skipped"),
+        }
+        let _: () = con.xack(STREAM_KEY, GROUP, &[&entry.id]).await?;
+    }
+
+    debug!(events = entries.len(), subscriptions = subscriptions.len(), "events fanned out");
+    Ok(entries.len())
+}
+
+// Pasa a este consumidor los eventos que otro leyó y no confirmó (p. ej. cayó entre
+// la lectura y el XACK); luego `fan_out(con, true)` los reparte
+async fn reclaim_pending(con: &mut redis::aio::Connection) -> Result<usize> {
+    let reply: redis::Value = redis::cmd("XAUTOCLAIM")
+        .arg(STREAM_KEY)
+        .arg(GROUP)
+        .arg(CONSUMER)
+        .arg(RECLAIM_MIN_IDLE_MS)
+        .arg("0-0")
+        .arg("COUNT")
+        .arg(READ_BATCH)
+        .arg("JUSTID")
+        .query_async(con)
+        .await?;
+    let claimed = match reply {
+        redis::Value::Bulk(parts) => match parts.get(1) {
+            Some(redis::Value::Bulk(ids)) => ids.len(),
+            _ => 0,
+        },
+        _ => 0,
+    };
+    if claimed > 0 {
+        info!(claimed, "reclaimed unacknowledged events");
+    }
+    Ok(claimed)
+}
+
+// Reprograma la entrega o, agotados los intentos, la manda a dead letter
+async fn record_failure(con: &mut redis::aio::Connection, leased: &Leased<Delivery>, error: &str) -> redis::RedisResult<()> {
+    let mut delivery = leased.item.clone();
+    delivery.attempts += 1;
+    delivery.last_error = Some(error.to_string());
+    delivery.failed_at = Some(Utc::now().timestamp());
+
+    if DELIVERIES.fail(con, Some(leased), &delivery, delivery.attempts).await? {
+        warn!(delivery_id = %delivery.id, subscription_id = %delivery.subscription_id, error, "webhook delivery moved to dead letter");
+        metrics::webhook_delivery("dead_letter");
+    } else {
+        debug!(delivery_id = %delivery.id, attempts = delivery.attempts, error, "webhook delivery failed, will retry");
+        metrics::webhook_delivery("retry");
+    }
+    Ok(())
+}
+
+// Cliente para la URL de la suscripción tras resolver su host: todas las direcciones
+// tienen que ser públicas y la conexión va solo a esas, así que un DNS que cambie
+// entre la comprobación y el POST (rebinding) no llega a la red interna. Sin
+// redirecciones por lo mismo
+async fn client_for(url: &str) -> Result<reqwest::Client> {
+    let parsed = subscriptions::check_url(url)
+        .ok_or_else(|| anyhow::anyhow!("webhook URL is not a public https URL"))?;
+    let port = parsed.port_or_known_default().unwrap_or(443);
+    let builder = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none());
+
+    let domain = match parsed.host() {
+        Some(Host::Domain(domain)) => domain.to_string(),
+        // Una IP literal ya se comprobó en check_url
+        _ => return Ok(builder.build()?),
+    };
+    let addrs: Vec<SocketAddr> = tokio::net::lookup_host((domain.as_str(), port)).await?.collect();
+    if addrs.is_empty() {
+        return Err(anyhow::anyhow!("webhook host {} did not resolve", domain));
+    }
+    if let Some(addr) = addrs.iter().find(|addr| !subscriptions::is_public(addr.ip())) {
+        return Err(anyhow::anyhow!("webhook host {} resolves to non-public address {}", domain, addr.ip()));
+    }
+    Ok(builder.resolve_to_addrs(&domain, &addrs).build()?)
+}
+
+async fn post(subscription: &Subscription, delivery: &Delivery) -> Result<()> {
+    let http = client_for(&subscription.url).await?;
+    let timestamp = Utc::now().timestamp();
+    let response = http
+        .post(&subscription.url)
+        .timeout(DELIVERY_TIMEOUT)
+        .header("Content-Type", "application/json")
+        .header("X-Qawakun-Event", &delivery.event_type)
+        .header("X-Qawakun-Delivery", &delivery.id)
+        .header("X-Qawakun-Timestamp", timestamp.to_string())
+        .header("X-Qawakun-Signature", signature(&subscription.secret, timestamp, &delivery.body))
+        .body(delivery.body.clone())
+        .send()
+        .await?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(anyhow::anyhow!("subscriber returned {}", status));
+    }
+    Ok(())
+}
+
+// Entrega en paralelo lo que ya toca; cada POST tiene su propio timeout, así que un
+// suscriptor lento solo retrasa sus propias entregas
+async fn deliver_due(con: &mut redis::aio::Connection, shutdown: &Shutdown) -> Result<usize> {
+    let mut delivered = 0;
+    let mut in_flight = FuturesUnordered::new();
+    let mut per_subscription: HashMap<String, usize> = HashMap::new();
+    let mut drained = false;
+
+    loop {
+        while !drained && !shutdown.is_shutdown() && in_flight.len() < MAX_IN_FLIGHT {
+            let leased = match DELIVERIES.take_due::<Delivery>(con).await? {
+                Some(leased) => leased,
+                None => {
+                    drained = true;
+                    break;
+                },
+            };
+            // Suscripción borrada después de repartir el evento
+            let subscription = match subscriptions::get(con, &leased.item.subscription_id).await? {
+                Some(subscription) => subscription,
+                None => {
+                    DELIVERIES.complete(con, &leased).await?;
+                    continue;
+                },
+            };
+            let busy = per_subscription.entry(subscription.id.clone()).or_default();
+            if *busy >= MAX_IN_FLIGHT_PER_SUBSCRIPTION {
+                DELIVERIES.postpone(con, &leased, POLL_INTERVAL.as_secs() as i64).await?;
+                continue;
+            }
+            *busy += 1;
+
+            in_flight.push(async move {
+                let delivery = &leased.item;
+                let result = telemetry::traced("webhook_delivery", Some(delivery.id.clone()), post(&subscription, delivery)).await;
+                (leased, subscription, result)
+            });
+        }
+
+        let (leased, subscription, result) = match in_flight.next().await {
+            Some(done) => done,
+            None => break,
+        };
+        if let Some(busy) = per_subscription.get_mut(&subscription.id) {
+            *busy -= 1;
+        }
+        match result {
+            Ok(()) => {
+                debug!(delivery_id = %leased.item.id, event = %leased.item.event_type, subscription_id = %subscription.id, "webhook delivered");
+                metrics::webhook_delivery("delivered");
+                DELIVERIES.complete(con, &leased).await?;
+                delivered += 1;
+            },
+            Err(e) => record_failure(con, &leased, &e.to_string()).await?,
+        }
+    }
+    Ok(delivered)
+}
+
+pub async fn dead_letters(con: &mut redis::aio::Connection) -> redis::RedisResult<Vec<Delivery>> {
+    DELIVERIES.dead_letters(con).await
+}
+
+// Devuelve todo el dead letter a la cola de entregas con los intentos a cero
+pub async fn requeue_dead_letters(con: &mut redis::aio::Connection) -> redis::RedisResult<usize> {
+    DELIVERIES.requeue_dead_letters(con, |delivery: &mut Delivery| delivery.attempts = 0).await
+}
+
+// Reparte los eventos del stream entre las suscripciones y entrega los webhooks.
+// Entrega al menos una vez: los suscriptores deduplican por el `id` del evento
+pub async fn start_dispatcher(redis_client: redis::Client, mut shutdown: Shutdown) -> Result<()> {
+    info!("starting webhook dispatcher");
+    let mut con = redis_client.get_async_connection().await?;
+    ensure_group(&mut con).await?;
+    fan_out(&mut con, true).await?;
+
+    loop {
+        if is_paused(&redis_client, Control::Webhooks).await {
+            info!("webhooks paused, skipping deliveries");
+            if shutdown.sleep(PAUSED_RETRY).await {
+                break;
+            }
+            continue;
+        }
+
+        let result = async {
+            if reclaim_pending(&mut con).await? > 0 {
+                fan_out(&mut con, true).await?;
+            }
+            while fan_out(&mut con, false).await? == READ_BATCH {}
+            deliver_due(&mut con, &shutdown).await
+        }.await;
+        if let Err(e) = result {
+            error!(error = %e, "error dispatching webhooks");
+            metrics::redis_error("events");
+            con = redis_client.get_async_connection().await?;
+        }
+
+        if shutdown.sleep(POLL_INTERVAL).await {
+            break;
+        }
+    }
+
+    info!("webhook dispatcher stopped");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signature_is_hmac_of_timestamp_and_body() {
+        // Calculado aparte: HMAC-SHA256("whsec_test", "1700000000.{\"id\":\"1-0\"}")
+        assert_eq!(
+            signature("whsec_test", 1_700_000_000, r#"{"id":"1-0"}"#),
+            "sha256=d5433c87444c4ded147110fca241afc9ee9e948362b221556221fad2881f3d75",
+        );
+    }
+
+    #[test]
+    fn signature_covers_timestamp_body_and_secret() {
+        let base = signature("whsec_test", 1_700_000_000, r#"{"id":"1-0"}"#);
+        assert_ne!(base, signature("whsec_test", 1_700_000_001, r#"{"id":"1-0"}"#));
+        assert_ne!(base, signature("whsec_test", 1_700_000_000, r#"{"id":"1-1"}"#));
+        assert_ne!(base, signature("whsec_other", 1_700_000_000, r#"{"id":"1-0"}"#));
+    }
+
+    #[actix_web::test]
+    async fn client_refuses_internal_urls() {
+        assert!(client_for("http://example.com/hook").await.is_err());
+        assert!(client_for("https://127.0.0.1/hook").await.is_err());
+        assert!(client_for("https://169.254.169.254/latest/meta-data").await.is_err());
+        assert!(client_for("https://93.184.216.34/hook").await.is_ok());
+    }
+}
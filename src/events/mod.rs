@@ -0,0 +1,89 @@
+use anyhow::Result;
+use chrono::Utc;
+use redis::streams::StreamMaxlen;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use crate::metrics;
+use tracing::{debug, warn};
+
+pub mod delivery;
+pub mod subscriptions;
+
+// Stream con todos los eventos publicados; se recorta a las últimas entradas
+pub const STREAM_KEY: &str = "events:stream";
+const STREAM_MAXLEN: usize = 10_000;
+
+// Eventos del backend. El `type` es el nombre con el que se filtran las suscripciones
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum Event {
+    #[serde(rename = "proposal.submitted")]
+    ProposalSubmitted { wallet: String, fid: u64, proposal_type: String, channel: String },
+    #[serde(rename = "proposal.status_changed")]
+    ProposalStatusChanged { wallet: String, fid: u64, status: i32, label: Option<String> },
+    #[serde(rename = "proposal.elevated")]
+    ProposalElevated { wallet: String, proposal_type: String, tx_hash: String, block_number: Option<u64> },
+    #[serde(rename = "proposal.monthly_winners")]
+    MonthlyWinnersSelected { month: u64, winners: Vec<String> },
+    #[serde(rename = "nft.minted")]
+    NftMinted { wallet: String, fid: u64, token_id: u64 },
+    #[serde(rename = "context.updated")]
+    ContextUpdated { chars: usize },
+}
+
+impl Event {
+    pub const TYPES: [&'static str; 6] = [
+        "proposal.submitted",
+        "proposal.status_changed",
+        "proposal.elevated",
+        "proposal.monthly_winners",
+        "nft.minted",
+        "context.updated",
+    ];
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Event::ProposalSubmitted { .. } => "proposal.submitted",
+            Event::ProposalStatusChanged { .. } => "proposal.status_changed",
+            Event::ProposalElevated { .. } => "proposal.elevated",
+            Event::MonthlyWinnersSelected { .. } => "proposal.monthly_winners",
+            Event::NftMinted { .. } => "nft.minted",
+            Event::ContextUpdated { .. } => "context.updated",
+        }
+    }
+}
+
+// Evento tal como se entrega a los suscriptores: id del stream, tipo, fecha y datos
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Envelope {
+    pub id: String,
+    pub occurred_at: String,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+// Añade el evento al stream y devuelve su id
+pub async fn publish(redis_client: &redis::Client, event: &Event) -> Result<String> {
+    let mut con = redis_client.get_async_connection().await?;
+    let occurred_at = Utc::now().to_rfc3339();
+    let id: String = con.xadd_maxlen(
+        STREAM_KEY,
+        StreamMaxlen::Approx(STREAM_MAXLEN),
+        "*",
+        &[("event", serde_json::to_string(event)?), ("occurred_at", occurred_at)],
+    ).await?;
+    Ok(id)
+}
+
+// Para los handlers: se publica en segundo plano y los fallos solo se registran
+pub fn publish_in_background(redis_client: redis::Client, event: Event) {
+    tokio::spawn(async move {
+        match publish(&redis_client, &event).await {
+            Ok(id) => debug!(id = %id, event = event.kind(), "event published"),
+            Err(e) => {
+                warn!(event = event.kind(), error = %e, "error publishing event");
+                metrics::redis_error("events");
+            },
+        }
+    });
+}
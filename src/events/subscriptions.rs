@@ -0,0 +1,178 @@
+use anyhow::Result;
+use chrono::Utc;
+use rand::RngCore;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use url::{Host, Url};
+use super::Event;
+
+// Hash id -> Subscription en JSON
+const SUBSCRIPTIONS_KEY: &str = "events:subscriptions";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subscription {
+    pub id: String,
+    pub url: String,
+    // Clave del HMAC de las entregas; solo se muestra al crear la suscripción
+    pub secret: String,
+    // Tipos de evento a recibir; vacío = todos
+    #[serde(default)]
+    pub events: Vec<String>,
+    pub created_by: String,
+    pub created_at: String,
+}
+
+impl Subscription {
+    pub fn wants(&self, kind: &str) -> bool {
+        self.events.is_empty() || self.events.iter().any(|event| event == kind)
+    }
+
+    // Copia para listados, sin el secreto
+    pub fn redacted(&self) -> Self {
+        Self { secret: String::new(), ..self.clone() }
+    }
+}
+
+pub enum Invalid {
+    Url,
+    EventType(String),
+}
+
+// Solo https y hacia hosts públicos: una suscripción no puede apuntar a la red
+// interna (loopback, redes privadas, link-local como 169.254.169.254). Los nombres
+// de host se vuelven a comprobar al entregar, después de resolver el DNS
+pub fn check_url(url: &str) -> Option<Url> {
+    let parsed = Url::parse(url).ok()?;
+    if parsed.scheme() != "https" {
+        return None;
+    }
+    match parsed.host()? {
+        Host::Ipv4(ip) if !is_public(IpAddr::V4(ip)) => None,
+        Host::Ipv6(ip) if !is_public(IpAddr::V6(ip)) => None,
+        Host::Domain(domain) if is_local_name(domain) => None,
+        _ => Some(parsed),
+    }
+}
+
+fn is_local_name(domain: &str) -> bool {
+    let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+    domain == "localhost" || [".localhost", ".local", ".internal"].iter().any(|suffix| domain.ends_with(suffix))
+}
+
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, _, _] = ip.octets();
+            // 0.0.0.0/8 y 100.64.0.0/10 (CGNAT) no tienen helper estable
+            !(ip.is_private()
+                || ip.is_loopback()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b)))
+        },
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public(IpAddr::V4(ip)),
+            None => !(ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                || ip.is_unique_local()
+                || ip.is_unicast_link_local()),
+        },
+    }
+}
+
+fn random_hex(bytes: usize) -> String {
+    let mut buffer = vec![0u8; bytes];
+    rand::thread_rng().fill_bytes(&mut buffer);
+    hex::encode(buffer)
+}
+
+pub async fn create(
+    redis_client: &redis::Client,
+    url: &str,
+    events: Vec<String>,
+    created_by: &str,
+) -> Result<std::result::Result<Subscription, Invalid>> {
+    if check_url(url).is_none() {
+        return Ok(Err(Invalid::Url));
+    }
+    if let Some(unknown) = events.iter().find(|event| !Event::TYPES.contains(&event.as_str())) {
+        return Ok(Err(Invalid::EventType(unknown.clone())));
+    }
+
+    let subscription = Subscription {
+        id: random_hex(8),
+        url: url.to_string(),
+        secret: random_hex(32),
+        events,
+        created_by: created_by.to_string(),
+        created_at: Utc::now().to_rfc3339(),
+    };
+    let mut con = redis_client.get_async_connection().await?;
+    let _: () = con.hset(SUBSCRIPTIONS_KEY, &subscription.id, serde_json::to_string(&subscription)?).await?;
+    Ok(Ok(subscription))
+}
+
+pub async fn list(con: &mut redis::aio::Connection) -> redis::RedisResult<Vec<Subscription>> {
+    let stored: Vec<String> = con.hvals(SUBSCRIPTIONS_KEY).await?;
+    let mut subscriptions: Vec<Subscription> = stored.iter().filter_map(|s| serde_json::from_str(s).ok()).collect();
+    subscriptions.sort_by(|a, b| a.created_at.cmp(&b.created_at));
+    Ok(subscriptions)
+}
+
+pub async fn get(con: &mut redis::aio::Connection, id: &str) -> redis::RedisResult<Option<Subscription>> {
+    let stored: Option<String> = con.hget(SUBSCRIPTIONS_KEY, id).await?;
+    Ok(stored.and_then(|s| serde_json::from_str(&s).ok()))
+}
+
+// Devuelve false si no existía
+pub async fn delete(con: &mut redis::aio::Connection, id: &str) -> redis::RedisResult<bool> {
+    let removed: i32 = con.hdel(SUBSCRIPTIONS_KEY, id).await?;
+    Ok(removed == 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accepts_public_https_urls() {
+        assert!(check_url("https://example.com/hooks/qawakun").is_some());
+        assert!(check_url("https://93.184.216.34:8443/hook").is_some());
+        assert!(check_url("https://[2606:4700::1111]/hook").is_some());
+    }
+
+    #[test]
+    fn rejects_plain_http_and_other_schemes() {
+        assert!(check_url("http://example.com/hook").is_none());
+        assert!(check_url("ftp://example.com/hook").is_none());
+        assert!(check_url("not a url").is_none());
+    }
+
+    #[test]
+    fn rejects_internal_hosts() {
+        for url in [
+            "https://localhost/hook",
+            "https://api.localhost./hook",
+            "https://redis.internal/hook",
+            "https://127.0.0.1/hook",
+            "https://10.0.0.5/hook",
+            "https://172.16.0.1/hook",
+            "https://192.168.1.10/hook",
+            "https://169.254.169.254/latest/meta-data",
+            "https://100.64.0.1/hook",
+            "https://0.0.0.0/hook",
+            "https://[::1]/hook",
+            "https://[fd00::1]/hook",
+            "https://[fe80::1]/hook",
+            "https://[::ffff:127.0.0.1]/hook",
+        ] {
+            assert!(check_url(url).is_none(), "{} should be rejected", url);
+        }
+    }
+}
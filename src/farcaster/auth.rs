@@ -6,6 +6,8 @@ use serde_json::json;
 use anyhow::Result;
 use base64::Engine;
 use serde::Deserialize;
+use crate::telemetry;
+use tracing::{error, info};
 
 const API_ROOT: &str = "https://api.warpcast.com";
 
@@ -20,8 +22,32 @@ struct CustodyAddressResult {
     custody_address: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct MeRoot {
+    result: MeResult,
+}
+
+#[derive(Debug, Deserialize)]
+struct MeResult {
+    user: FarcasterUser,
+}
+
+// Usuario dueño del token de sesión
+#[derive(Debug, Clone, Deserialize)]
+pub struct FarcasterUser {
+    pub fid: u64,
+    pub username: String,
+}
+
 pub struct Auth;
 
+// Token de sesión de Warpcast y su caducidad en milisegundos
+#[derive(Debug, Clone)]
+pub struct Session {
+    pub token: String,
+    pub expires_at: i64,
+}
+
 #[derive(Debug, Deserialize)]
 struct AuthResponse {
     result: AuthResult,
@@ -38,19 +64,17 @@ struct Token {
 }
 
 impl Auth {
+    // Pide un token de sesión firmado por la wallet, válido `duration_secs` (300 por defecto)
     pub async fn handle_session(
         wallet: &Wallet<SigningKey>,
         duration_secs: Option<i64>,
-    ) -> Result<String> {
-        let wallet_address = wallet.address();
-        println!("🔑 Iniciando sesión en Farcaster...");
-
-
+    ) -> Result<Session> {
+        let expires_at = Utc::now().timestamp_millis() + (duration_secs.unwrap_or(300) * 1000);
         let payload = json!({
             "method": "generateToken",
             "params": {
                 "timestamp": Utc::now().timestamp_millis(),
-                "expiresAt": Utc::now().timestamp_millis() + (duration_secs.unwrap_or(300) * 1000)
+                "expiresAt": expires_at
             }
         });
 
@@ -74,25 +98,61 @@ impl Auth {
         let response_text = response.text().await?;
 
         if status.is_success() {
-            println!("✅ Autenticación exitosa");
+            info!("Farcaster authentication succeeded");
             
             let auth_response: AuthResponse = serde_json::from_str(&response_text)?;
-            let session_token = auth_response.result.token.secret;
-            
-            match Self::get_custody_address_by_fid(892331, &session_token).await {
-                Ok(custody_address) => {
-                    let _wallet_clean = wallet_address.to_string().to_lowercase().trim_start_matches("0x").to_string();
-                    let _custody_clean = custody_address.to_lowercase().trim_start_matches("0x").to_string();
-                },
-                Err(_) => println!("⚠️ No se pudo verificar custody address")
-            }
-            
-            Ok(session_token)
+            Ok(Session {
+                token: auth_response.result.token.secret,
+                expires_at,
+            })
         } else {
             Err(anyhow::anyhow!("Error de autenticación: {}", response_text))
         }
     }
 
+    // Resuelve la cuenta del token y comprueba que su custody address es la wallet
+    // que firma las sesiones. Si no coincide el bot estaría publicando con otra cuenta
+    pub async fn verify_custody(wallet: &Wallet<SigningKey>, token: &str) -> Result<FarcasterUser> {
+        let me = Self::get_me(token).await?;
+        info!(fid = me.fid, username = %me.username, "Farcaster session resolved");
+
+        let custody_address = Self::get_custody_address_by_fid(me.fid, token).await?;
+        let wallet_address = format!("{:?}", wallet.address());
+        if !custody_address.eq_ignore_ascii_case(&wallet_address) {
+            error!(
+                fid = me.fid,
+                custody = %custody_address,
+                wallet = %telemetry::redact_wallet(&wallet_address),
+                "wallet derived from MNEMONIC is not the custody address of the bot FID"
+            );
+            return Err(anyhow::anyhow!(
+                "Derived wallet is not the custody address of FID {} ({})",
+                me.fid, custody_address
+            ));
+        }
+
+        Ok(me)
+    }
+
+    // Resuelve el FID y username de la cuenta autenticada
+    pub async fn get_me(token: &str) -> Result<FarcasterUser> {
+        let response = reqwest::Client::new()
+            .get(format!("{}/v2/me", API_ROOT))
+            .header("Authorization", format!("Bearer {}", token))
+            .send()
+            .await?;
+
+        let status = response.status();
+        let response_text = response.text().await?;
+
+        if !status.is_success() {
+            return Err(anyhow::anyhow!("Error getting authenticated user: {}", response_text));
+        }
+
+        let me: MeRoot = serde_json::from_str(&response_text)?;
+        Ok(me.result.user)
+    }
+
     async fn get_custody_address_by_fid(fid: u64, token: &str) -> Result<String> {
         let client = reqwest::Client::new();
         let response = client
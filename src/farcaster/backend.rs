@@ -0,0 +1,46 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use std::env;
+use std::sync::Arc;
+use super::auth::FarcasterUser;
+use super::cast::{Cast, CastResult};
+use super::composer::CastPayload;
+use super::hub::HubBackend;
+use super::memory::MemoryBackend;
+use super::warpcast::WarpcastBackend;
+
+// Cliente de Farcaster desacoplado del proveedor: Warpcast, un Hub o uno en memoria
+#[async_trait]
+pub trait FarcasterBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    // Cuenta con la que publica el bot
+    fn bot(&self) -> &FarcasterUser;
+
+    // Menciones y respuestas al bot, más recientes primero
+    async fn get_mentions(&self, limit: Option<i32>, cursor: Option<&str>) -> Result<CastResult>;
+
+    // Un cast por hash; algunos backends necesitan el FID del autor para buscarlo
+    async fn get_cast(&self, fid: Option<u64>, hash: &str) -> Result<Option<Cast>>;
+
+    // Respuestas directas a un cast
+    async fn get_replies(&self, parent_fid: u64, parent_hash: &str) -> Result<Vec<Cast>>;
+
+    // Conversación completa bajo la raíz `thread_hash`, sin incluir necesariamente la raíz
+    async fn get_thread(&self, root_fid: Option<u64>, thread_hash: &str) -> Result<Vec<Cast>>;
+
+    // Publica un solo cast; el troceo y los embeds los resuelve el composer
+    async fn publish_cast(&self, payload: &CastPayload) -> Result<Cast>;
+}
+
+// FARCASTER_BACKEND=warpcast (por defecto) | hub | memory
+pub async fn from_env() -> Result<Arc<dyn FarcasterBackend>> {
+    let kind = env::var("FARCASTER_BACKEND").unwrap_or_else(|_| "warpcast".to_string());
+
+    match kind.to_lowercase().as_str() {
+        "warpcast" => Ok(Arc::new(WarpcastBackend::from_env().await?)),
+        "hub" => Ok(Arc::new(HubBackend::from_env().await?)),
+        "memory" => Ok(Arc::new(MemoryBackend::from_env()?)),
+        other => Err(anyhow::anyhow!("Unknown FARCASTER_BACKEND: {}", other)),
+    }
+}
@@ -1,43 +1,60 @@
 use serde::{Deserialize, Serialize};
 use anyhow::Result;
+use async_trait::async_trait;
 use redis::AsyncCommands;
-use serde_json::json;
 use chrono::{DateTime, Utc};
-use crate::openai_methods::get_text::handle_conversation;
-use std::env;
-use tokio;
+use std::collections::HashMap;
+use crate::api::admin::{is_paused, Control};
+use crate::api::health::FARCASTER_LAST_POLL_KEY;
+use crate::metrics;
+use crate::social::{AgentPipeline, Author, Batch, InboundMessage, Outcome, SocialChannel, ThreadEntry};
+use std::sync::Arc;
+use super::auth::FarcasterUser;
+use super::backend::FarcasterBackend;
+use super::composer::{CastDraft, CastParent};
+use tracing::{debug, error, info, warn};
 
-const API_ROOT: &str = "https://api.warpcast.com";
 const LAST_PROCESSED_CAST_KEY: &str = "farcaster:last_processed_cast";
-const MY_FID: u64 = 979204;  // Tu FID
-const BOT_USERNAME: &str = "@qawakun";  // Agregar esta constante
-
-#[derive(Debug, Deserialize)]
-pub struct CastRoot {
-    result: CastResult,
-}
+// Límites al reconstruir el contexto de un hilo
+const MAX_THREAD_DEPTH: usize = 10;
+const MAX_THREAD_MESSAGES: usize = 20;
+// Páginas máximas del feed de menciones a recorrer por poll, y casts por página
+const MAX_MENTION_PAGES: usize = 5;
+const MENTION_PAGE_SIZE: i32 = 25;
+pub const POLL_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60*15); // 15 minutos
 
 #[derive(Debug, Deserialize)]
 pub struct CastResult {
-    casts: Vec<Cast>,
+    pub(super) casts: Vec<Cast>,
     #[serde(default)]
-    cursor: Option<String>,
+    pub(super) cursor: Option<String>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+// La API de Warpcast devuelve camelCase; en Redis se guardan en snake_case
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct Cast {
-    hash: String,
-    thread_hash: Option<String>,
-    parent_hash: Option<String>,
-    author: CastAuthor,
-    text: String,
-    timestamp: i64,
+    pub(super) hash: String,
+    #[serde(alias = "threadHash")]
+    pub(super) thread_hash: Option<String>,
+    #[serde(alias = "parentHash")]
+    pub(super) parent_hash: Option<String>,
+    #[serde(default, alias = "parentAuthor")]
+    pub(super) parent_author: Option<CastAuthor>,
+    // Canal en el que se publicó, si lo hay
+    #[serde(default, alias = "parentUrl")]
+    pub(super) parent_url: Option<String>,
+    pub(super) author: CastAuthor,
+    pub(super) text: String,
+    pub(super) timestamp: i64,
+    #[serde(default)]
+    pub(super) mentions: Vec<CastAuthor>,
 }
 
-#[derive(Debug, Deserialize, Serialize)]
+#[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct CastAuthor {
-    fid: u64,
-    username: String,
+    pub(super) fid: u64,
+    #[serde(default)]
+    pub(super) username: String,
 }
 
 impl Cast {
@@ -48,289 +65,358 @@ impl Cast {
     }
 }
 
+// Un cast sin thread_hash es la raíz de su propio hilo
+fn thread_key(cast: &Cast) -> String {
+    cast.thread_hash.clone().unwrap_or_else(|| cast.hash.clone())
+}
+
+fn conversation_key(thread: &str) -> String {
+    format!("farcaster:conversation:{}", thread)
+}
+
+fn inbound(cast: Cast) -> InboundMessage<Cast> {
+    InboundMessage {
+        id: cast.hash.clone(),
+        author: Author { id: cast.author.fid.to_string(), handle: Some(cast.author.username.clone()) },
+        text: cast.text.clone(),
+        event: cast,
+    }
+}
+
 pub struct CastClient {
-    session_token: String,
+    backend: Arc<dyn FarcasterBackend>,
     redis_client: redis::Client,
+    pipeline: AgentPipeline,
 }
 
 impl CastClient {
-    pub fn new(session_token: String, redis_url: &str) -> Result<Self> {
+    pub fn new(backend: Arc<dyn FarcasterBackend>, redis_url: &str) -> Result<Self> {
         let redis_client = redis::Client::open(redis_url)?;
-        Ok(Self { session_token, redis_client })
+        let bot = backend.bot();
+        info!(backend = backend.name(), fid = bot.fid, username = %bot.username, "Farcaster bot identity resolved");
+        let pipeline = AgentPipeline::new(redis_client.clone());
+        Ok(Self { backend, redis_client, pipeline })
     }
 
-    pub async fn fetch_and_display_recent_casts(&self, fid: u64, limit: Option<i32>) -> Result<()> {
-        println!("📥 Obteniendo casts recientes...");
-        
-        let casts = self.get_casts_by_fid(fid, limit, None).await?;
-        
-        println!("\n=== Casts Encontrados ===");
-        for cast in casts.result.casts {
-            println!("\n🔷 Cast ID: {}", cast.hash);
-            println!("👤 Autor: @{} (FID: {})", cast.author.username, cast.author.fid);
-            println!("📝 Contenido: {}", cast.text);
-            println!("⏰ Timestamp: {}", cast.format_timestamp());
-            
-            if let Some(ref thread) = cast.thread_hash {
-                println!("🧵 Thread ID: {}", thread);
-            }
-            if let Some(ref parent) = cast.parent_hash {
-                println!("↩️ Respuesta a: {}", parent);
+    // La identidad del bot sale del backend (sesión o configuración), no de constantes
+    fn bot(&self) -> &FarcasterUser {
+        self.backend.bot()
+    }
+
+    // Página del feed de menciones y respuestas al bot (más recientes primero)
+    pub async fn get_mentions(&self, limit: Option<i32>, cursor: Option<&str>) -> Result<CastResult> {
+        let mentions = self.backend.get_mentions(limit, cursor).await?;
+        self.cache_casts(&mentions.casts).await?;
+        Ok(mentions)
+    }
+
+    // Respuestas directas a un cast del bot
+    pub async fn get_replies(&self, hash: &str) -> Result<Vec<Cast>> {
+        let replies = self.backend.get_replies(self.bot().fid, hash).await?;
+        self.cache_casts(&replies).await?;
+        Ok(replies)
+    }
+
+    async fn cache_casts(&self, casts: &[Cast]) -> Result<()> {
+        let mut con = self.redis_client.get_async_connection().await?;
+        for cast in casts {
+            let key = conversation_key(&thread_key(cast));
+            let _: () = con.hset(&key, cast.hash.clone(), serde_json::to_string(cast)?).await?;
+            let _: () = con.expire(&key, 24*60*60).await?; // Expira en 24 horas
+        }
+        Ok(())
+    }
+
+    // Publica un borrador; si no cabe en un cast sale como cadena numerada de
+    // respuestas. Todas las partes se guardan en `thread` (o en el hilo de la primera)
+    pub async fn publish(&self, draft: &CastDraft, thread: Option<&str>) -> Result<Vec<Cast>> {
+        let mut published: Vec<Cast> = Vec::new();
+
+        for mut payload in draft.compose() {
+            if let Some(previous) = published.last() {
+                payload.parent = Some(CastParent::Cast {
+                    hash: previous.hash.clone(),
+                    fid: previous.author.fid,
+                });
             }
-            
-            // Verificar si es parte de una conversación
-            let mut con = self.redis_client.get_async_connection().await?;
-            let conversation_key = format!("farcaster:conversation:{}", 
-                cast.thread_hash.as_deref().unwrap_or(&cast.hash));
-            
-            let conversation_exists: bool = con.exists(&conversation_key).await?;
-            if conversation_exists {
-                println!("💬 Parte de una conversación activa");
+
+            let mut cast = self.backend.publish_cast(&payload).await?;
+            if cast.thread_hash.is_none() {
+                cast.thread_hash = thread
+                    .map(String::from)
+                    .or_else(|| published.first().map(thread_key));
             }
+
+            // Guardar en Redis para mantener el historial de la conversación
+            self.cache_casts(std::slice::from_ref(&cast)).await?;
+            published.push(cast);
         }
 
-        Ok(())
+        if published.len() > 1 {
+            info!(parts = published.len(), "long cast published as a reply chain");
+        }
+        Ok(published)
     }
 
-    pub async fn get_casts_by_fid(&self, fid: u64, limit: Option<i32>, cursor: Option<&str>) -> Result<CastRoot> {
-        let mut url = format!("{}/v2/casts?fid={}", API_ROOT, fid);
-        if let Some(limit) = limit {
-            url.push_str(&format!("&limit={}", limit));
+    pub async fn poll_mentions(&self) -> Result<()> {
+        // Con las respuestas pausadas no se leen menciones: el cursor no avanza y
+        // se contestan al reanudar
+        if self.replies_paused().await {
+            info!("Farcaster replies paused, leaving mentions for later");
+            metrics::mention("farcaster", "paused");
+            return Ok(());
         }
-        if let Some(cursor) = cursor {
-            url.push_str(&format!("&cursor={}", cursor));
+
+        info!("looking for Farcaster mentions");
+        let batch = self.fetch().await?;
+        info!(count = batch.messages.len(), "mentions retrieved");
+
+        // El cursor avanza mención a mención y no pasa de una que hay que reintentar:
+        // el siguiente poll vuelve a leerla y las ya contestadas se saltan por la marca
+        let mut completed = true;
+        for mention in &batch.messages {
+            if !self.process_mention(mention).await? {
+                completed = false;
+                break;
+            }
+            self.commit(&mention.id).await?;
+        }
+        if let (true, Some(hash)) = (completed, &batch.cursor) {
+            self.commit(hash).await?;
         }
 
-        let client = reqwest::Client::new();
-        let response = client
-            .get(&url)
-            .header("Authorization", format!("Bearer {}", self.session_token))
-            .send()
-            .await?;
+        let mut con = self.redis_client.get_async_connection().await?;
+        let _: () = con.set(FARCASTER_LAST_POLL_KEY, Utc::now().timestamp()).await?;
+
+        Ok(())
+    }
 
-        let casts: CastRoot = response.json().await?;
-        
+    pub async fn replies_paused(&self) -> bool {
+        is_paused(&self.redis_client, Control::FarcasterReplies).await
+    }
 
-        let mut con = self.redis_client.get_async_connection().await?;
-        for cast in &casts.result.casts {
-            let key = format!("farcaster:conversation:{}", cast.thread_hash.as_deref().unwrap_or(&cast.hash));
-            let _: () = con.hset(&key, cast.hash.clone(), serde_json::to_string(cast)?).await?;
-            let _: () = con.expire(&key, 24*60*60).await?; // Expira en 24 horas
+    // Menciones que llegan fuera del poll (webhook, respuestas al lore del canal);
+    // se filtran igual que las del poll. Quien llama comprueba antes la pausa.
+    // Falso si la respuesta falló y hay que volver a intentarlo
+    pub async fn handle_queued_mention(&self, cast: Cast) -> Result<bool> {
+        if cast.author.fid == self.bot().fid || !self.is_mention_to_us(&cast).await? {
+            debug!(hash = %cast.hash, "queued cast is not a mention, ignoring");
+            return Ok(true);
         }
 
-        Ok(casts)
+        self.cache_casts(std::slice::from_ref(&cast)).await?;
+        self.process_mention(&inbound(cast)).await
     }
 
-    pub async fn publish_cast(&self, content: &str, reply_to: Option<(&str, u64)>) -> Result<Cast> {
-        let payload = match reply_to {
-            Some((parent_hash, parent_fid)) => json!({
-                "parent": {
-                    "hash": parent_hash,
-                    "fid": parent_fid
-                },
-                "text": content.trim()
-            }),
-            None => json!({ 
-                "text": content.trim() 
-            })
+    // El webhook y el poll de reconciliación comparten la marca, así que cada
+    // mención se contesta una sola vez. Si la respuesta falla se libera la marca y
+    // devuelve false para reintentarla (hasta agotar los intentos)
+    async fn process_mention(&self, mention: &InboundMessage<Cast>) -> Result<bool> {
+        if !self.pipeline.claim(self, &mention.id).await? {
+            debug!(hash = %mention.id, "mention already handled");
+            return Ok(true);
+        }
+
+        match self.pipeline.respond(self, mention).await {
+            Ok(Outcome::Replied(_)) => info!(hash = %mention.id, "replied to mention"),
+            Ok(Outcome::Spam) => {},
+            Err(e) => {
+                error!(hash = %mention.id, error = %e, "failed to reply");
+                metrics::mention("farcaster", "failed");
+                return Ok(!self.pipeline.release(self, &mention.id).await?);
+            },
+        }
+        Ok(true)
+    }
+
+    // Una mención es un @username en el texto o una respuesta directa a un cast
+    // del bot; si el feed no trae el autor del padre se busca el cast padre
+    async fn is_mention_to_us(&self, cast: &Cast) -> Result<bool> {
+        let handle = format!("@{}", self.bot().username.to_lowercase());
+        if cast.mentions.iter().any(|m| m.fid == self.bot().fid)
+            || cast.text.to_lowercase().contains(&handle)
+        {
+            return Ok(true);
+        }
+
+        let parent_hash = match &cast.parent_hash {
+            Some(parent_hash) => parent_hash,
+            None => return Ok(false),
         };
 
-        let response = reqwest::Client::new()
-            .post(format!("{}/v2/casts", API_ROOT))
-            .header("Content-Type", "application/json")
-            .header("Authorization", format!("Bearer {}", self.session_token))
-            .json(&payload)
-            .send()
-            .await?
-            .text()
-            .await?;
+        if let Some(parent_author) = &cast.parent_author {
+            return Ok(parent_author.fid == self.bot().fid);
+        }
+
+        let parent = self.find_cast(&thread_key(cast), parent_hash, None).await?;
+        Ok(parent.map(|p| p.author.fid == self.bot().fid).unwrap_or(false))
+    }
 
-        let cast: Cast = serde_json::from_str(&response)?;
-        
-        // Guardar en Redis para mantener el historial de la conversación
+    // Busca un cast primero en la caché del hilo y si no en el backend
+    async fn find_cast(&self, thread: &str, hash: &str, fid: Option<u64>) -> Result<Option<Cast>> {
         let mut con = self.redis_client.get_async_connection().await?;
-        let key = format!("farcaster:conversation:{}", 
-            cast.thread_hash.as_deref().unwrap_or(&cast.hash));
-        let _: () = con.hset(&key, &cast.hash, &response).await?;
-        let _: () = con.expire(&key, 24*60*60).await?;
+        let cached: Option<String> = con.hget(conversation_key(thread), hash).await?;
+        if let Some(cast) = cached.and_then(|c| serde_json::from_str::<Cast>(&c).ok()) {
+            return Ok(Some(cast));
+        }
 
+        let cast = self.backend.get_cast(fid, hash).await?;
+        if let Some(cast) = &cast {
+            self.cache_casts(std::slice::from_ref(cast)).await?;
+        }
         Ok(cast)
     }
 
-    pub async fn fetch_and_process_mentions(&self, fid: u64, limit: Option<i32>) -> Result<()> {
-        println!("👂 Looking for Farcaster mentions...");
+    // Contexto del hilo: los casts ya vistos del hilo, la cadena de padres hasta la
+    // raíz y el resto de la conversación bajo la raíz, en orden cronológico
+    async fn load_thread(&self, cast: &Cast) -> Result<Vec<Cast>> {
+        let thread = thread_key(cast);
         let mut con = self.redis_client.get_async_connection().await?;
-        let last_processed: Option<String> = con.get(LAST_PROCESSED_CAST_KEY).await.ok();
-        
-        let casts = self.get_casts_by_fid(fid, limit, None).await?;
-        println!("📥 {} casts retrieved", casts.result.casts.len());
-        
-        let mut new_mentions = Vec::new();
-        let mut found_last = false;
-
-        for cast in &casts.result.casts {
-            if let Some(ref last) = last_processed {
-                if cast.hash == *last {
-                    found_last = true;
-                    continue;
-                }
+        let cached: Vec<String> = con.hvals(conversation_key(&thread)).await?;
+
+        let mut casts: HashMap<String, Cast> = cached
+            .iter()
+            .filter_map(|c| serde_json::from_str::<Cast>(c).ok())
+            .map(|c| (c.hash.clone(), c))
+            .collect();
+
+        let mut next = cast.parent_hash.clone().map(|hash| (hash, cast.parent_author.as_ref().map(|a| a.fid)));
+        let mut depth = 0;
+        while let Some((hash, fid)) = next.take() {
+            if depth >= MAX_THREAD_DEPTH {
+                break;
             }
+            depth += 1;
+
+            let parent = match casts.get(&hash) {
+                Some(parent) => Some(parent.clone()),
+                None => match self.find_cast(&thread, &hash, fid).await {
+                    Ok(parent) => parent,
+                    Err(e) => {
+                        warn!(hash = %hash, error = %e, "could not load parent cast");
+                        None
+                    }
+                },
+            };
 
-            if !found_last || last_processed.is_none() {
-                if cast.author.fid != MY_FID && self.is_mention_to_us(&cast) {
-                    println!("\n🎯 New mention needs response: {}", cast.text);
-                    new_mentions.push(cast.clone());
-                }
+            if let Some(parent) = parent {
+                next = parent.parent_hash.clone().map(|h| (h, parent.parent_author.as_ref().map(|a| a.fid)));
+                casts.insert(parent.hash.clone(), parent);
             }
         }
 
-        if let Some(first_cast) = casts.result.casts.first() {
-            let _: () = con.set(LAST_PROCESSED_CAST_KEY, &first_cast.hash).await?;
-        }
-
-        if !new_mentions.is_empty() {
-            println!("\n🔔 Processing {} new mentions", new_mentions.len());
-            new_mentions.reverse();
-            
-            for mention in new_mentions {
-                match self.handle_mention(&mention).await {
-                    Ok(_) => println!("✅ Successfully replied to: {}", mention.text),
-                    Err(e) => println!("❌ Failed to reply: {}", e),
-                }
+        // Las demás ramas de la conversación; el FID de la raíz sale del recorrido de padres
+        if cast.parent_hash.is_some() {
+            let root_fid = casts.get(&thread).map(|root| root.author.fid);
+            match self.backend.get_thread(root_fid, &thread).await {
+                Ok(conversation) => {
+                    if let Err(e) = self.cache_casts(&conversation).await {
+                        warn!(thread = %thread, error = %e, "could not cache thread casts");
+                    }
+                    for reply in conversation {
+                        casts.entry(reply.hash.clone()).or_insert(reply);
+                    }
+                },
+                Err(e) => warn!(thread = %thread, error = %e, "could not load thread conversation"),
             }
         }
 
-        Ok(())
+        casts.remove(&cast.hash);
+        let mut thread: Vec<Cast> = casts.into_values().collect();
+        thread.sort_by_key(|c| c.timestamp);
+        let skip = thread.len().saturating_sub(MAX_THREAD_MESSAGES);
+        Ok(thread.into_iter().skip(skip).collect())
     }
+}
 
-    fn is_mention_to_us(&self, cast: &Cast) -> bool {
-        let is_mention = cast.text.to_lowercase().contains(&BOT_USERNAME.to_lowercase());
-        
-        let is_reply_to_us = match &cast.parent_hash {
-            Some(parent_hash) => {
-                let rt = tokio::runtime::Runtime::new().unwrap();
-                
-                rt.block_on(async {
-                    if let Ok(mut con) = self.redis_client.get_async_connection().await {
-                        let conversation_key = format!("farcaster:conversation:{}", 
-                            cast.thread_hash.as_deref().unwrap_or(parent_hash));
-                        
-                        match con.hget::<_, _, Option<String>>(&conversation_key, parent_hash).await {
-                            Ok(Some(parent_cast_str)) => {
-                                if let Ok(parent_cast) = serde_json::from_str::<Cast>(&parent_cast_str) {
-                                    parent_cast.author.fid == MY_FID || 
-                                    parent_cast.author.fid == cast.author.fid
-                                } else {
-                                    false
-                                }
-                            },
-                            _ => false
-                        }
-                    } else {
-                        false
-                    }
-                })
-            },
-            None => false
-        };
+#[async_trait]
+impl SocialChannel for CastClient {
+    type Event = Cast;
 
-        is_mention || is_reply_to_us
+    fn name(&self) -> &'static str {
+        "farcaster"
     }
 
-    async fn handle_mention(&self, cast: &Cast) -> Result<()> {
-        if self.is_spam(&cast.text) {
-            return Ok(());
-        }
-
-        let conversation_key = format!("farcaster:conversation:{}", 
-            cast.thread_hash.as_deref().unwrap_or(&cast.hash));
-        
-        let api_key = env::var("OPENAI_API_KEY")
-            .map_err(|e| anyhow::anyhow!("Failed to get OPENAI_API_KEY: {}", e))?;
-        let context = std::fs::read_to_string("context.md")
-            .map_err(|e| anyhow::anyhow!("Failed to read context.md: {}", e))?;
-
-        let response = handle_conversation(
-            &self.redis_client,
-            &api_key,
-            &cast.author.username,
-            &context,
-            &cast.text
+    fn channel_prompt(&self) -> String {
+        format!(
+            "You are @{} replying inside a Farcaster thread with several participants. \
+             Messages from other people are prefixed with their @username. Reply only to the last message, \
+             staying consistent with what you already said in the thread.",
+            self.bot().username
         )
-        .await
-        .map_err(|e| anyhow::anyhow!("OpenAI API error: {}", e))?;
-
-        if let Some(message) = response["choices"][0]["message"]["content"].as_str() {
-            let reply = self.publish_cast(message, Some((&cast.hash, cast.author.fid))).await
-                .map_err(|e| anyhow::anyhow!("Failed to publish cast: {}", e))?;
-
-            let mut con = self.redis_client.get_async_connection().await
-                .map_err(|e| anyhow::anyhow!("Redis connection error: {}", e))?;
-            
-            con.hset(&conversation_key, &reply.hash, serde_json::to_string(&reply)?)
-                .await
-                .map_err(|e| anyhow::anyhow!("Failed to save to Redis: {}", e))?;
-            
-            con.expire(&conversation_key, 24*60*60).await
-                .map_err(|e| anyhow::anyhow!("Failed to set expiry: {}", e))?;
-        } else {
-            return Err(anyhow::anyhow!("No response content in OpenAI result"));
-        }
-
-        Ok(())
     }
 
-    fn is_spam(&self, text: &str) -> bool {
-        use lazy_static::lazy_static;
-        use regex::Regex;
-
-        lazy_static! {
-            static ref SPAM_PATTERNS: Vec<Regex> = vec![
-                Regex::new(r"(?i)crypto").unwrap(),
-                Regex::new(r"(?i)pump").unwrap(),
-                Regex::new(r"(?i)airdrop").unwrap(),
-                Regex::new(r"(?i)blast").unwrap(),
-                Regex::new(r"(?i)token").unwrap(),
-                Regex::new(r"https?://").unwrap(),
-                Regex::new(r"(?i)drop").unwrap(),
-            ];
-        }
+    // Recorre el feed hasta llegar a la última mención procesada. En el primer
+    // arranque solo se mira la primera página para no contestar todo el historial.
+    // El cursor es el cast más reciente del feed, sea o no una mención
+    async fn fetch(&self) -> Result<Batch<Cast>> {
+        let mut con = self.redis_client.get_async_connection().await?;
+        let last_processed: Option<String> = con.get(LAST_PROCESSED_CAST_KEY).await.ok();
 
-        let mention_count = text.matches('@').count();
-        if mention_count > 3 {
-            println!("🚫 Demasiadas menciones detectadas");
-            return true;
-        }
+        let mut new_mentions = Vec::new();
+        let mut newest_hash: Option<String> = None;
+        let mut cursor: Option<String> = None;
 
-        for pattern in SPAM_PATTERNS.iter() {
-            if pattern.is_match(text) {
-                println!("🚫 Patrón de spam detectado");
-                return true;
-            }
-        }
+        for page in 0..MAX_MENTION_PAGES {
+            let result = self.get_mentions(Some(MENTION_PAGE_SIZE), cursor.as_deref()).await?;
+            debug!(page, count = result.casts.len(), "mention page retrieved");
 
-        false
-    }
+            if newest_hash.is_none() {
+                newest_hash = result.casts.first().map(|cast| cast.hash.clone());
+            }
 
-    async fn get_casts_with_retry(&self, fid: u64, limit: Option<i32>, cursor: Option<&str>) -> Result<CastRoot> {
-        let max_retries = 3;
-        let mut retry_count = 0;
-        let mut delay = 2;
-
-        loop {
-            match self.get_casts_by_fid(fid, limit, cursor).await {
-                Ok(casts) => return Ok(casts),
-                Err(e) => {
-                    retry_count += 1;
-                    if retry_count >= max_retries {
-                        return Err(e);
-                    }
-                    println!("⚠️ Error getting casts (attempt {}/{}): {}", retry_count, max_retries, e);
-                    println!("🕐 Waiting {} seconds before retry...", delay);
-                    tokio::time::sleep(tokio::time::Duration::from_secs(delay)).await;
-                    delay *= 2; // Exponential backoff
+            let mut found_last = false;
+            for cast in result.casts {
+                if last_processed.as_deref() == Some(cast.hash.as_str()) {
+                    found_last = true;
+                    break;
+                }
+                if cast.author.fid != self.bot().fid && self.is_mention_to_us(&cast).await? {
+                    info!(hash = %cast.hash, author_fid = cast.author.fid, "new mention needs response");
+                    new_mentions.push(cast);
                 }
             }
+
+            cursor = result.cursor;
+            if found_last || last_processed.is_none() || cursor.is_none() {
+                break;
+            }
         }
+
+        new_mentions.reverse();
+        Ok(Batch {
+            messages: new_mentions.into_iter().map(inbound).collect(),
+            cursor: newest_hash,
+        })
     }
-} 
\ No newline at end of file
+
+    async fn commit(&self, cursor: &str) -> Result<()> {
+        let mut con = self.redis_client.get_async_connection().await?;
+        let _: () = con.set(LAST_PROCESSED_CAST_KEY, cursor).await?;
+        Ok(())
+    }
+
+    async fn thread(&self, message: &InboundMessage<Cast>) -> Result<Vec<ThreadEntry>> {
+        Ok(self.load_thread(&message.event)
+            .await?
+            .into_iter()
+            .map(|cast| ThreadEntry {
+                from_bot: cast.author.fid == self.bot().fid,
+                author: Author {
+                    handle: Some(cast.author.username.clone()).filter(|username| !username.is_empty()),
+                    id: cast.author.fid.to_string(),
+                },
+                text: cast.text,
+            })
+            .collect())
+    }
+
+    // La respuesta queda en el mismo hilo aunque el backend no informe thread_hash
+    async fn send_reply(&self, message: &InboundMessage<Cast>, text: &str) -> Result<()> {
+        let cast = &message.event;
+        let draft = CastDraft::new(text).reply_to(&cast.hash, cast.author.fid);
+        self.publish(&draft, Some(&thread_key(cast))).await
+            .map_err(|e| anyhow::anyhow!("Failed to publish cast: {}", e))?;
+        Ok(())
+    }
+}
@@ -0,0 +1,173 @@
+use anyhow::Result;
+use std::env;
+use url::Url;
+use crate::api::cdp::nfts::PINATA_GATEWAY;
+use crate::text;
+
+// Límites del protocolo: texto en bytes (no caracteres) y embeds por cast
+pub const MAX_CAST_BYTES: usize = 320;
+pub const MAX_EMBEDS: usize = 2;
+const MAX_EMBED_URL_BYTES: usize = 256;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CastParent {
+    // Respuesta a un cast (hash, fid del autor)
+    Cast { hash: String, fid: u64 },
+    // Publicación en un canal, identificado por su parent_url
+    Url(String),
+}
+
+// Un cast listo para enviar al backend: texto dentro del límite y como mucho MAX_EMBEDS
+#[derive(Debug, Clone)]
+pub struct CastPayload {
+    pub text: String,
+    pub embeds: Vec<String>,
+    pub parent: Option<CastParent>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct CastDraft {
+    text: String,
+    embeds: Vec<String>,
+    parent: Option<CastParent>,
+}
+
+impl CastDraft {
+    pub fn new(text: &str) -> Self {
+        Self {
+            text: text.trim().to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn reply_to(mut self, hash: &str, fid: u64) -> Self {
+        self.parent = Some(CastParent::Cast { hash: hash.to_string(), fid });
+        self
+    }
+
+    pub fn in_channel(mut self, parent_url: &str) -> Self {
+        self.parent = Some(CastParent::Url(parent_url.to_string()));
+        self
+    }
+
+    pub fn embed(mut self, url: &str) -> Result<Self> {
+        let parsed = Url::parse(url.trim())?;
+        if parsed.scheme() != "https" && parsed.scheme() != "http" {
+            return Err(anyhow::anyhow!("Embed must be an http(s) URL: {}", url));
+        }
+        if parsed.as_str().len() > MAX_EMBED_URL_BYTES {
+            return Err(anyhow::anyhow!("Embed URL longer than {} bytes", MAX_EMBED_URL_BYTES));
+        }
+        if !self.embeds.iter().any(|e| e == parsed.as_str()) {
+            self.embeds.push(parsed.to_string());
+        }
+        Ok(self)
+    }
+
+    // URL pública del Frame (FRAME_URL)
+    pub fn embed_frame(self) -> Result<Self> {
+        let frame_url = env::var("FRAME_URL")
+            .map_err(|_| anyhow::anyhow!("FRAME_URL not configured"))?;
+        self.embed(&frame_url)
+    }
+
+    // Imagen de un NFT; acepta ipfs://CID, un CID suelto o una URL del gateway
+    pub fn embed_nft_image(self, image: &str) -> Result<Self> {
+        let image = image.trim();
+        let url = if image.starts_with("http://") || image.starts_with("https://") {
+            image.to_string()
+        } else {
+            format!("{}{}", PINATA_GATEWAY, image.trim_start_matches("ipfs://"))
+        };
+        self.embed(&url)
+    }
+
+    // Parte el borrador en casts encadenados. El primero lleva el padre; los
+    // siguientes se publican como respuesta al anterior. Los embeds van en los
+    // primeros casts, MAX_EMBEDS por cast
+    pub fn compose(&self) -> Vec<CastPayload> {
+        let mut parts = text::split_numbered(&self.text, MAX_CAST_BYTES, str::len);
+        let embed_casts = self.embeds.len().div_ceil(MAX_EMBEDS);
+        while parts.len() < embed_casts {
+            parts.push(String::new());
+        }
+
+        parts
+            .into_iter()
+            .enumerate()
+            .map(|(i, text)| CastPayload {
+                text,
+                embeds: self.embeds.iter().skip(i * MAX_EMBEDS).take(MAX_EMBEDS).cloned().collect(),
+                parent: if i == 0 { self.parent.clone() } else { None },
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_draft_is_one_cast_with_parent() {
+        let payloads = CastDraft::new("  hola  ").reply_to("0xabc", 7).compose();
+        assert_eq!(payloads.len(), 1);
+        assert_eq!(payloads[0].text, "hola");
+        assert_eq!(payloads[0].parent, Some(CastParent::Cast { hash: "0xabc".to_string(), fid: 7 }));
+    }
+
+    #[test]
+    fn long_draft_is_split_by_bytes_and_only_first_has_parent() {
+        let text = "El cóndor vuela sobre los Andes. ".repeat(30);
+        let payloads = CastDraft::new(&text).in_channel("https://warpcast.com/~/channel/qawakun").compose();
+        assert!(payloads.len() > 1);
+        for (i, payload) in payloads.iter().enumerate() {
+            assert!(payload.text.len() <= MAX_CAST_BYTES, "part {} is {} bytes", i, payload.text.len());
+            assert!(payload.text.ends_with(&format!(" ({}/{})", i + 1, payloads.len())));
+        }
+        assert_eq!(payloads[0].parent, Some(CastParent::Url("https://warpcast.com/~/channel/qawakun".to_string())));
+        assert!(payloads[1..].iter().all(|payload| payload.parent.is_none()));
+    }
+
+    #[test]
+    fn embeds_fill_the_first_casts_and_add_casts_if_needed() {
+        let mut draft = CastDraft::new("mira");
+        for i in 0..5 {
+            draft = draft.embed(&format!("https://example.com/{}", i)).unwrap();
+        }
+        let payloads = draft.compose();
+        assert_eq!(payloads.len(), 3);
+        assert_eq!(payloads[0].text, "mira");
+        assert_eq!(payloads[0].embeds, vec!["https://example.com/0", "https://example.com/1"]);
+        assert_eq!(payloads[1].text, "");
+        assert_eq!(payloads[1].embeds.len(), MAX_EMBEDS);
+        assert_eq!(payloads[2].embeds, vec!["https://example.com/4"]);
+    }
+
+    #[test]
+    fn embeds_are_validated_and_deduplicated() {
+        let draft = CastDraft::new("x")
+            .embed("https://example.com/a").unwrap()
+            .embed(" https://example.com/a ").unwrap();
+        assert_eq!(draft.compose()[0].embeds, vec!["https://example.com/a"]);
+
+        assert!(CastDraft::new("x").embed("ftp://example.com/a").is_err());
+        assert!(CastDraft::new("x").embed("not a url").is_err());
+        assert!(CastDraft::new("x").embed(&format!("https://example.com/{}", "a".repeat(MAX_EMBED_URL_BYTES))).is_err());
+    }
+
+    #[test]
+    fn nft_images_go_through_the_gateway() {
+        let payloads = CastDraft::new("nft")
+            .embed_nft_image("ipfs://QmCid").unwrap()
+            .embed_nft_image("QmOther").unwrap()
+            .embed_nft_image("https://example.com/image.png").unwrap()
+            .compose();
+        let embeds: Vec<String> = payloads.into_iter().flat_map(|payload| payload.embeds).collect();
+        assert_eq!(embeds, vec![
+            format!("{}QmCid", PINATA_GATEWAY),
+            format!("{}QmOther", PINATA_GATEWAY),
+            "https://example.com/image.png".to_string(),
+        ]);
+    }
+}
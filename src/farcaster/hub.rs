@@ -0,0 +1,686 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use ed25519_dalek::{Signer, SigningKey};
+use prost::Message as _;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::sync::Mutex;
+use tracing::{info, warn};
+use super::auth::FarcasterUser;
+use super::backend::FarcasterBackend;
+use super::cast::{Cast, CastAuthor, CastResult};
+use super::composer::{CastParent, CastPayload};
+
+// Los timestamps de Farcaster cuentan segundos desde 2021-01-01T00:00:00Z
+const FARCASTER_EPOCH: i64 = 1_609_459_200;
+
+const MESSAGE_TYPE_CAST_ADD: i32 = 1;
+const HASH_SCHEME_BLAKE3: i32 = 1;
+const SIGNATURE_SCHEME_ED25519: i32 = 1;
+const USER_DATA_TYPE_USERNAME: u32 = 6;
+// Saltos como mucho al subir por los padres buscando la raíz de un hilo
+const MAX_THREAD_DEPTH: usize = 20;
+// Tope de casts al recorrer una conversación hacia abajo desde la raíz
+const MAX_THREAD_CASTS: usize = 100;
+// Casts recientes del bot cuyas respuestas se buscan en cada poll de menciones
+const RECENT_BOT_CASTS: i32 = 10;
+
+// Mensajes protobuf del protocolo (solo los campos que usamos)
+#[derive(Clone, PartialEq, prost::Message)]
+struct ProtoMessage {
+    #[prost(message, optional, tag = "1")]
+    data: Option<MessageData>,
+    #[prost(bytes = "vec", tag = "2")]
+    hash: Vec<u8>,
+    #[prost(int32, tag = "3")]
+    hash_scheme: i32,
+    #[prost(bytes = "vec", tag = "4")]
+    signature: Vec<u8>,
+    #[prost(int32, tag = "5")]
+    signature_scheme: i32,
+    #[prost(bytes = "vec", tag = "6")]
+    signer: Vec<u8>,
+    #[prost(bytes = "vec", optional, tag = "7")]
+    data_bytes: Option<Vec<u8>>,
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct MessageData {
+    #[prost(int32, tag = "1")]
+    r#type: i32,
+    #[prost(uint64, tag = "2")]
+    fid: u64,
+    #[prost(uint32, tag = "3")]
+    timestamp: u32,
+    #[prost(int32, tag = "4")]
+    network: i32,
+    #[prost(oneof = "MessageBody", tags = "5")]
+    body: Option<MessageBody>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+enum MessageBody {
+    #[prost(message, tag = "5")]
+    CastAddBody(CastAddBody),
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct CastAddBody {
+    #[prost(uint64, repeated, tag = "2")]
+    mentions: Vec<u64>,
+    #[prost(oneof = "ProtoCastParent", tags = "3, 7")]
+    parent: Option<ProtoCastParent>,
+    #[prost(string, tag = "4")]
+    text: String,
+    #[prost(uint32, repeated, tag = "5")]
+    mentions_positions: Vec<u32>,
+    #[prost(message, repeated, tag = "6")]
+    embeds: Vec<Embed>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+enum ProtoCastParent {
+    #[prost(message, tag = "3")]
+    ParentCastId(CastId),
+    #[prost(string, tag = "7")]
+    ParentUrl(String),
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct Embed {
+    #[prost(oneof = "EmbedKind", tags = "1")]
+    embed: Option<EmbedKind>,
+}
+
+#[derive(Clone, PartialEq, prost::Oneof)]
+enum EmbedKind {
+    #[prost(string, tag = "1")]
+    Url(String),
+}
+
+#[derive(Clone, PartialEq, prost::Message)]
+struct CastId {
+    #[prost(uint64, tag = "1")]
+    fid: u64,
+    #[prost(bytes = "vec", tag = "2")]
+    hash: Vec<u8>,
+}
+
+// Respuestas JSON de la HTTP API del Hub
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HubMessagesPage {
+    messages: Vec<HubMessage>,
+    #[serde(default)]
+    next_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HubMessage {
+    data: HubMessageData,
+    hash: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HubMessageData {
+    fid: u64,
+    timestamp: i64,
+    #[serde(default)]
+    cast_add_body: Option<HubCastAddBody>,
+    #[serde(default)]
+    user_data_body: Option<HubUserDataBody>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct HubCastAddBody {
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    mentions: Vec<u64>,
+    #[serde(default)]
+    mentions_positions: Vec<u32>,
+    #[serde(default)]
+    parent_cast_id: Option<HubCastId>,
+    #[serde(default)]
+    parent_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HubCastId {
+    fid: u64,
+    hash: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HubUserDataBody {
+    value: String,
+}
+
+// HTTP API de un Hub (Snapchain o Hubble), publicando con un app signer Ed25519
+pub struct HubBackend {
+    http: reqwest::Client,
+    url: String,
+    bot: FarcasterUser,
+    signer: SigningKey,
+    network: i32,
+    usernames: Mutex<HashMap<u64, String>>,
+    // hash de un cast -> hash de la raíz de su hilo
+    thread_roots: Mutex<HashMap<String, String>>,
+}
+
+impl HubBackend {
+    pub async fn new(url: String, fid: u64, signer: SigningKey, network: i32) -> Result<Self> {
+        let mut backend = Self {
+            http: reqwest::Client::new(),
+            url: url.trim_end_matches('/').to_string(),
+            bot: FarcasterUser { fid, username: String::new() },
+            signer,
+            network,
+            usernames: Mutex::new(HashMap::new()),
+            thread_roots: Mutex::new(HashMap::new()),
+        };
+        backend.bot.username = backend.username(fid).await;
+        info!(url = %backend.url, fid, username = %backend.bot.username, "Farcaster hub backend ready");
+        Ok(backend)
+    }
+
+    // FARCASTER_HUB_URL, FARCASTER_FID, FARCASTER_SIGNER_KEY (hex, 32 bytes)
+    // y FARCASTER_NETWORK (mainnet por defecto, testnet o devnet)
+    pub async fn from_env() -> Result<Self> {
+        let url = env::var("FARCASTER_HUB_URL")?;
+        let fid: u64 = env::var("FARCASTER_FID")?.parse()?;
+
+        let key_hex = env::var("FARCASTER_SIGNER_KEY")?;
+        let key_bytes: [u8; 32] = hex::decode(key_hex.trim().trim_start_matches("0x"))?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("FARCASTER_SIGNER_KEY must be 32 bytes"))?;
+
+        let network = match env::var("FARCASTER_NETWORK").unwrap_or_else(|_| "mainnet".to_string()).as_str() {
+            "mainnet" => 1,
+            "testnet" => 2,
+            "devnet" => 3,
+            other => return Err(anyhow::anyhow!("Unknown FARCASTER_NETWORK: {}", other)),
+        };
+
+        Self::new(url, fid, SigningKey::from_bytes(&key_bytes), network).await
+    }
+
+    async fn get_page(&self, path: &str) -> Result<HubMessagesPage> {
+        let response = self.http
+            .get(format!("{}{}", self.url, path))
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow::anyhow!("Hub returned {}: {}", status, response.text().await?));
+        }
+        Ok(response.json().await?)
+    }
+
+    // Username de un FID, cacheado; si el hub no lo conoce queda vacío
+    async fn username(&self, fid: u64) -> String {
+        if let Some(name) = self.usernames.lock().unwrap().get(&fid) {
+            return name.clone();
+        }
+
+        let path = format!("/v1/userDataByFid?fid={}&user_data_type={}", fid, USER_DATA_TYPE_USERNAME);
+        let response = self.http.get(format!("{}{}", self.url, path)).send().await;
+        let name = match response {
+            Ok(response) if response.status().is_success() => response
+                .json::<HubMessage>()
+                .await
+                .ok()
+                .and_then(|message| message.data.user_data_body)
+                .map(|body| body.value)
+                .unwrap_or_default(),
+            Ok(response) => {
+                warn!(fid, status = %response.status(), "could not resolve username");
+                String::new()
+            },
+            Err(e) => {
+                warn!(fid, error = %e, "could not resolve username");
+                String::new()
+            }
+        };
+
+        self.usernames.lock().unwrap().insert(fid, name.clone());
+        name
+    }
+
+    async fn get_message(&self, fid: u64, hash: &str) -> Result<Option<HubMessage>> {
+        let response = self.http
+            .get(format!("{}/v1/castById?fid={}&hash={}", self.url, fid, hash))
+            .send()
+            .await?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(anyhow::anyhow!("Hub returned {}: {}", status, response.text().await?));
+        }
+        Ok(Some(response.json().await?))
+    }
+
+    // Los mensajes del Hub solo traen el padre: la raíz del hilo se busca subiendo
+    // por los padres. Si un padre no se puede leer, el último conocido hace de raíz
+    async fn thread_root(&self, parent_fid: u64, parent_hash: &str) -> String {
+        let mut visited = Vec::new();
+        let mut current = (parent_fid, parent_hash.to_string());
+        let root = loop {
+            if let Some(root) = self.thread_roots.lock().unwrap().get(&current.1) {
+                break root.clone();
+            }
+            visited.push(current.1.clone());
+            if visited.len() >= MAX_THREAD_DEPTH {
+                break current.1;
+            }
+
+            let parent = match self.get_message(current.0, &current.1).await {
+                Ok(message) => message
+                    .and_then(|message| message.data.cast_add_body)
+                    .and_then(|body| body.parent_cast_id),
+                Err(e) => {
+                    warn!(hash = %current.1, error = %e, "could not load parent cast for thread root");
+                    None
+                },
+            };
+            match parent {
+                Some(parent) => current = (parent.fid, parent.hash),
+                None => break current.1,
+            }
+        };
+
+        let mut roots = self.thread_roots.lock().unwrap();
+        for hash in visited {
+            roots.insert(hash, root.clone());
+        }
+        root
+    }
+
+    // Los hubs quitan las menciones del texto; se reinsertan como @username
+    async fn to_cast(&self, message: HubMessage) -> Option<Cast> {
+        let body = message.data.cast_add_body?;
+
+        let mut mentions = Vec::new();
+        for fid in &body.mentions {
+            mentions.push(CastAuthor { fid: *fid, username: self.username(*fid).await });
+        }
+
+        let mut text = body.text;
+        let mut positioned: Vec<(usize, &CastAuthor)> = body.mentions_positions
+            .iter()
+            .map(|p| *p as usize)
+            .zip(mentions.iter())
+            .collect();
+        positioned.sort_by_key(|(position, _)| std::cmp::Reverse(*position));
+        for (position, mention) in positioned {
+            if text.is_char_boundary(position) && !mention.username.is_empty() {
+                text.insert_str(position, &format!("@{}", mention.username));
+            }
+        }
+
+        let parent_author = match &body.parent_cast_id {
+            Some(parent) => Some(CastAuthor { fid: parent.fid, username: self.username(parent.fid).await }),
+            None => None,
+        };
+        // Un cast sin padre es la raíz de su propio hilo
+        let thread_hash = match &body.parent_cast_id {
+            Some(parent) => self.thread_root(parent.fid, &parent.hash).await,
+            None => message.hash.clone(),
+        };
+
+        Some(Cast {
+            hash: message.hash,
+            thread_hash: Some(thread_hash),
+            parent_hash: body.parent_cast_id.map(|parent| parent.hash),
+            parent_author,
+            parent_url: body.parent_url,
+            author: CastAuthor { fid: message.data.fid, username: self.username(message.data.fid).await },
+            text,
+            timestamp: (message.data.timestamp + FARCASTER_EPOCH) * 1000,
+            mentions,
+        })
+    }
+
+    async fn to_result(&self, page: HubMessagesPage) -> CastResult {
+        let mut casts = Vec::new();
+        for message in page.messages {
+            if let Some(cast) = self.to_cast(message).await {
+                casts.push(cast);
+            }
+        }
+
+        CastResult {
+            casts,
+            cursor: page.next_page_token.filter(|token| !token.is_empty()),
+        }
+    }
+
+    fn page_query(limit: Option<i32>, cursor: Option<&str>) -> String {
+        let mut query = format!("&pageSize={}&reverse=true", limit.unwrap_or(25));
+        if let Some(cursor) = cursor {
+            query.push_str(&format!("&pageToken={}", url::form_urlencoded::byte_serialize(cursor.as_bytes()).collect::<String>()));
+        }
+        query
+    }
+
+    // hash = blake3(data)[..20], firma Ed25519 sobre el hash
+    fn sign(&self, data: MessageData) -> ProtoMessage {
+        let data_bytes = data.encode_to_vec();
+        let hash = blake3::hash(&data_bytes).as_bytes()[..20].to_vec();
+        let signature = self.signer.sign(&hash).to_bytes().to_vec();
+
+        ProtoMessage {
+            data: Some(data),
+            hash,
+            hash_scheme: HASH_SCHEME_BLAKE3,
+            signature,
+            signature_scheme: SIGNATURE_SCHEME_ED25519,
+            signer: self.signer.verifying_key().to_bytes().to_vec(),
+            data_bytes: Some(data_bytes),
+        }
+    }
+}
+
+#[async_trait]
+impl FarcasterBackend for HubBackend {
+    fn name(&self) -> &'static str {
+        "hub"
+    }
+
+    fn bot(&self) -> &FarcasterUser {
+        &self.bot
+    }
+
+    // castsByMention no trae las respuestas al bot que no lo mencionan (Warpcast sí).
+    // En la primera página se añaden las respuestas a sus últimos casts; el cursor
+    // sigue siendo el de las menciones
+    async fn get_mentions(&self, limit: Option<i32>, cursor: Option<&str>) -> Result<CastResult> {
+        let path = format!("/v1/castsByMention?fid={}{}", self.bot.fid, Self::page_query(limit, cursor));
+        let page = self.get_page(&path).await?;
+        let mut result = self.to_result(page).await;
+        if cursor.is_some() {
+            return Ok(result);
+        }
+
+        let mut seen: HashSet<String> = result.casts.iter().map(|cast| cast.hash.clone()).collect();
+        let path = format!("/v1/castsByFid?fid={}{}", self.bot.fid, Self::page_query(Some(RECENT_BOT_CASTS), None));
+        for own in self.get_page(&path).await?.messages {
+            if own.data.cast_add_body.is_none() {
+                continue;
+            }
+            for reply in self.get_replies(self.bot.fid, &own.hash).await? {
+                if reply.author.fid != self.bot.fid && seen.insert(reply.hash.clone()) {
+                    result.casts.push(reply);
+                }
+            }
+        }
+        result.casts.sort_by_key(|cast| std::cmp::Reverse(cast.timestamp));
+        Ok(result)
+    }
+
+    async fn get_cast(&self, fid: Option<u64>, hash: &str) -> Result<Option<Cast>> {
+        // castById necesita el FID del autor
+        let fid = match fid {
+            Some(fid) => fid,
+            None => return Ok(None),
+        };
+
+        match self.get_message(fid, hash).await? {
+            Some(message) => Ok(self.to_cast(message).await),
+            None => Ok(None),
+        }
+    }
+
+    async fn get_replies(&self, parent_fid: u64, parent_hash: &str) -> Result<Vec<Cast>> {
+        let path = format!("/v1/castsByParent?fid={}&hash={}", parent_fid, parent_hash);
+        let page = self.get_page(&path).await?;
+        Ok(self.to_result(page).await.casts)
+    }
+
+    // El hub no indexa por raíz: se recorren las respuestas nivel a nivel desde ella
+    async fn get_thread(&self, root_fid: Option<u64>, thread_hash: &str) -> Result<Vec<Cast>> {
+        let root_fid = match root_fid {
+            Some(fid) => fid,
+            None => return Ok(Vec::new()),
+        };
+
+        let mut thread = Vec::new();
+        let mut level = vec![(root_fid, thread_hash.to_string())];
+        for _ in 0..MAX_THREAD_DEPTH {
+            let mut next = Vec::new();
+            for (fid, hash) in &level {
+                for reply in self.get_replies(*fid, hash).await? {
+                    next.push((reply.author.fid, reply.hash.clone()));
+                    thread.push(reply);
+                    if thread.len() >= MAX_THREAD_CASTS {
+                        return Ok(thread);
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            level = next;
+        }
+        Ok(thread)
+    }
+
+    async fn publish_cast(&self, payload: &CastPayload) -> Result<Cast> {
+        let parent = match &payload.parent {
+            Some(CastParent::Cast { hash, fid }) => Some(ProtoCastParent::ParentCastId(CastId {
+                fid: *fid,
+                hash: hex::decode(hash.trim_start_matches("0x"))?,
+            })),
+            Some(CastParent::Url(url)) => Some(ProtoCastParent::ParentUrl(url.clone())),
+            None => None,
+        };
+
+        let data = MessageData {
+            r#type: MESSAGE_TYPE_CAST_ADD,
+            fid: self.bot.fid,
+            timestamp: (chrono::Utc::now().timestamp() - FARCASTER_EPOCH) as u32,
+            network: self.network,
+            body: Some(MessageBody::CastAddBody(CastAddBody {
+                mentions: Vec::new(),
+                parent,
+                text: payload.text.clone(),
+                mentions_positions: Vec::new(),
+                embeds: payload.embeds
+                    .iter()
+                    .map(|url| Embed { embed: Some(EmbedKind::Url(url.clone())) })
+                    .collect(),
+            })),
+        };
+
+        let response = self.http
+            .post(format!("{}/v1/submitMessage", self.url))
+            .header("Content-Type", "application/octet-stream")
+            .body(self.sign(data).encode_to_vec())
+            .send()
+            .await?;
+
+        let status = response.status();
+        let response_text = response.text().await?;
+        if !status.is_success() {
+            return Err(anyhow::anyhow!("Hub rejected message ({}): {}", status, response_text));
+        }
+
+        let message: HubMessage = serde_json::from_str(&response_text)?;
+        self.to_cast(message)
+            .await
+            .ok_or_else(|| anyhow::anyhow!("Hub did not return a cast"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{web, App, HttpResponse, HttpServer};
+    use ed25519_dalek::{Signature, Verifier};
+    use serde_json::json;
+
+    const BOT_FID: u64 = 1;
+
+    fn message(fid: u64, hash: &str, timestamp: i64, text: &str, mentions: &[u64], parent: Option<(u64, &str)>) -> serde_json::Value {
+        json!({
+            "hash": hash,
+            "data": {
+                "fid": fid,
+                "timestamp": timestamp,
+                "castAddBody": {
+                    "text": text,
+                    "mentions": mentions,
+                    "mentionsPositions": mentions.iter().map(|_| 0).collect::<Vec<_>>(),
+                    "parentCastId": parent.map(|(fid, hash)| json!({ "fid": fid, "hash": hash })),
+                },
+            },
+        })
+    }
+
+    fn page(messages: Vec<serde_json::Value>) -> HttpResponse {
+        HttpResponse::Ok().json(json!({ "messages": messages, "nextPageToken": "" }))
+    }
+
+    // Hub falso: una mención, un cast del bot con una respuesta sin mención (y la
+    // mención repetida como respuesta) y submitMessage que guarda lo recibido
+    async fn fake_hub(submitted: web::Data<Mutex<Vec<Vec<u8>>>>) -> String {
+        let server = HttpServer::new(move || {
+            App::new()
+                .app_data(submitted.clone())
+                .route("/v1/userDataByFid", web::get().to(|query: web::Query<HashMap<String, String>>| async move {
+                    HttpResponse::Ok().json(json!({
+                        "hash": "0x00",
+                        "data": { "fid": query["fid"].parse::<u64>().unwrap(), "timestamp": 0, "userDataBody": { "value": format!("user{}", query["fid"]) } },
+                    }))
+                }))
+                .route("/v1/castsByMention", web::get().to(|| async {
+                    page(vec![message(2, "0xaa", 100, " hi", &[BOT_FID], Some((BOT_FID, "0xb0")))])
+                }))
+                .route("/v1/castsByFid", web::get().to(|| async {
+                    page(vec![message(BOT_FID, "0xb0", 50, "lore", &[], None)])
+                }))
+                .route("/v1/castsByParent", web::get().to(|query: web::Query<HashMap<String, String>>| async move {
+                    if query["hash"] != "0xb0" {
+                        return page(Vec::new());
+                    }
+                    page(vec![
+                        message(2, "0xaa", 100, " hi", &[BOT_FID], Some((BOT_FID, "0xb0"))),
+                        message(3, "0xcc", 200, "no mention here", &[], Some((BOT_FID, "0xb0"))),
+                        message(BOT_FID, "0xdd", 300, "own reply", &[], Some((BOT_FID, "0xb0"))),
+                    ])
+                }))
+                .route("/v1/castById", web::get().to(HttpResponse::NotFound))
+                .route("/v1/submitMessage", web::post().to(|body: web::Bytes, submitted: web::Data<Mutex<Vec<Vec<u8>>>>| async move {
+                    submitted.lock().unwrap().push(body.to_vec());
+                    let message = ProtoMessage::decode(body.as_ref()).unwrap();
+                    let data = message.data.unwrap();
+                    let text = match data.body {
+                        Some(MessageBody::CastAddBody(body)) => body.text,
+                        None => String::new(),
+                    };
+                    HttpResponse::Ok().json(super::tests::message(data.fid, &format!("0x{}", hex::encode(&message.hash)), data.timestamp as i64, &text, &[], None))
+                }))
+        })
+            .workers(1)
+            .bind(("127.0.0.1", 0))
+            .unwrap();
+        let address = server.addrs()[0];
+        actix_web::rt::spawn(server.run());
+        format!("http://{}", address)
+    }
+
+    async fn backend() -> (HubBackend, web::Data<Mutex<Vec<Vec<u8>>>>) {
+        let submitted = web::Data::new(Mutex::new(Vec::new()));
+        let url = fake_hub(submitted.clone()).await;
+        let backend = HubBackend::new(url, BOT_FID, SigningKey::from_bytes(&[7u8; 32]), 1).await.unwrap();
+        (backend, submitted)
+    }
+
+    #[actix_web::test]
+    async fn mentions_include_replies_without_mention() {
+        let (backend, _) = backend().await;
+        assert_eq!(backend.bot().username, "user1");
+
+        let result = backend.get_mentions(Some(25), None).await.unwrap();
+        let hashes: Vec<&str> = result.casts.iter().map(|cast| cast.hash.as_str()).collect();
+        // Más recientes primero, sin duplicados ni casts del propio bot
+        assert_eq!(hashes, vec!["0xcc", "0xaa"]);
+        assert_eq!(result.casts[1].text, "@user1 hi");
+        assert_eq!(result.casts[0].parent_author.as_ref().map(|author| author.fid), Some(BOT_FID));
+    }
+
+    #[actix_web::test]
+    async fn later_mention_pages_skip_replies() {
+        let (backend, _) = backend().await;
+        let result = backend.get_mentions(Some(25), Some("next")).await.unwrap();
+        let hashes: Vec<&str> = result.casts.iter().map(|cast| cast.hash.as_str()).collect();
+        assert_eq!(hashes, vec!["0xaa"]);
+    }
+
+    #[test]
+    fn sign_hashes_data_with_blake3_and_signs_the_hash() {
+        let signer = SigningKey::from_bytes(&[7u8; 32]);
+        let backend = HubBackend {
+            http: reqwest::Client::new(),
+            url: String::new(),
+            bot: FarcasterUser { fid: BOT_FID, username: "qawakun".to_string() },
+            signer: signer.clone(),
+            network: 1,
+            usernames: Mutex::new(HashMap::new()),
+            thread_roots: Mutex::new(HashMap::new()),
+        };
+        let data = MessageData {
+            r#type: MESSAGE_TYPE_CAST_ADD,
+            fid: BOT_FID,
+            timestamp: 42,
+            network: 1,
+            body: Some(MessageBody::CastAddBody(CastAddBody { text: "hello".to_string(), ..Default::default() })),
+        };
+
+        let message = backend.sign(data.clone());
+        let data_bytes = data.encode_to_vec();
+        assert_eq!(message.data_bytes.as_deref(), Some(data_bytes.as_slice()));
+        assert_eq!(message.hash, blake3::hash(&data_bytes).as_bytes()[..20].to_vec());
+        assert_eq!(message.hash_scheme, HASH_SCHEME_BLAKE3);
+        assert_eq!(message.signature_scheme, SIGNATURE_SCHEME_ED25519);
+        assert_eq!(message.signer, signer.verifying_key().to_bytes().to_vec());
+
+        let signature = Signature::from_slice(&message.signature).unwrap();
+        assert!(signer.verifying_key().verify(&message.hash, &signature).is_ok());
+    }
+
+    #[actix_web::test]
+    async fn publish_submits_a_signed_cast_add() {
+        let (backend, submitted) = backend().await;
+        let payload = CastPayload {
+            text: "hello hub".to_string(),
+            embeds: vec!["https://example.com".to_string()],
+            parent: Some(CastParent::Cast { hash: "0xb0".to_string(), fid: BOT_FID }),
+        };
+
+        let cast = backend.publish_cast(&payload).await.unwrap();
+        assert_eq!(cast.text, "hello hub");
+
+        let submitted = submitted.lock().unwrap();
+        let message = ProtoMessage::decode(submitted[0].as_slice()).unwrap();
+        let data = message.data.clone().unwrap();
+        assert_eq!(data.fid, BOT_FID);
+        assert_eq!(data.r#type, MESSAGE_TYPE_CAST_ADD);
+        assert_eq!(message.hash, blake3::hash(&data.encode_to_vec()).as_bytes()[..20].to_vec());
+        assert_eq!(cast.hash, format!("0x{}", hex::encode(&message.hash)));
+        match data.body {
+            Some(MessageBody::CastAddBody(body)) => {
+                assert_eq!(body.parent, Some(ProtoCastParent::ParentCastId(CastId { fid: BOT_FID, hash: vec![0xb0] })));
+                assert_eq!(body.embeds.len(), 1);
+            },
+            None => panic!("message without cast body"),
+        }
+    }
+}
@@ -0,0 +1,358 @@
+use anyhow::Result;
+use base64::Engine;
+use chrono::Utc;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::api::cdp::nfts::pin_image;
+use crate::openai_methods::get_image::generate_image;
+use crate::openai_methods::get_text::{get_chat_completion, ChatMessage};
+use crate::social::pipeline::narrative_context;
+use super::cast::CastClient;
+use super::composer::CastDraft;
+use tracing::{debug, error, info, warn};
+
+// Calendario de lore: hash id -> LoreEntry en JSON
+const CALENDAR_KEY: &str = "farcaster:lore:calendar";
+pub const TICK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+// Durante cuánto tiempo se siguen contestando las respuestas a un cast de lore
+const REPLY_WINDOW_SECS: i64 = 3 * 24 * 60 * 60;
+// Casts publicados que se le pasan al LLM para que no repita historias
+const RECENT_LORE_IN_PROMPT: usize = 5;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LoreStatus {
+    // Generado, esperando revisión
+    Pending,
+    Approved,
+    Rejected,
+    Published,
+    Failed,
+    // Llegó su hora sin que nadie lo aprobara
+    Expired,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct LoreEntry {
+    id: String,
+    text: String,
+    image_url: Option<String>,
+    // Segundos UNIX
+    scheduled_at: i64,
+    status: LoreStatus,
+    created_at: i64,
+    #[serde(default)]
+    reviewed_by: Option<String>,
+    #[serde(default)]
+    reviewed_at: Option<i64>,
+    // Motivo del rechazo o error al publicar
+    #[serde(default)]
+    reason: Option<String>,
+    #[serde(default)]
+    cast_hashes: Vec<String>,
+    #[serde(default)]
+    published_at: Option<i64>,
+}
+
+pub enum Review {
+    Approve { text: Option<String>, scheduled_at: Option<i64> },
+    Reject { reason: Option<String> },
+}
+
+pub enum Reviewed {
+    Updated(LoreEntry),
+    NotFound,
+    // El estado actual no admite la revisión (p. ej. ya publicado)
+    Conflict(LoreStatus),
+}
+
+pub struct LoreConfig {
+    // parent_url del canal
+    channel: String,
+    interval_secs: i64,
+    drafts_ahead: usize,
+    images: bool,
+}
+
+impl LoreConfig {
+    // Sin FARCASTER_LORE_CHANNEL no se programa nada
+    pub fn from_env() -> Option<Self> {
+        let channel = env::var("FARCASTER_LORE_CHANNEL").ok().filter(|c| !c.is_empty())?;
+        let interval_hours: i64 = env::var("FARCASTER_LORE_INTERVAL_HOURS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|h| *h > 0)
+            .unwrap_or(24);
+
+        Some(Self {
+            channel,
+            interval_secs: interval_hours * 60 * 60,
+            drafts_ahead: env::var("FARCASTER_LORE_DRAFTS_AHEAD")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(3),
+            images: env::var("FARCASTER_LORE_IMAGES").map(|v| v == "true").unwrap_or(false),
+        })
+    }
+}
+
+async fn load_calendar(con: &mut redis::aio::Connection) -> Result<Vec<LoreEntry>> {
+    let stored: Vec<String> = con.hvals(CALENDAR_KEY).await?;
+    let mut entries: Vec<LoreEntry> = stored
+        .iter()
+        .filter_map(|e| serde_json::from_str(e).ok())
+        .collect();
+    entries.sort_by_key(|e| e.scheduled_at);
+    Ok(entries)
+}
+
+async fn save(con: &mut redis::aio::Connection, entry: &LoreEntry) -> Result<()> {
+    let _: () = con.hset(CALENDAR_KEY, &entry.id, serde_json::to_string(entry)?).await?;
+    Ok(())
+}
+
+pub async fn calendar(redis_client: &redis::Client) -> Result<Vec<LoreEntry>> {
+    let mut con = redis_client.get_async_connection().await?;
+    load_calendar(&mut con).await
+}
+
+// Aprobar admite editar texto y hora; un borrador fallido o caducado se puede
+// volver a aprobar para reintentarlo
+pub async fn review(redis_client: &redis::Client, id: &str, review: Review, by: &str) -> Result<Reviewed> {
+    let mut con = redis_client.get_async_connection().await?;
+    let stored: Option<String> = con.hget(CALENDAR_KEY, id).await?;
+    let mut entry: LoreEntry = match stored {
+        Some(stored) => serde_json::from_str(&stored)?,
+        None => return Ok(Reviewed::NotFound),
+    };
+
+    if entry.status == LoreStatus::Published {
+        return Ok(Reviewed::Conflict(entry.status));
+    }
+
+    match review {
+        Review::Approve { text, scheduled_at } => {
+            if entry.status == LoreStatus::Rejected {
+                return Ok(Reviewed::Conflict(entry.status));
+            }
+            if let Some(text) = text {
+                entry.text = text.trim().to_string();
+            }
+            if let Some(scheduled_at) = scheduled_at {
+                entry.scheduled_at = scheduled_at;
+            }
+            entry.status = LoreStatus::Approved;
+            entry.reason = None;
+        },
+        Review::Reject { reason } => {
+            entry.status = LoreStatus::Rejected;
+            entry.reason = reason;
+        },
+    }
+    entry.reviewed_by = Some(by.to_string());
+    entry.reviewed_at = Some(Utc::now().timestamp());
+
+    save(&mut con, &entry).await?;
+    info!(id = %entry.id, status = ?entry.status, by, "lore entry reviewed");
+    Ok(Reviewed::Updated(entry))
+}
+
+// Publica el lore aprobado en el canal, genera borradores para los próximos huecos
+// del calendario y contesta las respuestas a los casts ya publicados
+pub struct LoreScheduler {
+    cast_client: Arc<CastClient>,
+    redis_client: redis::Client,
+    config: LoreConfig,
+}
+
+impl LoreScheduler {
+    pub fn new(cast_client: Arc<CastClient>, redis_client: redis::Client, config: LoreConfig) -> Self {
+        Self { cast_client, redis_client, config }
+    }
+
+    pub async fn tick(&self) -> Result<()> {
+        let mut con = self.redis_client.get_async_connection().await?;
+        let now = Utc::now().timestamp();
+
+        for mut entry in load_calendar(&mut con).await? {
+            if entry.status == LoreStatus::Pending && entry.scheduled_at <= now {
+                warn!(id = %entry.id, "lore draft was not reviewed in time, expiring");
+                entry.status = LoreStatus::Expired;
+                save(&mut con, &entry).await?;
+            }
+        }
+
+        // Como mucho un cast por ciclo para no inundar el canal tras una caída
+        let calendar = load_calendar(&mut con).await?;
+        // Cada paso es independiente: un fallo (p. ej. de OpenAI) se registra y el
+        // resto del ciclo sigue, sobre todo las respuestas
+        if let Some(entry) = calendar.iter().find(|e| e.status == LoreStatus::Approved && e.scheduled_at <= now) {
+            if let Err(e) = self.publish(&mut con, entry.clone()).await {
+                error!(id = %entry.id, error = %e, "failed to publish lore entry");
+            }
+        }
+        if let Err(e) = self.fill_calendar(&mut con, now).await {
+            error!(error = %e, "failed to fill lore calendar");
+        }
+        if let Err(e) = self.answer_replies(&mut con, now).await {
+            error!(error = %e, "failed to answer lore replies");
+        }
+        Ok(())
+    }
+
+    async fn publish(&self, con: &mut redis::aio::Connection, mut entry: LoreEntry) -> Result<()> {
+        let mut draft = CastDraft::new(&entry.text).in_channel(&self.config.channel);
+        if let Some(image_url) = &entry.image_url {
+            draft = draft.embed(image_url)?;
+        }
+
+        match self.cast_client.publish(&draft, None).await {
+            Ok(casts) => {
+                entry.status = LoreStatus::Published;
+                entry.cast_hashes = casts.iter().map(|c| c.hash.clone()).collect();
+                entry.published_at = Some(Utc::now().timestamp());
+                info!(id = %entry.id, hash = ?entry.cast_hashes.first(), "lore cast published");
+            },
+            Err(e) => {
+                error!(id = %entry.id, error = %e, "failed to publish lore cast");
+                entry.status = LoreStatus::Failed;
+                entry.reason = Some(e.to_string());
+            },
+        }
+        save(con, &entry).await
+    }
+
+    // Huecos alineados al intervalo (con 24 h, medianoche UTC). Un hueco está
+    // ocupado si tiene un borrador pendiente, aprobado o publicado; al rechazar
+    // uno, el siguiente ciclo genera otro para la misma hora
+    async fn fill_calendar(&self, con: &mut redis::aio::Connection, now: i64) -> Result<()> {
+        let calendar = load_calendar(con).await?;
+        let interval = self.config.interval_secs;
+        let first_slot = (now / interval + 1) * interval;
+
+        for slot in (0..self.config.drafts_ahead as i64).map(|k| first_slot + k * interval) {
+            let occupied = calendar.iter().any(|e| {
+                e.scheduled_at == slot
+                    && matches!(e.status, LoreStatus::Pending | LoreStatus::Approved | LoreStatus::Published)
+            });
+            if occupied {
+                continue;
+            }
+
+            let entry = self.generate(con, &calendar, slot).await?;
+            save(con, &entry).await?;
+            info!(id = %entry.id, scheduled_at = slot, "lore draft generated, waiting for review");
+        }
+        Ok(())
+    }
+
+    async fn generate(&self, con: &mut redis::aio::Connection, calendar: &[LoreEntry], slot: i64) -> Result<LoreEntry> {
+        let api_key = env::var("OPENAI_API_KEY")
+            .map_err(|e| anyhow::anyhow!("Failed to get OPENAI_API_KEY: {}", e))?;
+        let context = narrative_context(con).await?;
+
+        let mut prompt = "Write one short piece of Qawakun lore to post in the community's Farcaster channel: \
+                          a scene, a legend or a character moment from the world. At most 300 characters, \
+                          no hashtags, no links, no quotes around the text."
+            .to_string();
+        let recent: Vec<&str> = calendar
+            .iter()
+            .rev()
+            .filter(|e| e.status == LoreStatus::Published)
+            .take(RECENT_LORE_IN_PROMPT)
+            .map(|e| e.text.as_str())
+            .collect();
+        if !recent.is_empty() {
+            prompt.push_str("\n\nRecent posts, do not repeat them:\n- ");
+            prompt.push_str(&recent.join("\n- "));
+        }
+
+        let messages = vec![
+            ChatMessage { role: "system".to_string(), content: context },
+            ChatMessage { role: "user".to_string(), content: prompt },
+        ];
+        let response = get_chat_completion(&api_key, "farcaster_lore", messages)
+            .await
+            .map_err(|e| anyhow::anyhow!("OpenAI API error: {}", e))?;
+        let text = response["choices"][0]["message"]["content"]
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("No response content in OpenAI result"))?
+            .trim()
+            .trim_matches('"')
+            .to_string();
+
+        // Sin imagen el borrador sigue siendo válido
+        let image_url = if self.config.images {
+            match self.illustrate(&text).await {
+                Ok(url) => Some(url),
+                Err(e) => {
+                    warn!(error = %e, "could not generate lore image, drafting without it");
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        let created_at = Utc::now().timestamp();
+        Ok(LoreEntry {
+            id: format!("{}-{:04x}", slot, rand::random::<u16>()),
+            text,
+            image_url,
+            scheduled_at: slot,
+            status: LoreStatus::Pending,
+            created_at,
+            reviewed_by: None,
+            reviewed_at: None,
+            reason: None,
+            cast_hashes: Vec::new(),
+            published_at: None,
+        })
+    }
+
+    async fn illustrate(&self, text: &str) -> Result<String> {
+        let image = generate_image(text).await?;
+        let bytes = base64::engine::general_purpose::STANDARD.decode(image)?;
+        pin_image(bytes, "lore.png").await
+    }
+
+    // Las respuestas a un cast del bot no siempre llegan por el feed de menciones
+    // (el Hub solo indexa menciones), así que se consultan aquí y pasan por el
+    // mismo filtro y la misma marca de "ya contestada" que las menciones
+    async fn answer_replies(&self, con: &mut redis::aio::Connection, now: i64) -> Result<()> {
+        // Sin marcar nada: las respuestas se vuelven a consultar en cada ciclo
+        if self.cast_client.replies_paused().await {
+            debug!("Farcaster replies paused, not answering lore replies");
+            return Ok(());
+        }
+
+        let calendar = load_calendar(con).await?;
+        let recent = calendar.iter().filter(|e| {
+            e.status == LoreStatus::Published
+                && e.published_at.map(|at| now - at <= REPLY_WINDOW_SECS).unwrap_or(false)
+        });
+
+        for entry in recent {
+            for hash in &entry.cast_hashes {
+                let replies = match self.cast_client.get_replies(hash).await {
+                    Ok(replies) => replies,
+                    Err(e) => {
+                        warn!(hash = %hash, error = %e, "could not load replies to lore cast");
+                        continue;
+                    }
+                };
+                debug!(hash = %hash, replies = replies.len(), "lore cast replies");
+
+                for reply in replies {
+                    if let Err(e) = self.cast_client.handle_queued_mention(reply).await {
+                        error!(hash = %hash, error = %e, "failed to answer reply to lore cast");
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,124 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use std::env;
+use std::sync::Mutex;
+use tracing::info;
+use super::auth::FarcasterUser;
+use super::backend::FarcasterBackend;
+use super::cast::{Cast, CastAuthor, CastResult};
+use super::composer::{CastParent, CastPayload};
+
+// Hub falso en memoria para desarrollo local y pruebas: no sale a la red y
+// guarda lo que el bot publica
+pub struct MemoryBackend {
+    bot: FarcasterUser,
+    casts: Mutex<Vec<Cast>>,
+}
+
+impl MemoryBackend {
+    pub fn new(bot: FarcasterUser, casts: Vec<Cast>) -> Self {
+        Self { bot, casts: Mutex::new(casts) }
+    }
+
+    // FARCASTER_FID y FARCASTER_USERNAME definen la cuenta del bot;
+    // FARCASTER_MEMORY_FIXTURE apunta a un JSON con casts iniciales
+    pub fn from_env() -> Result<Self> {
+        let bot = FarcasterUser {
+            fid: env::var("FARCASTER_FID").ok().and_then(|fid| fid.parse().ok()).unwrap_or(1),
+            username: env::var("FARCASTER_USERNAME").unwrap_or_else(|_| "qawakun".to_string()),
+        };
+
+        let casts: Vec<Cast> = match env::var("FARCASTER_MEMORY_FIXTURE") {
+            Ok(path) => serde_json::from_str(&std::fs::read_to_string(path)?)?,
+            Err(_) => Vec::new(),
+        };
+
+        info!(fid = bot.fid, casts = casts.len(), "Farcaster in-memory backend ready");
+        Ok(Self::new(bot, casts))
+    }
+
+    fn page<F>(&self, limit: Option<i32>, cursor: Option<&str>, filter: F) -> Result<CastResult>
+    where
+        F: Fn(&Cast) -> bool,
+    {
+        let mut casts: Vec<Cast> = self.casts.lock().unwrap().iter().filter(|c| filter(c)).cloned().collect();
+        casts.sort_by_key(|cast| std::cmp::Reverse(cast.timestamp));
+
+        let offset: usize = cursor.map(|c| c.parse()).transpose()?.unwrap_or(0);
+        let limit = limit.unwrap_or(25).max(1) as usize;
+        let next = offset + limit;
+
+        Ok(CastResult {
+            cursor: (next < casts.len()).then(|| next.to_string()),
+            casts: casts.into_iter().skip(offset).take(limit).collect(),
+        })
+    }
+}
+
+#[async_trait]
+impl FarcasterBackend for MemoryBackend {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+
+    fn bot(&self) -> &FarcasterUser {
+        &self.bot
+    }
+
+    async fn get_mentions(&self, limit: Option<i32>, cursor: Option<&str>) -> Result<CastResult> {
+        let handle = format!("@{}", self.bot.username.to_lowercase());
+        self.page(limit, cursor, |cast| {
+            cast.author.fid != self.bot.fid && (
+                cast.mentions.iter().any(|m| m.fid == self.bot.fid)
+                || cast.parent_author.as_ref().map(|p| p.fid == self.bot.fid).unwrap_or(false)
+                || cast.text.to_lowercase().contains(&handle)
+            )
+        })
+    }
+
+    async fn get_cast(&self, _fid: Option<u64>, hash: &str) -> Result<Option<Cast>> {
+        Ok(self.casts.lock().unwrap().iter().find(|c| c.hash == hash).cloned())
+    }
+
+    async fn get_replies(&self, _parent_fid: u64, parent_hash: &str) -> Result<Vec<Cast>> {
+        Ok(self.page(Some(i32::MAX), None, |cast| cast.parent_hash.as_deref() == Some(parent_hash))?.casts)
+    }
+
+    async fn get_thread(&self, _root_fid: Option<u64>, thread_hash: &str) -> Result<Vec<Cast>> {
+        Ok(self.page(Some(i32::MAX), None, |cast| cast.thread_hash.as_deref() == Some(thread_hash))?.casts)
+    }
+
+    async fn publish_cast(&self, payload: &CastPayload) -> Result<Cast> {
+        let timestamp = chrono::Utc::now().timestamp_millis();
+        let seed = format!("{}:{}:{}", self.bot.fid, timestamp, payload.text);
+        let reply_to = match &payload.parent {
+            Some(CastParent::Cast { hash, fid }) => Some((hash.as_str(), *fid)),
+            _ => None,
+        };
+        let hash = format!("0x{}", hex::encode(&blake3::hash(seed.as_bytes()).as_bytes()[..20]));
+
+        let mut casts = self.casts.lock().unwrap();
+        let parent = reply_to.and_then(|(parent_hash, _)| casts.iter().find(|c| c.hash == parent_hash));
+        let cast = Cast {
+            hash,
+            thread_hash: parent.map(|p| p.thread_hash.clone().unwrap_or_else(|| p.hash.clone())),
+            parent_hash: reply_to.map(|(parent_hash, _)| parent_hash.to_string()),
+            parent_author: reply_to.map(|(_, fid)| CastAuthor {
+                fid,
+                username: parent.map(|p| p.author.username.clone()).unwrap_or_default(),
+            }),
+            author: CastAuthor { fid: self.bot.fid, username: self.bot.username.clone() },
+            parent_url: match &payload.parent {
+                Some(CastParent::Url(url)) => Some(url.clone()),
+                _ => None,
+            },
+            text: payload.text.clone(),
+            timestamp,
+            mentions: Vec::new(),
+        };
+
+        info!(hash = %cast.hash, parent = ?cast.parent_hash, embeds = ?payload.embeds, "cast published to in-memory backend");
+        casts.push(cast.clone());
+        Ok(cast)
+    }
+}
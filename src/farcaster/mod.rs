@@ -1,5 +1,13 @@
 pub mod auth;
+pub mod backend;
 pub mod cast;
+pub mod composer;
+pub mod hub;
+pub mod lore;
+pub mod memory;
+pub mod notifications;
+pub mod session;
+pub mod warpcast;
+pub mod webhook;
 
-pub use auth::Auth;
 pub use cast::CastClient;
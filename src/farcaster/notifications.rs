@@ -0,0 +1,580 @@
+use anyhow::Result;
+use base64::Engine;
+use chrono::Utc;
+use ed25519_dalek::{Signature, Verifier, VerifyingKey};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::env;
+use std::time::Duration;
+use crate::metrics;
+use crate::retry_queue::RetryQueue;
+use crate::workers::Shutdown;
+use tracing::{debug, error, info, warn};
+
+// Hash fid -> NotificationDetails en JSON, alimentado por el webhook del Frame
+const DETAILS_KEY: &str = "frame:notifications";
+const THROTTLE_PREFIX: &str = "frame:notifications:throttle:";
+const DAILY_PREFIX: &str = "frame:notifications:daily:";
+
+// Límites que aplican los clientes por token: una cada 30 s y 100 al día. Se
+// respetan aquí para no gastar peticiones que el cliente va a rechazar
+const THROTTLE_SECS: u64 = 30;
+const DAILY_LIMIT: u64 = 100;
+const MAX_TOKENS_PER_REQUEST: usize = 100;
+const MAX_TITLE_CHARS: usize = 32;
+const MAX_BODY_CHARS: usize = 128;
+
+// Notificaciones frenadas por el límite de 30 s: salen cuando vence la ventana. Si
+// el envío falla se reintentan con backoff y tras cinco intentos van a dead letter
+const PENDING: RetryQueue = RetryQueue::new("frame:notifications:pending", "frame:notifications:dead_letter", 5, THROTTLE_SECS as i64, 60);
+const PENDING_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+// Hub con el que se comprueba que la app key del evento es un signer del FID
+const DEFAULT_KEY_HUB_URL: &str = "https://hub-api.neynar.com";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct NotificationDetails {
+    pub url: String,
+    pub token: String,
+}
+
+// Evento del Frame en formato JSON Farcaster Signature: header, payload y firma en base64url
+#[derive(Debug, Deserialize)]
+struct SignedEvent {
+    header: String,
+    payload: String,
+    signature: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct EventHeader {
+    fid: u64,
+    #[serde(rename = "type")]
+    kind: String,
+    key: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EventPayload {
+    event: String,
+    #[serde(default)]
+    notification_details: Option<NotificationDetails>,
+}
+
+// Evento con la firma ya verificada; falta comprobar la app key contra el hub
+#[derive(Debug)]
+pub struct FrameEvent {
+    pub fid: u64,
+    pub key: String,
+    pub event: String,
+    notification_details: Option<NotificationDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OnChainSignersPage {
+    #[serde(default)]
+    events: Vec<OnChainEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct OnChainEvent {
+    #[serde(default)]
+    block_number: u64,
+    #[serde(default)]
+    log_index: u32,
+    signer_event_body: Option<SignerEventBody>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignerEventBody {
+    key: String,
+    event_type: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct SendResponse {
+    result: SendResult,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SendResult {
+    #[serde(default)]
+    successful_tokens: Vec<String>,
+    #[serde(default)]
+    invalid_tokens: Vec<String>,
+    #[serde(default)]
+    rate_limited_tokens: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Delivery {
+    Sent,
+    // Dentro de la ventana de 30 s del token; se envía cuando vence
+    Queued,
+    NoToken,
+    RateLimited,
+    InvalidToken,
+    Failed,
+}
+
+impl Delivery {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Delivery::Sent => "sent",
+            Delivery::Queued => "queued",
+            Delivery::NoToken => "no_token",
+            Delivery::RateLimited => "rate_limited",
+            Delivery::InvalidToken => "invalid_token",
+            Delivery::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    id: String,
+    title: String,
+    body: String,
+}
+
+impl Notification {
+    // `id` identifica el evento: el cliente descarta durante 24 h las notificaciones
+    // repetidas con el mismo id para el mismo FID
+    pub fn new(id: &str, title: &str, body: &str) -> Self {
+        Self {
+            id: id.chars().take(128).collect(),
+            title: title.chars().take(MAX_TITLE_CHARS).collect(),
+            body: body.chars().take(MAX_BODY_CHARS).collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct PendingNotification {
+    fid: u64,
+    notification: Notification,
+    #[serde(default)]
+    attempts: u32,
+}
+
+enum Reservation {
+    Reserved,
+    // Segundos que faltan para que acabe la ventana del token
+    Throttled(i64),
+    DailyLimit,
+}
+
+// Decodifica el evento y verifica la firma Ed25519 sobre "header.payload"
+pub fn parse_event(body: &[u8]) -> Result<FrameEvent> {
+    let engine = base64::engine::general_purpose::URL_SAFE_NO_PAD;
+    let signed: SignedEvent = serde_json::from_slice(body)?;
+
+    let header: EventHeader = serde_json::from_slice(&engine.decode(&signed.header)?)?;
+    if header.kind != "app_key" {
+        return Err(anyhow::anyhow!("Unsupported signature type: {}", header.kind));
+    }
+
+    let key: [u8; 32] = hex::decode(header.key.trim_start_matches("0x"))?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("App key must be 32 bytes"))?;
+    let signature: [u8; 64] = engine.decode(&signed.signature)?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("Signature must be 64 bytes"))?;
+    VerifyingKey::from_bytes(&key)?
+        .verify(format!("{}.{}", signed.header, signed.payload).as_bytes(), &Signature::from_bytes(&signature))?;
+
+    let payload: EventPayload = serde_json::from_slice(&engine.decode(&signed.payload)?)?;
+    Ok(FrameEvent {
+        fid: header.fid,
+        key: header.key,
+        event: payload.event,
+        notification_details: payload.notification_details,
+    })
+}
+
+// La app key tiene que ser un signer activo del FID (FARCASTER_HUB_URL, Neynar por defecto):
+// manda el último evento de esa key en orden de cadena, así que una baja o un reset la invalida
+pub async fn verify_app_key(fid: u64, key: &str) -> Result<bool> {
+    let hub_url = env::var("FARCASTER_HUB_URL").unwrap_or_else(|_| DEFAULT_KEY_HUB_URL.to_string());
+    let mut request = reqwest::Client::new()
+        .get(format!("{}/v1/onChainSignersByFid?fid={}", hub_url.trim_end_matches('/'), fid));
+    if let Ok(api_key) = env::var("NEYNAR_API_KEY") {
+        request = request.header("x-api-key", api_key);
+    }
+
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(anyhow::anyhow!("Hub returned {}: {}", status, response.text().await?));
+    }
+
+    let page: OnChainSignersPage = response.json().await?;
+    Ok(is_active_signer(page.events, key))
+}
+
+fn is_active_signer(mut events: Vec<OnChainEvent>, key: &str) -> bool {
+    events.sort_by_key(|event| (event.block_number, event.log_index));
+    events
+        .iter()
+        .filter_map(|event| event.signer_event_body.as_ref())
+        .filter(|body| body.key.eq_ignore_ascii_case(key))
+        .fold(false, |active, body| match body.event_type.as_str() {
+            "SIGNER_EVENT_TYPE_ADD" => true,
+            "SIGNER_EVENT_TYPE_REMOVE" | "SIGNER_EVENT_TYPE_ADMIN_RESET" => false,
+            _ => active,
+        })
+}
+
+// Guarda o borra el token según el evento; al activar notificaciones se manda una de bienvenida
+pub async fn apply_event(redis_client: &redis::Client, event: &FrameEvent) -> Result<()> {
+    let mut con = redis_client.get_async_connection().await?;
+
+    let details = match event.event.as_str() {
+        "frame_added" | "miniapp_added" | "notifications_enabled" => event.notification_details.as_ref(),
+        "frame_removed" | "miniapp_removed" | "notifications_disabled" => None,
+        other => {
+            debug!(fid = event.fid, event = other, "ignoring Frame event");
+            return Ok(());
+        }
+    };
+
+    match details {
+        Some(details) => {
+            let _: () = con.hset(DETAILS_KEY, event.fid, serde_json::to_string(details)?).await?;
+            info!(fid = event.fid, event = %event.event, "Frame notifications enabled");
+            notify_in_background(
+                redis_client.clone(),
+                event.fid,
+                Notification::new(
+                    &format!("welcome-{}", event.fid),
+                    "Welcome to Qawakun",
+                    "You will be notified about your NFT and your proposals.",
+                ),
+            );
+        },
+        None => {
+            let _: () = con.hdel(DETAILS_KEY, event.fid).await?;
+            info!(fid = event.fid, event = %event.event, "Frame notifications disabled");
+        },
+    }
+    Ok(())
+}
+
+// Reserva un envío para el FID, salvo que no hayan pasado 30 s desde el anterior o
+// se haya agotado el cupo diario
+async fn reserve(con: &mut redis::aio::Connection, fid: u64) -> redis::RedisResult<Reservation> {
+    let throttle_key = format!("{}{}", THROTTLE_PREFIX, fid);
+    let reserved: Option<String> = redis::cmd("SET")
+        .arg(&throttle_key)
+        .arg(1)
+        .arg("NX")
+        .arg("EX")
+        .arg(THROTTLE_SECS)
+        .query_async(con)
+        .await?;
+    if reserved.is_none() {
+        let remaining: i64 = con.ttl(&throttle_key).await?;
+        return Ok(Reservation::Throttled(remaining.max(1)));
+    }
+
+    let daily_key = format!("{}{}:{}", DAILY_PREFIX, fid, Utc::now().format("%Y%m%d"));
+    let sent: u64 = con.incr(&daily_key, 1).await?;
+    if sent == 1 {
+        let _: () = con.expire(&daily_key, 24 * 60 * 60).await?;
+    }
+    Ok(if sent <= DAILY_LIMIT { Reservation::Reserved } else { Reservation::DailyLimit })
+}
+
+async fn queue_pending(con: &mut redis::aio::Connection, fid: u64, notification: &Notification, delay_secs: i64) -> redis::RedisResult<()> {
+    let pending = PendingNotification { fid, notification: notification.clone(), attempts: 0 };
+    PENDING.schedule(con, &pending, delay_secs).await
+}
+
+async fn post(url: &str, tokens: &[String], notification: &Notification) -> Result<SendResult> {
+    let target_url = env::var("FRAME_URL")
+        .map_err(|_| anyhow::anyhow!("FRAME_URL not configured"))?;
+
+    let response = reqwest::Client::new()
+        .post(url)
+        .json(&json!({
+            "notificationId": notification.id,
+            "title": notification.title,
+            "body": notification.body,
+            "targetUrl": target_url,
+            "tokens": tokens,
+        }))
+        .send()
+        .await?;
+
+    let status = response.status();
+    let response_text = response.text().await?;
+    if !status.is_success() {
+        return Err(anyhow::anyhow!("Notification endpoint returned {}: {}", status, response_text));
+    }
+
+    let parsed: SendResponse = serde_json::from_str(&response_text)?;
+    Ok(parsed.result)
+}
+
+fn outcome(result: &SendResult, token: &str) -> Delivery {
+    if result.invalid_tokens.iter().any(|t| t == token) {
+        Delivery::InvalidToken
+    } else if result.rate_limited_tokens.iter().any(|t| t == token) {
+        Delivery::RateLimited
+    } else if result.successful_tokens.iter().any(|t| t == token) {
+        Delivery::Sent
+    } else {
+        Delivery::Failed
+    }
+}
+
+// Un token inválido (el usuario quitó el Frame o revocó permisos) no se vuelve a usar.
+// Solo se borra si sigue siendo el guardado: puede haber llegado uno nuevo por webhook
+async fn forget_token(con: &mut redis::aio::Connection, fid: u64, details: &NotificationDetails) -> Result<()> {
+    let stored: Option<String> = con.hget(DETAILS_KEY, fid).await?;
+    let current = stored.and_then(|s| serde_json::from_str::<NotificationDetails>(&s).ok());
+    if current.as_ref() == Some(details) {
+        let _: () = con.hdel(DETAILS_KEY, fid).await?;
+        info!(fid, "invalid Frame notification token removed");
+    }
+    Ok(())
+}
+
+pub async fn notify(redis_client: &redis::Client, fid: u64, notification: &Notification) -> Result<Delivery> {
+    let mut con = redis_client.get_async_connection().await?;
+
+    let stored: Option<String> = con.hget(DETAILS_KEY, fid).await?;
+    let details = match stored.and_then(|s| serde_json::from_str::<NotificationDetails>(&s).ok()) {
+        Some(details) => details,
+        None => return Ok(Delivery::NoToken),
+    };
+
+    let delivery = match reserve(&mut con, fid).await? {
+        Reservation::Reserved => {
+            let result = post(&details.url, std::slice::from_ref(&details.token), notification).await?;
+            outcome(&result, &details.token)
+        },
+        Reservation::Throttled(remaining) => {
+            queue_pending(&mut con, fid, notification, remaining).await?;
+            Delivery::Queued
+        },
+        Reservation::DailyLimit => Delivery::RateLimited,
+    };
+
+    if delivery == Delivery::InvalidToken {
+        forget_token(&mut con, fid, &details).await?;
+    }
+    metrics::frame_notification(delivery.as_str());
+    Ok(delivery)
+}
+
+// Envía a todos los FIDs suscritos salvo `skip`, agrupando tokens por URL de cliente
+pub async fn broadcast(
+    redis_client: &redis::Client,
+    notification: &Notification,
+    skip: &[u64],
+) -> Result<HashMap<&'static str, usize>> {
+    let mut con = redis_client.get_async_connection().await?;
+    let stored: HashMap<u64, String> = con.hgetall(DETAILS_KEY).await?;
+
+    let mut summary: HashMap<&'static str, usize> = HashMap::new();
+    let mut by_url: HashMap<String, Vec<(u64, NotificationDetails)>> = HashMap::new();
+    for (fid, details) in stored {
+        if skip.contains(&fid) {
+            continue;
+        }
+        let details: NotificationDetails = match serde_json::from_str(&details) {
+            Ok(details) => details,
+            Err(_) => continue,
+        };
+        match reserve(&mut con, fid).await? {
+            Reservation::Reserved => by_url.entry(details.url.clone()).or_default().push((fid, details)),
+            Reservation::Throttled(remaining) => {
+                queue_pending(&mut con, fid, notification, remaining).await?;
+                metrics::frame_notification(Delivery::Queued.as_str());
+                *summary.entry(Delivery::Queued.as_str()).or_default() += 1;
+            },
+            Reservation::DailyLimit => *summary.entry(Delivery::RateLimited.as_str()).or_default() += 1,
+        }
+    }
+
+    for (url, targets) in by_url {
+        for batch in targets.chunks(MAX_TOKENS_PER_REQUEST) {
+            let tokens: Vec<String> = batch.iter().map(|(_, details)| details.token.clone()).collect();
+            let result = match post(&url, &tokens, notification).await {
+                Ok(result) => Some(result),
+                Err(e) => {
+                    warn!(url = %url, tokens = tokens.len(), error = %e, "error sending Frame notifications");
+                    None
+                }
+            };
+
+            for (fid, details) in batch {
+                let delivery = result.as_ref()
+                    .map(|result| outcome(result, &details.token))
+                    .unwrap_or(Delivery::Failed);
+                if delivery == Delivery::InvalidToken {
+                    forget_token(&mut con, *fid, details).await?;
+                }
+                metrics::frame_notification(delivery.as_str());
+                *summary.entry(delivery.as_str()).or_default() += 1;
+            }
+        }
+    }
+
+    info!(id = %notification.id, summary = ?summary, "Frame notification broadcast");
+    Ok(summary)
+}
+
+// Para los handlers: la notificación sale en segundo plano y los fallos solo se registran
+pub fn notify_in_background(redis_client: redis::Client, fid: u64, notification: Notification) {
+    tokio::spawn(async move {
+        match notify(&redis_client, fid, &notification).await {
+            Ok(delivery) => debug!(fid, id = %notification.id, delivery = delivery.as_str(), "Frame notification"),
+            Err(e) => warn!(fid, id = %notification.id, error = %e, "error sending Frame notification"),
+        }
+    });
+}
+
+// Envía las notificaciones pendientes cuya ventana ya venció. Si vuelven a caer en
+// la ventana (otro envío se adelantó) se encolan de nuevo desde `notify`
+pub async fn start_pending_sender(redis_client: redis::Client, mut shutdown: Shutdown) -> Result<()> {
+    info!("starting Frame notification sender");
+    let mut con = redis_client.get_async_connection().await?;
+
+    loop {
+        let result: Result<()> = async {
+            while let Some(pending) = PENDING.take_due::<PendingNotification>(&mut con).await? {
+                let PendingNotification { fid, notification, attempts } = &pending.item;
+                match notify(&redis_client, *fid, notification).await {
+                    Ok(delivery) => {
+                        debug!(fid, id = %notification.id, delivery = delivery.as_str(), "pending Frame notification");
+                        PENDING.complete(&mut con, &pending).await?;
+                    },
+                    Err(e) => {
+                        warn!(fid, id = %notification.id, error = %e, "error sending pending Frame notification");
+                        let retry = PendingNotification { fid: *fid, notification: notification.clone(), attempts: attempts + 1 };
+                        PENDING.fail(&mut con, Some(&pending), &retry, retry.attempts).await?;
+                    },
+                }
+            }
+            Ok(())
+        }.await;
+        if let Err(e) = result {
+            error!(error = %e, "error sending pending Frame notifications");
+            con = redis_client.get_async_connection().await?;
+        }
+
+        if shutdown.sleep(PENDING_POLL_INTERVAL).await {
+            break;
+        }
+    }
+
+    info!("Frame notification sender stopped");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Signer, SigningKey};
+
+    fn encode(value: serde_json::Value) -> String {
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(value.to_string())
+    }
+
+    fn signed_event(signer: &SigningKey, fid: u64, payload: serde_json::Value) -> serde_json::Value {
+        let header = encode(json!({
+            "fid": fid,
+            "type": "app_key",
+            "key": format!("0x{}", hex::encode(signer.verifying_key().to_bytes())),
+        }));
+        let payload = encode(payload);
+        let signature = signer.sign(format!("{}.{}", header, payload).as_bytes());
+        json!({
+            "header": header,
+            "payload": payload,
+            "signature": base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(signature.to_bytes()),
+        })
+    }
+
+    fn added() -> serde_json::Value {
+        json!({
+            "event": "frame_added",
+            "notificationDetails": { "url": "https://api.warpcast.com/v1/frame-notifications", "token": "token-1" },
+        })
+    }
+
+    #[test]
+    fn parses_event_signed_with_app_key() {
+        let signer = SigningKey::from_bytes(&[3u8; 32]);
+        let body = signed_event(&signer, 42, added()).to_string();
+
+        let event = parse_event(body.as_bytes()).unwrap();
+        assert_eq!(event.fid, 42);
+        assert_eq!(event.event, "frame_added");
+        assert_eq!(event.key, format!("0x{}", hex::encode(signer.verifying_key().to_bytes())));
+        assert_eq!(event.notification_details.unwrap().token, "token-1");
+    }
+
+    #[test]
+    fn rejects_tampered_payload_or_foreign_signature() {
+        let signer = SigningKey::from_bytes(&[3u8; 32]);
+        let mut event = signed_event(&signer, 42, added());
+        event["payload"] = json!(encode(json!({ "event": "frame_removed" })));
+        assert!(parse_event(event.to_string().as_bytes()).is_err());
+
+        // Firmado por otra key que la del header
+        let other = SigningKey::from_bytes(&[4u8; 32]);
+        let mut event = signed_event(&signer, 42, added());
+        event["signature"] = signed_event(&other, 42, added())["signature"].clone();
+        assert!(parse_event(event.to_string().as_bytes()).is_err());
+    }
+
+    #[test]
+    fn rejects_non_app_key_signatures() {
+        let signer = SigningKey::from_bytes(&[3u8; 32]);
+        let mut event = signed_event(&signer, 42, added());
+        event["header"] = json!(encode(json!({ "fid": 42, "type": "custody", "key": "0x00" })));
+        assert!(parse_event(event.to_string().as_bytes()).is_err());
+    }
+
+    fn signer_event(block_number: u64, log_index: u32, key: &str, event_type: &str) -> OnChainEvent {
+        OnChainEvent {
+            block_number,
+            log_index,
+            signer_event_body: Some(SignerEventBody { key: key.to_string(), event_type: event_type.to_string() }),
+        }
+    }
+
+    #[test]
+    fn app_key_state_follows_the_last_event_in_chain_order() {
+        let key = "0xABCDEF";
+        assert!(!is_active_signer(Vec::new(), key));
+        assert!(is_active_signer(vec![signer_event(10, 0, "0xabcdef", "SIGNER_EVENT_TYPE_ADD")], key));
+
+        // Llegan desordenados: la baja es posterior al alta
+        let removed = vec![
+            signer_event(20, 1, key, "SIGNER_EVENT_TYPE_REMOVE"),
+            signer_event(10, 0, key, "SIGNER_EVENT_TYPE_ADD"),
+        ];
+        assert!(!is_active_signer(removed, key));
+
+        let re_added = vec![
+            signer_event(30, 0, key, "SIGNER_EVENT_TYPE_ADD"),
+            signer_event(20, 0, key, "SIGNER_EVENT_TYPE_ADMIN_RESET"),
+            signer_event(10, 0, key, "SIGNER_EVENT_TYPE_ADD"),
+        ];
+        assert!(is_active_signer(re_added, key));
+
+        // Eventos de otras keys no cuentan
+        assert!(!is_active_signer(vec![signer_event(10, 0, "0x1234", "SIGNER_EVENT_TYPE_ADD")], key));
+    }
+}
@@ -0,0 +1,78 @@
+use anyhow::Result;
+use chrono::Utc;
+use ethers::signers::LocalWallet;
+use tokio::sync::{Mutex, RwLock};
+use tracing::{info, warn};
+use super::auth::{Auth, FarcasterUser, Session};
+
+// Duración de cada token y margen con el que se renueva antes de que caduque
+pub const SESSION_DURATION_SECS: i64 = 60 * 60;
+const REFRESH_MARGIN_SECS: i64 = 5 * 60;
+
+// Mantiene vivo el token de Warpcast: lo renueva antes de caducar y tras un 401
+pub struct SessionManager {
+    wallet: LocalWallet,
+    session: RwLock<Session>,
+    // Una sola renovación a la vez; el resto de peticiones espera y reutiliza el token nuevo
+    refreshing: Mutex<()>,
+}
+
+impl SessionManager {
+    // Abre la sesión y verifica la custody address; devuelve también la cuenta del bot
+    pub async fn start(wallet: LocalWallet) -> Result<(Self, FarcasterUser)> {
+        let session = Auth::handle_session(&wallet, Some(SESSION_DURATION_SECS)).await?;
+        let bot = Auth::verify_custody(&wallet, &session.token).await?;
+        info!(fid = bot.fid, expires_at = session.expires_at, "Farcaster session started");
+
+        Ok((
+            Self {
+                wallet,
+                session: RwLock::new(session),
+                refreshing: Mutex::new(()),
+            },
+            bot,
+        ))
+    }
+
+    // Token vigente, renovado si entra en el margen de caducidad
+    pub async fn token(&self) -> Result<String> {
+        {
+            let session = self.session.read().await;
+            if !expiring(&session) {
+                return Ok(session.token.clone());
+            }
+        }
+        self.refresh(None).await
+    }
+
+    // Renueva después de que la API rechace `rejected`. Si otra petición ya lo
+    // renovó mientras tanto se devuelve ese token sin pedir otro
+    pub async fn refresh_rejected(&self, rejected: &str) -> Result<String> {
+        warn!("Farcaster session token rejected, refreshing");
+        self.refresh(Some(rejected)).await
+    }
+
+    async fn refresh(&self, rejected: Option<&str>) -> Result<String> {
+        let _guard = self.refreshing.lock().await;
+        {
+            let session = self.session.read().await;
+            let stale = match rejected {
+                Some(rejected) => session.token == rejected,
+                None => expiring(&session),
+            };
+            if !stale {
+                return Ok(session.token.clone());
+            }
+        }
+
+        let session = Auth::handle_session(&self.wallet, Some(SESSION_DURATION_SECS)).await?;
+        info!(expires_at = session.expires_at, "Farcaster session refreshed");
+        let token = session.token.clone();
+        *self.session.write().await = session;
+        Ok(token)
+    }
+}
+
+fn expiring(session: &Session) -> bool {
+    Utc::now().timestamp_millis() >= session.expires_at - REFRESH_MARGIN_SECS * 1000
+}
@@ -0,0 +1,189 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use ethers::signers::coins_bip39::English;
+use ethers::signers::{LocalWallet, MnemonicBuilder, Signer};
+use serde::Deserialize;
+use serde_json::json;
+use std::env;
+use crate::telemetry;
+use tracing::info;
+use super::auth::FarcasterUser;
+use super::backend::FarcasterBackend;
+use super::cast::{Cast, CastResult};
+use super::composer::{CastParent, CastPayload};
+use super::session::SessionManager;
+
+const API_ROOT: &str = "https://api.warpcast.com";
+
+#[derive(Debug, Deserialize)]
+struct CastRoot {
+    result: CastResult,
+}
+
+// Feed de menciones y respuestas: el cursor de la siguiente página viene en `next`
+#[derive(Debug, Deserialize)]
+struct NotificationsRoot {
+    result: NotificationsResult,
+    #[serde(default)]
+    next: Option<NextPage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NotificationsResult {
+    #[serde(default)]
+    notifications: Vec<Cast>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NextPage {
+    cursor: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SingleCastRoot {
+    result: SingleCastResult,
+}
+
+#[derive(Debug, Deserialize)]
+struct SingleCastResult {
+    cast: Cast,
+}
+
+// API privada de Warpcast con un token de sesión firmado por la custody address
+pub struct WarpcastBackend {
+    http: reqwest::Client,
+    session: SessionManager,
+    bot: FarcasterUser,
+}
+
+impl WarpcastBackend {
+    pub async fn new(wallet: LocalWallet) -> Result<Self> {
+        let (session, bot) = SessionManager::start(wallet).await?;
+        Ok(Self { http: reqwest::Client::new(), session, bot })
+    }
+
+    pub async fn from_env() -> Result<Self> {
+        let mnemonic = env::var("MNEMONIC")?;
+        let wallet: LocalWallet = MnemonicBuilder::<English>::default()
+            .phrase(mnemonic.as_str())
+            .derivation_path("m/44'/60'/0'/0/0")?
+            .build()?
+            .with_chain_id(1u64);
+
+        let wallet_address = format!("{:?}", wallet.address());
+        info!(wallet = %telemetry::redact_wallet(&wallet_address), "starting Farcaster session");
+
+        Self::new(wallet).await
+    }
+
+    // Envía la petición con el token vigente; si la API responde 401 renueva la
+    // sesión y reintenta una sola vez
+    async fn send<F>(&self, build: F) -> Result<reqwest::Response>
+    where
+        F: Fn(&reqwest::Client) -> reqwest::RequestBuilder + Send + Sync,
+    {
+        let token = self.session.token().await?;
+        let response = build(&self.http).bearer_auth(&token).send().await?;
+        if response.status() != reqwest::StatusCode::UNAUTHORIZED {
+            return Ok(response);
+        }
+
+        let token = self.session.refresh_rejected(&token).await?;
+        Ok(build(&self.http).bearer_auth(&token).send().await?)
+    }
+
+    async fn get(&self, url: &str) -> Result<reqwest::Response> {
+        let response = self.send(|http| http.get(url)).await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(anyhow::anyhow!("Warpcast returned {}: {}", status, response.text().await?));
+        }
+        Ok(response)
+    }
+}
+
+// El cursor es opaco y puede traer caracteres reservados de la query
+fn encode_query(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
+#[async_trait]
+impl FarcasterBackend for WarpcastBackend {
+    fn name(&self) -> &'static str {
+        "warpcast"
+    }
+
+    fn bot(&self) -> &FarcasterUser {
+        &self.bot
+    }
+
+    async fn get_mentions(&self, limit: Option<i32>, cursor: Option<&str>) -> Result<CastResult> {
+        let mut url = format!("{}/v1/mention-and-reply-notifications?limit={}", API_ROOT, limit.unwrap_or(25));
+        if let Some(cursor) = cursor {
+            url.push_str(&format!("&cursor={}", encode_query(cursor)));
+        }
+
+        let root: NotificationsRoot = self.get(&url).await?.json().await?;
+        Ok(CastResult {
+            casts: root.result.notifications,
+            cursor: root.next.and_then(|next| next.cursor),
+        })
+    }
+
+    async fn get_cast(&self, _fid: Option<u64>, hash: &str) -> Result<Option<Cast>> {
+        let url = format!("{}/v2/cast?hash={}", API_ROOT, hash);
+        let response = self.send(|http| http.get(&url)).await?;
+
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(anyhow::anyhow!("Warpcast returned {}: {}", status, response.text().await?));
+        }
+
+        let root: SingleCastRoot = response.json().await?;
+        Ok(Some(root.result.cast))
+    }
+
+    async fn get_replies(&self, _parent_fid: u64, parent_hash: &str) -> Result<Vec<Cast>> {
+        let url = format!("{}/v2/thread-casts?castHash={}", API_ROOT, parent_hash);
+        let root: CastRoot = self.get(&url).await?.json().await?;
+
+        Ok(root.result.casts
+            .into_iter()
+            .filter(|cast| cast.parent_hash.as_deref() == Some(parent_hash))
+            .collect())
+    }
+
+    async fn get_thread(&self, _root_fid: Option<u64>, thread_hash: &str) -> Result<Vec<Cast>> {
+        let url = format!("{}/v2/thread-casts?castHash={}", API_ROOT, thread_hash);
+        let root: CastRoot = self.get(&url).await?.json().await?;
+        Ok(root.result.casts)
+    }
+
+    async fn publish_cast(&self, cast: &CastPayload) -> Result<Cast> {
+        let mut payload = json!({
+            "text": cast.text,
+            "embeds": cast.embeds,
+        });
+        match &cast.parent {
+            Some(CastParent::Cast { hash, fid }) => payload["parent"] = json!({ "hash": hash, "fid": fid }),
+            Some(CastParent::Url(url)) => payload["parent"] = json!({ "url": url }),
+            None => {},
+        }
+
+        let url = format!("{}/v2/casts", API_ROOT);
+        let response = self.send(|http| http.post(&url).json(&payload)).await?;
+
+        let status = response.status();
+        let response_text = response.text().await?;
+        if !status.is_success() {
+            return Err(anyhow::anyhow!("Warpcast returned {}: {}", status, response_text));
+        }
+
+        let published: SingleCastRoot = serde_json::from_str(&response_text)?;
+        Ok(published.result.cast)
+    }
+}
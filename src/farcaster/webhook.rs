@@ -0,0 +1,149 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha512;
+use crate::retry_queue::RetryQueue;
+use crate::social::inbox::Inbox;
+use super::cast::{Cast, CastAuthor};
+
+// Cola de casts recibidos por webhook, consumida por el worker de Farcaster
+pub const MENTION_INBOX: Inbox = Inbox::new("farcaster:mention_queue", "farcaster:webhook:", 24 * 60 * 60);
+
+// Menciones del webhook cuya respuesta falló: 1 min la primera vez y el doble tras
+// cada fallo, para que los intentos del pipeline no se gasten en segundos
+pub const RETRY_QUEUE: RetryQueue = RetryQueue::new("farcaster:retry_queue", "farcaster:dead_letter", 5, 60, 10 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedCast {
+    pub cast: Cast,
+    pub attempts: u32,
+}
+
+pub const SIGNATURE_HEADER: &str = "X-Neynar-Signature";
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Ingested {
+    Queued,
+    Duplicate,
+    Ignored,
+}
+
+#[derive(Debug, Deserialize)]
+struct WebhookEvent {
+    #[serde(rename = "type")]
+    kind: String,
+    data: serde_json::Value,
+}
+
+// Formato de cast de Neynar (snake_case, timestamp ISO 8601)
+#[derive(Debug, Deserialize)]
+struct NeynarCast {
+    hash: String,
+    thread_hash: Option<String>,
+    parent_hash: Option<String>,
+    #[serde(default)]
+    parent_author: Option<NeynarParentAuthor>,
+    #[serde(default)]
+    parent_url: Option<String>,
+    author: NeynarUser,
+    text: String,
+    timestamp: DateTime<Utc>,
+    #[serde(default)]
+    mentioned_profiles: Vec<NeynarUser>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NeynarParentAuthor {
+    fid: Option<u64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NeynarUser {
+    fid: u64,
+    #[serde(default)]
+    username: String,
+}
+
+impl From<NeynarCast> for Cast {
+    fn from(cast: NeynarCast) -> Self {
+        Cast {
+            hash: cast.hash,
+            thread_hash: cast.thread_hash,
+            parent_hash: cast.parent_hash,
+            parent_author: cast.parent_author
+                .and_then(|parent| parent.fid)
+                .map(|fid| CastAuthor { fid, username: String::new() }),
+            parent_url: cast.parent_url,
+            author: CastAuthor { fid: cast.author.fid, username: cast.author.username },
+            text: cast.text,
+            timestamp: cast.timestamp.timestamp_millis(),
+            mentions: cast.mentioned_profiles
+                .into_iter()
+                .map(|user| CastAuthor { fid: user.fid, username: user.username })
+                .collect(),
+        }
+    }
+}
+
+// La firma es el HMAC-SHA512 en hex del cuerpo crudo con el secreto del webhook
+pub fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let signature = match hex::decode(signature.trim()) {
+        Ok(signature) => signature,
+        Err(_) => return false,
+    };
+
+    let mut mac = match Hmac::<Sha512>::new_from_slice(secret.as_bytes()) {
+        Ok(mac) => mac,
+        Err(_) => return false,
+    };
+    mac.update(body);
+    mac.verify_slice(&signature).is_ok()
+}
+
+// Deduplica por hash (los reintentos del proveedor llegan repetidos) y encola
+// los `cast.created`; el resto de eventos se ignora
+pub async fn ingest(redis_client: &redis::Client, body: &[u8]) -> Result<Ingested> {
+    let event: WebhookEvent = serde_json::from_slice(body)?;
+    if event.kind != "cast.created" {
+        return Ok(Ingested::Ignored);
+    }
+
+    let cast: Cast = serde_json::from_value::<NeynarCast>(event.data)?.into();
+
+    let mut con = redis_client.get_async_connection().await?;
+    if !MENTION_INBOX.push(&mut con, &cast.hash, &serde_json::to_string(&cast)?).await? {
+        return Ok(Ingested::Duplicate);
+    }
+    Ok(Ingested::Queued)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sign(secret: &str, body: &[u8]) -> String {
+        let mut mac = Hmac::<Sha512>::new_from_slice(secret.as_bytes()).unwrap();
+        mac.update(body);
+        hex::encode(mac.finalize().into_bytes())
+    }
+
+    #[test]
+    fn accepts_hmac_of_raw_body() {
+        let body = br#"{"type":"cast.created","data":{}}"#;
+        let signature = sign("webhook-secret", body);
+        assert!(verify_signature("webhook-secret", body, &signature));
+        assert!(verify_signature("webhook-secret", body, &format!(" {}\n", signature)));
+    }
+
+    #[test]
+    fn rejects_wrong_secret_body_or_signature() {
+        let body = br#"{"type":"cast.created","data":{}}"#;
+        let signature = sign("webhook-secret", body);
+        assert!(!verify_signature("other-secret", body, &signature));
+        assert!(!verify_signature("webhook-secret", br#"{"type":"cast.created","data":[]}"#, &signature));
+        assert!(!verify_signature("webhook-secret", body, &signature[..64]));
+        assert!(!verify_signature("webhook-secret", body, "not hex"));
+        assert!(!verify_signature("webhook-secret", body, ""));
+    }
+}
@@ -0,0 +1,529 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use rand::RngCore;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::metrics;
+use crate::telemetry;
+use crate::workers::Shutdown;
+use tokio::time::Duration;
+use tracing::{debug, error, info, warn};
+
+// Cola por tipo de job: sorted set id -> momento en que toca ejecutar el job
+const QUEUE_PREFIX: &str = "jobs:queue:";
+// Jobs en ejecución por tipo: sorted set id -> fin del lease. Un lease vencido es un
+// worker que murió a mitad del job
+const RUNNING_PREFIX: &str = "jobs:running:";
+const JOB_PREFIX: &str = "jobs:";
+const IDEMPOTENCY_PREFIX: &str = "jobs:idempotency:";
+
+// Un job terminado se conserva una semana para consultar su estado
+const FINISHED_TTL_SECS: i64 = 7 * 24 * 60 * 60;
+// Ventana en la que la misma idempotency key devuelve el mismo job
+const IDEMPOTENCY_TTL_SECS: u64 = 24 * 60 * 60;
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+// Espera antes del primer reintento; se duplica con cada intento fallido
+const RETRY_BASE_SECS: i64 = 30;
+// Más que lo que tarda un mint con sus dos recibos
+const LEASE_SECS: i64 = 10 * 60;
+const POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Retrying,
+    Succeeded,
+    Failed,
+}
+
+impl JobStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Retrying => "retrying",
+            JobStatus::Succeeded => "succeeded",
+            JobStatus::Failed => "failed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Job {
+    pub id: String,
+    pub kind: String,
+    pub status: JobStatus,
+    pub payload: serde_json::Value,
+    #[serde(default)]
+    pub idempotency_key: Option<String>,
+    pub attempts: u32,
+    pub max_attempts: u32,
+    #[serde(default)]
+    pub result: Option<serde_json::Value>,
+    #[serde(default)]
+    pub error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub run_at: i64,
+    // Correlation ID de la petición que lo encoló; enlaza la petición con la ejecución
+    #[serde(default)]
+    pub correlation_id: Option<String>,
+}
+
+impl Job {
+    // Estado para GET /jobs/{id}; el payload puede llevar datos del usuario y no se expone
+    pub fn summary(&self) -> serde_json::Value {
+        serde_json::json!({
+            "id": self.id,
+            "kind": self.kind,
+            "status": self.status,
+            "attempts": self.attempts,
+            "result": self.result,
+            "error": self.error,
+            "created_at": self.created_at,
+            "updated_at": self.updated_at,
+            "next_attempt_at": if self.status == JobStatus::Retrying { Some(self.run_at) } else { None },
+        })
+    }
+
+    pub fn is_finished(&self) -> bool {
+        matches!(self.status, JobStatus::Succeeded | JobStatus::Failed)
+    }
+}
+
+pub enum Enqueued {
+    Created(Job),
+    // Ya había un job con esa idempotency key
+    Existing(Job),
+}
+
+impl Enqueued {
+    pub fn job(&self) -> &Job {
+        match self {
+            Enqueued::Created(job) | Enqueued::Existing(job) => job,
+        }
+    }
+}
+
+// Sin conversión desde anyhow::Error: cada handler decide qué errores se pueden
+// reintentar sin repetir un efecto que ya salió (p. ej. una transacción enviada)
+pub enum JobError {
+    Retry(anyhow::Error),
+    // No tiene sentido reintentar (datos inválidos, ya reclamado, ...)
+    Fatal(anyhow::Error),
+    // Todavía no se puede ejecutar (p. ej. minting pausado); no gasta un intento
+    Defer(Duration),
+}
+
+#[async_trait]
+pub trait JobHandler: Send + Sync {
+    fn kind(&self) -> &'static str;
+
+    // Devuelve el resultado que se guarda en el job
+    async fn run(&self, payload: &serde_json::Value) -> std::result::Result<serde_json::Value, JobError>;
+}
+
+fn job_key(id: &str) -> String {
+    format!("{}{}", JOB_PREFIX, id)
+}
+
+fn queue_key(kind: &str) -> String {
+    format!("{}{}", QUEUE_PREFIX, kind)
+}
+
+fn running_key(kind: &str) -> String {
+    format!("{}{}", RUNNING_PREFIX, kind)
+}
+
+fn new_id() -> String {
+    let mut bytes = [0u8; 12];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+async fn save(con: &mut redis::aio::Connection, job: &Job) -> redis::RedisResult<()> {
+    let payload = serde_json::to_string(job).unwrap_or_default();
+    if job.is_finished() {
+        con.set_ex(job_key(&job.id), payload, FINISHED_TTL_SECS as usize).await
+    } else {
+        con.set(job_key(&job.id), payload).await
+    }
+}
+
+pub async fn get(con: &mut redis::aio::Connection, id: &str) -> redis::RedisResult<Option<Job>> {
+    let stored: Option<String> = con.get(job_key(id)).await?;
+    Ok(stored.and_then(|s| serde_json::from_str(&s).ok()))
+}
+
+// Encola un job. Con idempotency key, mientras el job anterior con esa clave no
+// haya fallado se devuelve ese en lugar de crear otro
+pub async fn enqueue(
+    redis_client: &redis::Client,
+    kind: &str,
+    payload: serde_json::Value,
+    idempotency_key: Option<String>,
+) -> Result<Enqueued> {
+    let mut con = redis_client.get_async_connection().await?;
+    let now = Utc::now().timestamp();
+    let job = Job {
+        id: new_id(),
+        kind: kind.to_string(),
+        status: JobStatus::Queued,
+        payload,
+        idempotency_key: idempotency_key.clone(),
+        attempts: 0,
+        max_attempts: DEFAULT_MAX_ATTEMPTS,
+        result: None,
+        error: None,
+        created_at: now,
+        updated_at: now,
+        run_at: now,
+        correlation_id: telemetry::correlation_id(),
+    };
+
+    if let Some(key) = &idempotency_key {
+        let key = format!("{}{}", IDEMPOTENCY_PREFIX, key);
+        let reserved: Option<String> = redis::cmd("SET")
+            .arg(&key)
+            .arg(&job.id)
+            .arg("NX")
+            .arg("EX")
+            .arg(IDEMPOTENCY_TTL_SECS)
+            .query_async(&mut con)
+            .await?;
+        if reserved.is_none() {
+            let existing_id: Option<String> = con.get(&key).await?;
+            let existing = match existing_id {
+                Some(id) => get(&mut con, &id).await?,
+                None => None,
+            };
+            match existing {
+                Some(existing) if existing.status != JobStatus::Failed => return Ok(Enqueued::Existing(existing)),
+                _ => {
+                    let _: () = con.set_ex(&key, &job.id, IDEMPOTENCY_TTL_SECS as usize).await?;
+                },
+            }
+        }
+    }
+
+    let _: () = redis::pipe()
+        .atomic()
+        .set(job_key(&job.id), serde_json::to_string(&job)?)
+        .ignore()
+        .zadd(queue_key(&job.kind), &job.id, now)
+        .ignore()
+        .query_async(&mut con)
+        .await?;
+    metrics::job(&job.kind, "queued");
+    debug!(id = %job.id, kind = %job.kind, "job queued");
+    Ok(Enqueued::Created(job))
+}
+
+// Devuelve a la cola los jobs cuyo lease venció (el worker murió a mitad del job)
+// y devuelve sus ids
+const RECOVER_EXPIRED_SCRIPT: &str = r"
+local now = tonumber(ARGV[1])
+local expired = redis.call('ZRANGEBYSCORE', KEYS[2], '-inf', now)
+for _, id in ipairs(expired) do
+    redis.call('ZREM', KEYS[2], id)
+    redis.call('ZADD', KEYS[1], now, id)
+end
+return expired
+";
+
+// Pasa el siguiente job que ya toca de la cola a running con su lease, de una vez
+// para que dos workers no se lleven el mismo ni se pierda si el proceso cae entre
+// medias
+const TAKE_DUE_SCRIPT: &str = r"
+local due = redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', tonumber(ARGV[1]), 'LIMIT', 0, 1)
+if #due == 0 then
+    return false
+end
+redis.call('ZREM', KEYS[1], due[1])
+redis.call('ZADD', KEYS[2], tonumber(ARGV[1]) + tonumber(ARGV[2]), due[1])
+return due[1]
+";
+
+async fn recover_expired(con: &mut redis::aio::Connection, kind: &str) -> redis::RedisResult<()> {
+    let now = Utc::now().timestamp();
+    let expired: Vec<String> = redis::Script::new(RECOVER_EXPIRED_SCRIPT)
+        .key(queue_key(kind))
+        .key(running_key(kind))
+        .arg(now)
+        .invoke_async(con)
+        .await?;
+    for id in expired {
+        if let Some(mut job) = get(con, &id).await? {
+            warn!(id = %job.id, kind = %job.kind, attempts = job.attempts, "job lease expired, requeuing");
+            job.status = JobStatus::Retrying;
+            job.error = Some("worker stopped while running the job".to_string());
+            job.updated_at = now;
+            job.run_at = now;
+            save(con, &job).await?;
+        }
+    }
+    Ok(())
+}
+
+// Saca de la cola el siguiente job que ya toca y lo marca en ejecución con un lease.
+// Un id sin job guardado se descarta
+async fn take_due(con: &mut redis::aio::Connection, kind: &str) -> redis::RedisResult<Option<Job>> {
+    loop {
+        let now = Utc::now().timestamp();
+        let id: Option<String> = redis::Script::new(TAKE_DUE_SCRIPT)
+            .key(queue_key(kind))
+            .key(running_key(kind))
+            .arg(now)
+            .arg(LEASE_SECS)
+            .invoke_async(con)
+            .await?;
+        let id = match id {
+            Some(id) => id,
+            None => return Ok(None),
+        };
+
+        match get(con, &id).await? {
+            Some(mut job) => {
+                job.status = JobStatus::Running;
+                job.attempts += 1;
+                job.updated_at = now;
+                save(con, &job).await?;
+                return Ok(Some(job));
+            },
+            None => {
+                let _: () = con.zrem(running_key(kind), &id).await?;
+            },
+        }
+    }
+}
+
+async fn finish(
+    con: &mut redis::aio::Connection,
+    mut job: Job,
+    outcome: std::result::Result<serde_json::Value, JobError>,
+) -> redis::RedisResult<()> {
+    let now = Utc::now().timestamp();
+    job.updated_at = now;
+
+    match outcome {
+        Ok(result) => {
+            info!(id = %job.id, kind = %job.kind, attempts = job.attempts, "job succeeded");
+            job.status = JobStatus::Succeeded;
+            job.result = Some(result);
+            job.error = None;
+        },
+        Err(JobError::Defer(delay)) => {
+            debug!(id = %job.id, kind = %job.kind, delay_secs = delay.as_secs(), "job deferred");
+            job.status = JobStatus::Queued;
+            job.attempts -= 1;
+            job.run_at = now + delay.as_secs() as i64;
+        },
+        Err(JobError::Retry(e)) if job.attempts < job.max_attempts => {
+            warn!(id = %job.id, kind = %job.kind, attempts = job.attempts, error = %e, "job failed, will retry");
+            job.status = JobStatus::Retrying;
+            job.error = Some(e.to_string());
+            job.run_at = now + RETRY_BASE_SECS * 2i64.pow(job.attempts - 1);
+        },
+        Err(JobError::Retry(e)) | Err(JobError::Fatal(e)) => {
+            error!(id = %job.id, kind = %job.kind, attempts = job.attempts, error = %e, "job failed");
+            job.status = JobStatus::Failed;
+            job.error = Some(e.to_string());
+        },
+    }
+
+    // Estado, cola y lease juntos: si no, una caída tras guardar el resultado
+    // dejaría el lease vencer y el job terminado se volvería a ejecutar
+    let payload = serde_json::to_string(&job).unwrap_or_default();
+    let mut pipe = redis::pipe();
+    pipe.atomic();
+    if job.is_finished() {
+        pipe.set_ex(job_key(&job.id), payload, FINISHED_TTL_SECS as usize).ignore();
+    } else {
+        pipe.set(job_key(&job.id), payload).ignore()
+            .zadd(queue_key(&job.kind), &job.id, job.run_at).ignore();
+    }
+    pipe.zrem(running_key(&job.kind), &job.id).ignore();
+    let _: () = pipe.query_async(con).await?;
+    metrics::job(&job.kind, job.status.as_str());
+    Ok(())
+}
+
+pub struct JobRunner {
+    redis_client: redis::Client,
+    handlers: HashMap<&'static str, Arc<dyn JobHandler>>,
+}
+
+impl JobRunner {
+    pub fn new(redis_client: redis::Client) -> Self {
+        Self { redis_client, handlers: HashMap::new() }
+    }
+
+    pub fn register(&mut self, handler: Arc<dyn JobHandler>) {
+        self.handlers.insert(handler.kind(), handler);
+    }
+
+    pub fn kinds(&self) -> Vec<&'static str> {
+        self.handlers.keys().copied().collect()
+    }
+
+    async fn run(&self, job: &Job) -> std::result::Result<serde_json::Value, JobError> {
+        match self.handlers.get(job.kind.as_str()) {
+            Some(handler) => handler.run(&job.payload).await,
+            None => Err(JobError::Fatal(anyhow::anyhow!("no handler for job kind {}", job.kind))),
+        }
+    }
+}
+
+// Un worker por tipo de job, así un post a X no espera detrás de un mint. Dentro de
+// un tipo los jobs van de uno en uno: los mints salen de la misma wallet y en
+// paralelo se pisarían el nonce. Al apagar se termina el job en curso
+pub async fn start_worker(runner: Arc<JobRunner>, kind: &'static str, mut shutdown: Shutdown) -> Result<()> {
+    info!(kind, "starting job worker");
+    let mut con = runner.redis_client.get_async_connection().await?;
+
+    loop {
+        recover_expired(&mut con, kind).await?;
+        while !shutdown.is_shutdown() {
+            let job = match take_due(&mut con, kind).await? {
+                Some(job) => job,
+                None => break,
+            };
+            debug!(id = %job.id, kind = %job.kind, attempt = job.attempts, "running job");
+            let outcome = telemetry::traced("job", job.correlation_id.clone(), runner.run(&job)).await;
+            finish(&mut con, job, outcome).await?;
+        }
+
+        if shutdown.sleep(POLL_INTERVAL).await {
+            break;
+        }
+    }
+
+    info!(kind, "job worker stopped");
+    Ok(())
+}
+
+// Necesitan un Redis: TEST_REDIS_URL=redis://... cargo test -- --ignored
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redis_client() -> redis::Client {
+        let url = std::env::var("TEST_REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1/".to_string());
+        redis::Client::open(url).unwrap()
+    }
+
+    // Tipo de job propio de cada test para no pisarse con otros ni con datos reales
+    fn test_kind() -> String {
+        format!("test-{}", new_id())
+    }
+
+    async fn cleanup(con: &mut redis::aio::Connection, kind: &str, jobs: &[&str], idempotency_key: Option<&str>) {
+        let mut keys = vec![queue_key(kind), running_key(kind)];
+        keys.extend(jobs.iter().map(|id| job_key(id)));
+        keys.extend(idempotency_key.map(|key| format!("{}{}", IDEMPOTENCY_PREFIX, key)));
+        let _: () = con.del(keys).await.unwrap();
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs Redis (TEST_REDIS_URL)"]
+    async fn take_then_finish_releases_the_lease() {
+        let client = redis_client();
+        let mut con = client.get_async_connection().await.unwrap();
+        let kind = test_kind();
+
+        let job = enqueue(&client, &kind, serde_json::json!({ "n": 1 }), None).await.unwrap().job().clone();
+        let taken = take_due(&mut con, &kind).await.unwrap().expect("job should be due");
+        assert_eq!(taken.id, job.id);
+        assert_eq!(taken.status, JobStatus::Running);
+        assert_eq!(taken.attempts, 1);
+        let running: Vec<String> = con.zrange(running_key(&kind), 0, -1).await.unwrap();
+        assert_eq!(running, vec![job.id.clone()]);
+        assert!(take_due(&mut con, &kind).await.unwrap().is_none());
+
+        finish(&mut con, taken, Ok(serde_json::json!({ "ok": true }))).await.unwrap();
+        let stored = get(&mut con, &job.id).await.unwrap().unwrap();
+        assert_eq!(stored.status, JobStatus::Succeeded);
+        assert_eq!(stored.result, Some(serde_json::json!({ "ok": true })));
+        let running: i64 = con.zcard(running_key(&kind)).await.unwrap();
+        let queued: i64 = con.zcard(queue_key(&kind)).await.unwrap();
+        assert_eq!((running, queued), (0, 0));
+
+        cleanup(&mut con, &kind, &[&job.id], None).await;
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs Redis (TEST_REDIS_URL)"]
+    async fn retry_goes_back_to_the_queue_with_backoff() {
+        let client = redis_client();
+        let mut con = client.get_async_connection().await.unwrap();
+        let kind = test_kind();
+
+        let job = enqueue(&client, &kind, serde_json::json!({}), None).await.unwrap().job().clone();
+        let taken = take_due(&mut con, &kind).await.unwrap().unwrap();
+        finish(&mut con, taken, Err(JobError::Retry(anyhow::anyhow!("rpc down")))).await.unwrap();
+
+        let stored = get(&mut con, &job.id).await.unwrap().unwrap();
+        assert_eq!(stored.status, JobStatus::Retrying);
+        assert_eq!(stored.error.as_deref(), Some("rpc down"));
+        let score: Option<i64> = con.zscore(queue_key(&kind), &job.id).await.unwrap();
+        assert!(score.unwrap() >= Utc::now().timestamp() + RETRY_BASE_SECS - 1);
+        // Todavía no toca
+        assert!(take_due(&mut con, &kind).await.unwrap().is_none());
+
+        cleanup(&mut con, &kind, &[&job.id], None).await;
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs Redis (TEST_REDIS_URL)"]
+    async fn expired_lease_is_recovered() {
+        let client = redis_client();
+        let mut con = client.get_async_connection().await.unwrap();
+        let kind = test_kind();
+
+        let job = enqueue(&client, &kind, serde_json::json!({}), None).await.unwrap().job().clone();
+        take_due(&mut con, &kind).await.unwrap().unwrap();
+        // El worker murió: el lease vence sin que nadie llame a finish
+        let _: () = con.zadd(running_key(&kind), &job.id, Utc::now().timestamp() - 1).await.unwrap();
+
+        recover_expired(&mut con, &kind).await.unwrap();
+        let stored = get(&mut con, &job.id).await.unwrap().unwrap();
+        assert_eq!(stored.status, JobStatus::Retrying);
+        let running: i64 = con.zcard(running_key(&kind)).await.unwrap();
+        assert_eq!(running, 0);
+
+        let retaken = take_due(&mut con, &kind).await.unwrap().expect("recovered job should be due");
+        assert_eq!(retaken.id, job.id);
+        assert_eq!(retaken.attempts, 2);
+
+        cleanup(&mut con, &kind, &[&job.id], None).await;
+    }
+
+    #[actix_web::test]
+    #[ignore = "needs Redis (TEST_REDIS_URL)"]
+    async fn duplicate_enqueue_returns_existing_until_it_fails() {
+        let client = redis_client();
+        let mut con = client.get_async_connection().await.unwrap();
+        let kind = test_kind();
+        let key = format!("{}:wallet", kind);
+
+        let first = enqueue(&client, &kind, serde_json::json!({}), Some(key.clone())).await.unwrap();
+        assert!(matches!(first, Enqueued::Created(_)));
+        let second = enqueue(&client, &kind, serde_json::json!({}), Some(key.clone())).await.unwrap();
+        assert!(matches!(&second, Enqueued::Existing(job) if job.id == first.job().id));
+        let queued: i64 = con.zcard(queue_key(&kind)).await.unwrap();
+        assert_eq!(queued, 1);
+
+        // Un job fallido libera la clave
+        let taken = take_due(&mut con, &kind).await.unwrap().unwrap();
+        finish(&mut con, taken, Err(JobError::Fatal(anyhow::anyhow!("invalid payload")))).await.unwrap();
+        let third = enqueue(&client, &kind, serde_json::json!({}), Some(key.clone())).await.unwrap();
+        assert!(matches!(&third, Enqueued::Created(job) if job.id != first.job().id));
+
+        cleanup(&mut con, &kind, &[&first.job().id, &third.job().id], Some(&key)).await;
+    }
+}
@@ -1,35 +1,158 @@
 use actix_web::{App, HttpServer, web};
+use actix_web::dev::Service;
 use dotenv::dotenv;
-use ethers::signers::{LocalWallet, Signer};
-use ethers::signers::coins_bip39::English;
-use ethers::signers::MnemonicBuilder;
-use crate::farcaster::{Auth, CastClient};
+use crate::farcaster::CastClient;
+use crate::farcaster::lore::{LoreConfig, LoreScheduler};
 use crate::api::cdp::nfts::NftManager;
+use crate::api::nft_claim::{resume_interrupted_claims, NftClaimJob};
+use crate::api::proposals::ProposalManager;
+use crate::api::admin::{is_paused, Control};
+use crate::telegram::TelegramBot;
+use crate::discord::DiscordBot;
+use crate::jobs::JobRunner;
+use crate::twitter::posts::TwitterPostJob;
+use crate::workers::{Shutdown, Supervisor};
+use std::sync::Arc;
 use tokio::time::{sleep, Duration};
 mod api;
 mod openai_methods;
 mod twitter;
+mod discord;
+mod events;
+mod jobs;
 mod farcaster;
-use hex;
+mod metrics;
+mod retry_queue;
+mod social;
+mod telegram;
+mod telemetry;
+mod text;
+mod workers;
 use anyhow::Result;
 use std::env;
 use redis;
+use tracing::{debug, error, info, warn, Instrument};
 
-async fn start_farcaster_monitoring(cast_client: CastClient) {
-    println!("Starting Farcaster monitoring");
+// Tiempo máximo para que los workers terminen su lote en curso al apagar
+const WORKER_SHUTDOWN_GRACE: Duration = Duration::from_secs(60);
+
+async fn start_farcaster_monitoring(
+    cast_client: Arc<CastClient>,
+    redis_client: redis::Client,
+    mut shutdown: Shutdown,
+) -> Result<()> {
+    info!("starting Farcaster monitoring");
     
     loop {
-        match cast_client.fetch_and_process_mentions(892331, Some(10)).await {
-            Ok(_) => println!("✅ Procesamiento de menciones completado exitosamente"),
-            Err(e) => println!("❌ Error procesando menciones de Farcaster: {}", e),
+        if is_paused(&redis_client, Control::FarcasterWorker).await {
+            info!("Farcaster monitoring paused, skipping poll");
+            if shutdown.sleep(farcaster::cast::POLL_INTERVAL).await {
+                info!("Farcaster monitoring stopped");
+                return Ok(());
+            }
+            continue;
+        }
+
+        match cast_client.poll_mentions().await {
+            Ok(_) => info!("Farcaster mention poll completed"),
+            Err(e) => error!(error = %e, "Farcaster mention poll failed"),
         }
         
         // Aumentar el intervalo para evitar rate limiting
-        println!("😴 Esperando antes del próximo ciclo...");
-        sleep(Duration::from_secs(60*15)).await; // 15 minutos
+        info!("waiting for next Farcaster poll");
+        if shutdown.sleep(farcaster::cast::POLL_INTERVAL).await {
+            info!("Farcaster monitoring stopped");
+            return Ok(());
+        }
+    }
+}
+
+// Consume las menciones que llegan por /webhooks/farcaster; el poll de arriba
+// queda como reconciliación por si se pierde algún webhook
+async fn start_farcaster_webhook_consumer(
+    cast_client: Arc<CastClient>,
+    redis_client: redis::Client,
+    mut shutdown: Shutdown,
+) -> Result<()> {
+    info!("starting Farcaster webhook consumer");
+    let mut con = redis_client.get_async_connection().await?;
+    let inbox = &farcaster::webhook::MENTION_INBOX;
+    let retries = &farcaster::webhook::RETRY_QUEUE;
+    inbox.recover(&mut con).await?;
+
+    while !shutdown.is_shutdown() {
+        // Pausado, las menciones se quedan en la cola hasta reanudar
+        if is_paused(&redis_client, Control::FarcasterWorker).await || cast_client.replies_paused().await {
+            if shutdown.sleep(Duration::from_secs(30)).await {
+                break;
+            }
+            continue;
+        }
+
+        // Primero los reintentos que ya tocan
+        if let Some(retry) = retries.take_due::<farcaster::webhook::FailedCast>(&mut con).await? {
+            let attempts = retry.item.attempts + 1;
+            if handle_webhook_mention(&cast_client, retry.item.cast.clone()).await {
+                retries.complete(&mut con, &retry).await?;
+            } else {
+                let failed = farcaster::webhook::FailedCast { cast: retry.item.cast.clone(), attempts };
+                if retries.fail(&mut con, Some(&retry), &failed, attempts).await? {
+                    warn!(attempts, "Farcaster mention moved to dead letter");
+                }
+            }
+            continue;
+        }
+
+        // El timeout corto permite ver la señal de apagado entre esperas. Un fallo
+        // pasa a la cola de reintentos con backoff antes de confirmarlo en el inbox
+        if let Some(delivery) = inbox.next::<farcaster::cast::Cast>(&mut con, 5).await? {
+            if !handle_webhook_mention(&cast_client, delivery.item.clone()).await {
+                let failed = farcaster::webhook::FailedCast { cast: delivery.item.clone(), attempts: 1 };
+                retries.fail(&mut con, None, &failed, 1).await?;
+            }
+            inbox.ack(&mut con, &delivery).await?;
+        }
+    }
+
+    info!("Farcaster webhook consumer stopped");
+    Ok(())
+}
+
+// True si la mención quedó resuelta (contestada, descartada o sin más intentos)
+async fn handle_webhook_mention(cast_client: &CastClient, cast: farcaster::cast::Cast) -> bool {
+    match cast_client.handle_queued_mention(cast).await {
+        Ok(handled) => handled,
+        Err(e) => {
+            error!(error = %e, "failed to process queued Farcaster mention");
+            false
+        },
     }
 }
 
+// Calendario de lore del canal: publica lo aprobado y prepara los siguientes borradores
+async fn start_farcaster_lore(
+    scheduler: Arc<LoreScheduler>,
+    redis_client: redis::Client,
+    mut shutdown: Shutdown,
+) -> Result<()> {
+    info!("starting Farcaster lore scheduler");
+
+    while !shutdown.is_shutdown() {
+        if is_paused(&redis_client, Control::FarcasterWorker).await {
+            debug!("Farcaster paused, skipping lore tick");
+        } else if let Err(e) = scheduler.tick().await {
+            error!(error = %e, "Farcaster lore tick failed");
+        }
+
+        if shutdown.sleep(farcaster::lore::TICK_INTERVAL).await {
+            break;
+        }
+    }
+
+    info!("Farcaster lore scheduler stopped");
+    Ok(())
+}
+
 fn check_env_vars() {
     let required_vars = vec![
         "JWT_SECRET",
@@ -41,9 +164,9 @@ fn check_env_vars() {
 
     for var in required_vars {
         if std::env::var(var).is_err() {
-            println!("❌ Missing required environment variable: {}", var);
+            error!(var, "missing required environment variable");
         } else {
-            println!("✅ Found environment variable: {}", var);
+            info!(var, "found environment variable");
         }
     }
 }
@@ -51,9 +174,10 @@ fn check_env_vars() {
 #[actix_web::main]
 async fn main() -> Result<()> {
     dotenv::dotenv().ok();
+    telemetry::init();
     check_env_vars();
     
-    println!("\n🔍 Checking required environment variables:");
+    info!("checking required environment variables");
     let required_vars = [
         "APP_USER", "APP_PASSWORD", "JWT_SECRET", "OPENAI_API_KEY",
         "TWITTER_API_KEY", "TWITTER_API_SECRET",
@@ -72,111 +196,261 @@ async fn main() -> Result<()> {
     }
 
     if missing_vars.is_empty() {
-        println!("✅ All required variables are configured");
+        info!("all required variables are configured");
     } else {
-        println!("❌ Missing variables:");
-        for var in missing_vars {
-            println!("   • {}", var);
-        }
+        error!(missing = ?missing_vars, "missing required environment variables");
         return Err(anyhow::anyhow!("Missing required environment variables"));
     }
     
-    println!("\n🚀 Starting server at http://127.0.0.1:8080");
+    info!("starting server at http://127.0.0.1:8080");
     sleep(Duration::from_secs(2)).await;
 
-    println!("\n📱 Initializing X (Twitter) integration...");
+    info!("initializing X (Twitter) integration");
     let twitter_client = match twitter::client::TwitterClient::new().await {
         Ok(client) => {
-            println!("✅ X (Twitter) client initialized successfully");
+            info!("X (Twitter) client initialized");
             Some(client)
         },
         Err(e) => {
-            println!("⚠️ Error initializing X (Twitter) (server will continue without X): {}", e);
+            warn!(error = %e, "X (Twitter) unavailable, server will continue without it");
             None
         }
     };
 
+    let supervisor = web::Data::new(Supervisor::new());
+    let twitter_client_data = twitter_client.clone().map(web::Data::new);
+
     if let Some(client) = twitter_client {
-        println!("🔗 Starting X (Twitter) streams...");
-        let twitter_client_clone = client.clone();
-        tokio::spawn(async move {
-            if let Err(e) = twitter::stream::start_streams(twitter_client_clone).await {
-                println!("⚠️ Error in X (Twitter) streams: {}", e);
-            }
+        info!("starting X (Twitter) streams");
+        if twitter::dm::dms_enabled() {
+            let client = client.clone();
+            supervisor.spawn("twitter_dms", move |shutdown| {
+                twitter::dm::start_dm_polling(client.clone(), shutdown)
+            });
+        }
+        supervisor.spawn("twitter", move |shutdown| {
+            twitter::stream::start_streams(client.clone(), shutdown)
         });
     }
     
     sleep(Duration::from_secs(2)).await;
-    println!("\n🌟 Initializing Farcaster integration...");
+    info!("initializing Farcaster integration");
     let farcaster_client = match async {
-        let mnemonic = std::env::var("MNEMONIC")?;
-        println!("🔑 Generating wallet from seed phrase...");
-        let wallet: LocalWallet = MnemonicBuilder::<English>::default()
-            .phrase(mnemonic.as_str())
-            .derivation_path("m/44'/60'/0'/0/0")?
-            .build()?
-            .with_chain_id(1u64);
-
-        let wallet_address = wallet.address();
-        println!("👛 Wallet address: {}", wallet_address);
-        
-        println!("🔍 Verifying generated wallet...");
-        let address_bytes = wallet_address.as_bytes();
-        let address_suffix = &address_bytes[address_bytes.len()-4..];
-        println!("✨ Wallet verification: ...{}", hex::encode(address_suffix));
-        
-        println!("🔐 Starting Farcaster session...");
-        let session_token = Auth::handle_session(&wallet, Some(3600)).await?;
-        
-        println!("🔗 Creating Casts client...");
-        let cast_client = CastClient::new(session_token, &env::var("REDIS_URL")?)?;
+        let backend = farcaster::backend::from_env().await?;
+        let cast_client = CastClient::new(backend, &env::var("REDIS_URL")?)?;
         
         Ok::<_, anyhow::Error>(cast_client)
     }.await {
         Ok(client) => {
-            println!("✅ Farcaster client initialized successfully");
+            info!("Farcaster client initialized");
             Some(client)
         },
         Err(e) => {
-            println!("⚠️ Error initializing Farcaster (server will continue without Farcaster): {}", e);
+            warn!(error = %e, "Farcaster unavailable, server will continue without it");
             None
         }
     };
 
+    let mut cast_client_data = None;
     if let Some(client) = farcaster_client {
-        println!("📡 Starting Farcaster monitoring...");
-        let cast_client_clone = client;
-        tokio::spawn(async move {
-            start_farcaster_monitoring(cast_client_clone).await;
-        });
+        let cast_client = Arc::new(client);
+        cast_client_data = Some(web::Data::from(Arc::clone(&cast_client)));
+        let redis_client = redis::Client::open(env::var("REDIS_URL")?)?;
+        {
+            let cast_client = Arc::clone(&cast_client);
+            let redis_client = redis_client.clone();
+            supervisor.spawn("farcaster", move |shutdown| {
+                start_farcaster_monitoring(Arc::clone(&cast_client), redis_client.clone(), shutdown)
+            });
+        }
+
+        if env::var("FARCASTER_WEBHOOK_SECRET").map(|s| !s.is_empty()).unwrap_or(false) {
+            let cast_client = Arc::clone(&cast_client);
+            let redis_client = redis_client.clone();
+            supervisor.spawn("farcaster_webhooks", move |shutdown| {
+                start_farcaster_webhook_consumer(Arc::clone(&cast_client), redis_client.clone(), shutdown)
+            });
+        }
+
+        if let Some(config) = LoreConfig::from_env() {
+            let scheduler = Arc::new(LoreScheduler::new(Arc::clone(&cast_client), redis_client.clone(), config));
+            supervisor.spawn("farcaster_lore", move |shutdown| {
+                start_farcaster_lore(Arc::clone(&scheduler), redis_client.clone(), shutdown)
+            });
+        }
     }
 
     sleep(Duration::from_secs(2)).await;
-    println!("\n🎮 Initializing NFT Manager...");
+    info!("initializing NFT manager");
     let redis_client = web::Data::new(redis::Client::open(env::var("REDIS_URL")?)?);
+
+    {
+        let redis_client = redis_client.get_ref().clone();
+        supervisor.spawn("events", move |shutdown| {
+            events::delivery::start_dispatcher(redis_client.clone(), shutdown)
+        });
+    }
+
+    {
+        let redis_client = redis_client.get_ref().clone();
+        supervisor.spawn("frame_notifications", move |shutdown| {
+            farcaster::notifications::start_pending_sender(redis_client.clone(), shutdown)
+        });
+    }
     
     let nft_manager = match NftManager::new().await {
         Ok(manager) => {
-            println!("✅ NFT Manager initialized successfully");
+            info!("NFT manager initialized");
             web::Data::new(manager)
         },
         Err(e) => {
-            println!("❌ Error initializing NFT Manager: {}", e);
+            error!(error = %e, "failed to initialize NFT manager");
             return Err(anyhow::anyhow!("Failed to initialize NFT Manager"));
         }
     };
 
+    let mut job_runner = JobRunner::new(redis_client.get_ref().clone());
+    job_runner.register(Arc::new(NftClaimJob::new(redis_client.get_ref().clone(), nft_manager.clone().into_inner())));
+    if let Some(client) = &twitter_client_data {
+        job_runner.register(Arc::new(TwitterPostJob::new(redis_client.get_ref().clone(), client.get_ref().clone())));
+    }
+    let job_runner = Arc::new(job_runner);
+    for kind in job_runner.kinds() {
+        let job_runner = Arc::clone(&job_runner);
+        supervisor.spawn(&format!("jobs:{}", kind), move |shutdown| {
+            jobs::start_worker(Arc::clone(&job_runner), kind, shutdown)
+        });
+    }
+    match resume_interrupted_claims(redis_client.get_ref()).await {
+        Ok(0) => {},
+        Ok(resumed) => info!(resumed, "interrupted NFT claims queued again"),
+        Err(e) => warn!(error = %e, "error resuming interrupted NFT claims"),
+    }
+
+    info!("initializing proposal manager");
+    let proposal_manager = match ProposalManager::new().await {
+        Ok(manager) => {
+            info!("proposal manager initialized");
+            Some(web::Data::new(manager))
+        },
+        Err(e) => {
+            warn!(error = %e, "proposals contract unavailable, server will continue without it");
+            None
+        }
+    };
+
+    if env::var("TELEGRAM_BOT_TOKEN").map(|token| !token.is_empty()).unwrap_or(false) {
+        info!("initializing Telegram integration");
+        match telegram::client::TelegramClient::from_env().await {
+            Ok(client) => {
+                let bot = Arc::new(TelegramBot::new(client, redis_client.get_ref().clone(), Some(nft_manager.clone().into_inner())));
+                if let Err(e) = bot.configure().await {
+                    warn!(error = %e, "error configuring Telegram bot");
+                }
+                if telegram::bot::webhook_mode() {
+                    supervisor.spawn("telegram", move |shutdown| {
+                        telegram::bot::start_webhook_consumer(Arc::clone(&bot), shutdown)
+                    });
+                } else {
+                    supervisor.spawn("telegram", move |shutdown| {
+                        telegram::bot::start_polling(Arc::clone(&bot), shutdown)
+                    });
+                }
+            },
+            Err(e) => warn!(error = %e, "Telegram unavailable, server will continue without it"),
+        }
+    }
+
+    if env::var("DISCORD_BOT_TOKEN").map(|token| !token.is_empty()).unwrap_or(false) {
+        info!("initializing Discord integration");
+        match discord::client::DiscordClient::from_env().await {
+            Ok(client) => {
+                let proposals = proposal_manager.clone().map(|manager| manager.into_inner());
+                let bot = Arc::new(DiscordBot::new(client.clone(), redis_client.get_ref().clone(), proposals.clone()));
+                if let Err(e) = bot.register_commands().await {
+                    warn!(error = %e, "error registering Discord slash commands");
+                }
+                supervisor.spawn("discord", move |shutdown| {
+                    discord::bot::start_gateway(Arc::clone(&bot), shutdown)
+                });
+
+                let governance_channel = env::var("DISCORD_GOVERNANCE_CHANNEL_ID").ok().filter(|id| !id.is_empty());
+                match (governance_channel, proposals) {
+                    (Some(channel_id), Some(proposals)) => {
+                        let redis = redis_client.get_ref().clone();
+                        supervisor.spawn("discord_governance", move |shutdown| {
+                            discord::governance::start_governance_feed(
+                                client.clone(),
+                                Arc::clone(&proposals),
+                                redis.clone(),
+                                channel_id.clone(),
+                                shutdown,
+                            )
+                        });
+                    },
+                    (Some(_), None) => warn!("proposals contract unavailable, Discord governance feed disabled"),
+                    (None, _) => {},
+                }
+            },
+            Err(e) => warn!(error = %e, "Discord unavailable, server will continue without it"),
+        }
+    }
+
     sleep(Duration::from_secs(2)).await;
-    println!("\n🌐 Configuring web server...");
+    info!("configuring web server");
+    let app_supervisor = supervisor.clone();
     HttpServer::new(move || {
-        App::new()
+        let mut app = App::new()
+            .wrap_fn(|req, srv| {
+                let started = std::time::Instant::now();
+                let method = req.method().to_string();
+                let route = req.match_pattern().unwrap_or_else(|| "unmatched".to_string());
+                let request_id = req.headers()
+                    .get(telemetry::CORRELATION_HEADER)
+                    .and_then(|v| v.to_str().ok())
+                    .filter(|v| !v.is_empty() && v.len() <= 128)
+                    .map(String::from)
+                    .unwrap_or_else(telemetry::new_correlation_id);
+                let span = tracing::info_span!("http_request", %request_id, %method, %route);
+                let fut = telemetry::with_correlation_id(request_id.clone(), srv.call(req).instrument(span.clone()));
+                async move {
+                    let mut res = fut.await?;
+                    let status = res.status().as_u16();
+                    metrics::observe_http(&method, &route, status, started.elapsed());
+                    span.in_scope(|| info!(status, elapsed_ms = started.elapsed().as_millis() as u64, "request completed"));
+                    if let Ok(value) = actix_web::http::header::HeaderValue::from_str(&request_id) {
+                        res.headers_mut().insert(
+                            actix_web::http::header::HeaderName::from_static("x-request-id"),
+                            value,
+                        );
+                    }
+                    Ok(res)
+                }
+            })
             .app_data(redis_client.clone())
             .app_data(nft_manager.clone())
+            .app_data(app_supervisor.clone());
+        if let Some(proposal_manager) = proposal_manager.clone() {
+            app = app.app_data(proposal_manager);
+        }
+        if let Some(cast_client) = cast_client_data.clone() {
+            app = app.app_data(cast_client);
+        }
+        if let Some(twitter_client) = twitter_client_data.clone() {
+            app = app.app_data(twitter_client);
+        }
+        app
             .configure(api::handlers::config)
     })
     .bind("127.0.0.1:8080")?
+    // Al recibir SIGTERM/SIGINT se deja de aceptar conexiones y se espera a que
+    // terminen las peticiones en curso (mints incluidos)
+    .shutdown_timeout(120)
     .run()
     .await?;
+
+    info!("HTTP server stopped, waiting for background workers");
+    supervisor.shutdown(WORKER_SHUTDOWN_GRACE).await;
     
     Ok(())
 }
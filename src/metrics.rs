@@ -0,0 +1,173 @@
+use actix_web::{web, HttpResponse, Responder};
+use lazy_static::lazy_static;
+use prometheus::{
+    register_counter_vec, register_histogram_vec, register_int_counter_vec, register_int_gauge_vec,
+    CounterVec, Encoder, HistogramVec, IntCounterVec, IntGaugeVec, TextEncoder,
+};
+use redis::AsyncCommands;
+use std::time::Duration;
+use crate::api::proposals::Proposal;
+use tracing::warn;
+
+lazy_static! {
+    // API HTTP
+    pub static ref HTTP_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "qawakun_http_requests_total",
+        "HTTP requests handled, by method, route and status",
+        &["method", "route", "status"]
+    ).unwrap();
+    pub static ref HTTP_LATENCY: HistogramVec = register_histogram_vec!(
+        "qawakun_http_request_duration_seconds",
+        "HTTP request latency, by method and route",
+        &["method", "route"]
+    ).unwrap();
+
+    // LLM
+    pub static ref OPENAI_REQUESTS: IntCounterVec = register_int_counter_vec!(
+        "qawakun_openai_requests_total",
+        "Chat completion calls, by channel",
+        &["channel"]
+    ).unwrap();
+    pub static ref OPENAI_TOKENS: IntCounterVec = register_int_counter_vec!(
+        "qawakun_openai_tokens_total",
+        "Tokens reported by OpenAI usage, by channel and kind (prompt, completion)",
+        &["channel", "kind"]
+    ).unwrap();
+    pub static ref OPENAI_ERRORS: IntCounterVec = register_int_counter_vec!(
+        "qawakun_openai_errors_total",
+        "Failed chat completion calls, by channel",
+        &["channel"]
+    ).unwrap();
+
+    // Chain
+    pub static ref NFT_MINTS: IntCounterVec = register_int_counter_vec!(
+        "qawakun_nft_mints_total",
+        "NFT mint transactions, by outcome",
+        &["status"]
+    ).unwrap();
+    pub static ref NFT_TRANSFERS: IntCounterVec = register_int_counter_vec!(
+        "qawakun_nft_transfers_total",
+        "NFT transfer transactions, by outcome",
+        &["status"]
+    ).unwrap();
+    pub static ref GAS_USED: CounterVec = register_counter_vec!(
+        "qawakun_gas_used_total",
+        "Gas used by confirmed transactions, by operation",
+        &["operation"]
+    ).unwrap();
+    pub static ref PROPOSALS_BY_STATUS: IntGaugeVec = register_int_gauge_vec!(
+        "qawakun_proposals",
+        "Proposals stored in Redis, by status",
+        &["status"]
+    ).unwrap();
+
+    // Workers sociales
+    pub static ref SOCIAL_MENTIONS: IntCounterVec = register_int_counter_vec!(
+        "qawakun_social_mentions_total",
+        "Mentions seen by the social workers, by channel and outcome (processed, spam, failed, paused, dead_letter, over_budget)",
+        &["channel", "outcome"]
+    ).unwrap();
+
+    pub static ref FRAME_NOTIFICATIONS: IntCounterVec = register_int_counter_vec!(
+        "qawakun_frame_notifications_total",
+        "Frame notifications, by outcome (sent, queued, rate_limited, invalid_token, failed, no_token)",
+        &["outcome"]
+    ).unwrap();
+
+    pub static ref WEBHOOK_DELIVERIES: IntCounterVec = register_int_counter_vec!(
+        "qawakun_webhook_deliveries_total",
+        "Outbound webhook deliveries, by outcome (delivered, retry, dead_letter)",
+        &["outcome"]
+    ).unwrap();
+
+    pub static ref JOBS: IntCounterVec = register_int_counter_vec!(
+        "qawakun_jobs_total",
+        "Background job transitions, by kind and status (queued, succeeded, retrying, failed)",
+        &["kind", "status"]
+    ).unwrap();
+
+    pub static ref REDIS_ERRORS: IntCounterVec = register_int_counter_vec!(
+        "qawakun_redis_errors_total",
+        "Redis errors, by component",
+        &["component"]
+    ).unwrap();
+}
+
+pub fn observe_http(method: &str, route: &str, status: u16, elapsed: Duration) {
+    HTTP_REQUESTS
+        .with_label_values(&[method, route, &status.to_string()])
+        .inc();
+    HTTP_LATENCY
+        .with_label_values(&[method, route])
+        .observe(elapsed.as_secs_f64());
+}
+
+pub fn observe_openai_usage(channel: &str, response: &serde_json::Value) {
+    OPENAI_REQUESTS.with_label_values(&[channel]).inc();
+    if let Some(prompt) = response["usage"]["prompt_tokens"].as_u64() {
+        OPENAI_TOKENS.with_label_values(&[channel, "prompt"]).inc_by(prompt);
+    }
+    if let Some(completion) = response["usage"]["completion_tokens"].as_u64() {
+        OPENAI_TOKENS.with_label_values(&[channel, "completion"]).inc_by(completion);
+    }
+    if response.get("error").is_some() {
+        OPENAI_ERRORS.with_label_values(&[channel]).inc();
+    }
+}
+
+pub fn observe_gas(operation: &str, gas_used: Option<ethers::types::U256>) {
+    // Un valor fuera de u128 no es gas real; se descarta en lugar de hacer panic
+    if let Some(Ok(gas)) = gas_used.map(u128::try_from) {
+        GAS_USED.with_label_values(&[operation]).inc_by(gas as f64);
+    }
+}
+
+pub fn mention(channel: &str, outcome: &str) {
+    SOCIAL_MENTIONS.with_label_values(&[channel, outcome]).inc();
+}
+
+pub fn frame_notification(outcome: &str) {
+    FRAME_NOTIFICATIONS.with_label_values(&[outcome]).inc();
+}
+
+pub fn webhook_delivery(outcome: &str) {
+    WEBHOOK_DELIVERIES.with_label_values(&[outcome]).inc();
+}
+
+pub fn job(kind: &str, status: &str) {
+    JOBS.with_label_values(&[kind, status]).inc();
+}
+
+pub fn redis_error(component: &str) {
+    REDIS_ERRORS.with_label_values(&[component]).inc();
+}
+
+async fn refresh_proposal_gauges(redis_client: &redis::Client) -> redis::RedisResult<()> {
+    let mut con = redis_client.get_async_connection().await?;
+    let proposals: Vec<String> = con.hvals("proposals").await?;
+
+    PROPOSALS_BY_STATUS.reset();
+    for proposal in proposals.iter().filter_map(|p| serde_json::from_str::<Proposal>(p).ok()) {
+        PROPOSALS_BY_STATUS
+            .with_label_values(&[&proposal.status.to_string()])
+            .inc();
+    }
+    Ok(())
+}
+
+pub async fn metrics_endpoint(redis_client: web::Data<redis::Client>) -> impl Responder {
+    if let Err(e) = refresh_proposal_gauges(&redis_client).await {
+        warn!(error = %e, "error refreshing proposal metrics");
+        redis_error("metrics");
+    }
+
+    let mut buffer = Vec::new();
+    let encoder = TextEncoder::new();
+    if let Err(e) = encoder.encode(&prometheus::gather(), &mut buffer) {
+        return HttpResponse::InternalServerError().body(format!("Error encoding metrics: {}", e));
+    }
+
+    HttpResponse::Ok()
+        .content_type(encoder.format_type())
+        .body(buffer)
+}
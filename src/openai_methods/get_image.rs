@@ -1,6 +1,8 @@
 use reqwest::header;
 use serde_json::json;
 use anyhow::Result;
+use tracing::{debug, info};
+use crate::telemetry;
 
 pub async fn generate_image(prompt: &str) -> Result<String> {
     let client = reqwest::Client::new();
@@ -30,10 +32,9 @@ pub async fn generate_image(prompt: &str) -> Result<String> {
         "response_format": "b64_json"
     });
 
-    println!("🎨 Enviando solicitud a DALL-E 2 con prompt: {}", modified_prompt);
+    info!(prompt_chars = modified_prompt.len(), "sending DALL-E 2 request");
     
-    let response = client
-        .post(url)
+    let response = telemetry::propagate(client.post(url))
         .headers(headers)
         .json(&body)
         .send()
@@ -47,14 +48,14 @@ pub async fn generate_image(prompt: &str) -> Result<String> {
     let response_data: serde_json::Value = response.json().await?;
     
     if let Some(revised_prompt) = response_data["data"][0]["revised_prompt"].as_str() {
-        println!("📝 Prompt revisado por DALL-E: {}", revised_prompt);
+        debug!(revised_prompt, "prompt revised by DALL-E");
     }
     
     let base64_image = response_data["data"][0]["b64_json"]
         .as_str()
         .ok_or_else(|| anyhow::anyhow!("No se encontró la imagen en la respuesta"))?;
 
-    println!("✅ Imagen generada exitosamente");
+    info!("image generated");
     
     Ok(base64_image.to_string())
 }
\ No newline at end of file
@@ -1,7 +1,9 @@
-use redis::AsyncCommands;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use std::error::Error;
+use crate::metrics;
+use crate::telemetry;
+use tracing::{debug, error, info};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ChatMessage {
@@ -24,13 +26,14 @@ fn create_chat_completions_body(messages: Vec<ChatMessage>) -> ChatCompletionsBo
 
 pub async fn get_chat_completion(
     api_key: &str, 
+    channel: &str,
     messages: Vec<ChatMessage>
 ) -> Result<serde_json::Value, Box<dyn Error + Send + Sync>> {
     let client = Client::new();
     let body = create_chat_completions_body(messages);
 
-    println!("📤 OpenAI request");
-    let response = match client.post("https://api.openai.com/v1/chat/completions")
+    debug!(channel, messages = body.messages.len(), "OpenAI request");
+    let response = match telemetry::propagate(client.post("https://api.openai.com/v1/chat/completions"))
         .header("Content-Type", "application/json")
         .header("Authorization", format!("Bearer {}", api_key))
         .json(&body)
@@ -38,74 +41,25 @@ pub async fn get_chat_completion(
         .await 
     {
         Ok(resp) => {
-            println!("📥 OpenAI status: {}", resp.status());
+            info!(channel, status = resp.status().as_u16(), "OpenAI response");
             resp
         },
         Err(e) => {
-            println!("❌ OpenAI error: {}", e);
+            error!(channel, error = %e, "OpenAI request failed");
+            metrics::OPENAI_ERRORS.with_label_values(&[channel]).inc();
             return Err(Box::new(e));
         }
     };
 
     match response.json().await {
-        Ok(completion) => Ok(completion),
+        Ok(completion) => {
+            metrics::observe_openai_usage(channel, &completion);
+            Ok(completion)
+        },
         Err(e) => {
-            println!("❌ JSON error: {}", e);
+            error!(channel, error = %e, "invalid OpenAI response");
+            metrics::OPENAI_ERRORS.with_label_values(&[channel]).inc();
             Err(Box::new(e))
         }
     }
 }
-
-pub async fn handle_conversation(
-    redis_client: &redis::Client,
-    api_key: &str,
-    user_author: &str,
-    system_content: &str,
-    user_content: &str
-) -> Result<serde_json::Value, Box<dyn Error + Send + Sync>> {
-    let mut con = match redis_client.get_async_connection().await {
-        Ok(conn) => conn,
-        Err(e) => {
-            println!("❌ Redis connection error: {}", e);
-            return Err(Box::new(e));
-        }
-    };
-
-    let conversation_key = format!("conversation:{}", user_author);
-    let mut messages: Vec<ChatMessage> = match con.get::<_, String>(&conversation_key).await {
-        Ok(stored_json) => {
-            println!("📖 Existing chat");
-            serde_json::from_str(&stored_json)?
-        },
-        Err(_) => {
-            println!("🆕 New chat");
-            vec![ChatMessage {
-                role: "system".to_string(),
-                content: system_content.to_string(),
-            }]
-        }
-    };
-
-    messages.push(ChatMessage {
-        role: "user".to_string(),
-        content: user_content.to_string(),
-    });
-
-    let response = get_chat_completion(api_key, messages.clone()).await?;
-
-    if let Some(message_content) = response["choices"][0]["message"]["content"].as_str() {
-        messages.push(ChatMessage {
-            role: "assistant".to_string(),
-            content: message_content.to_string(),
-        });
-        
-        if let Ok(json) = serde_json::to_string(&messages) {
-            match con.set::<_, _, ()>(conversation_key, json).await {
-                Ok(_) => println!("💾 Chat saved"),
-                Err(e) => println!("⚠️ Error saving chat: {}", e)
-            }
-        }
-    }
-
-    Ok(response)
-}
\ No newline at end of file
@@ -0,0 +1,163 @@
+use chrono::Utc;
+use redis::AsyncCommands;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+// Cola de reintentos con backoff y dead letter, compartida por las menciones de X y
+// las entregas de webhooks. La cola es un sorted set con score = momento del
+// siguiente intento; al sacar un elemento pasa a `{cola}:running` con un lease, así
+// que si el proceso cae a mitad de intento vuelve a la cola cuando el lease vence
+pub struct RetryQueue {
+    queue: &'static str,
+    dead_letter: &'static str,
+    max_attempts: u32,
+    // Espera antes del primer reintento; se duplica con cada intento fallido
+    retry_base_secs: i64,
+    lease_secs: i64,
+}
+
+// Elemento sacado de la cola; `raw` es el payload tal cual para poder soltar el lease
+pub struct Leased<T> {
+    pub item: T,
+    raw: String,
+}
+
+const DEAD_LETTER_MAX: isize = 1000;
+
+// Devuelve a la cola los leases vencidos y mueve el siguiente elemento que ya toca a
+// running, todo de una vez para que dos procesos no se lleven el mismo
+const TAKE_DUE_SCRIPT: &str = r"
+local now = tonumber(ARGV[1])
+for _, item in ipairs(redis.call('ZRANGEBYSCORE', KEYS[2], '-inf', now)) do
+    redis.call('ZREM', KEYS[2], item)
+    redis.call('ZADD', KEYS[1], now, item)
+end
+local due = redis.call('ZRANGEBYSCORE', KEYS[1], '-inf', now, 'LIMIT', 0, 1)
+if #due == 0 then
+    return false
+end
+redis.call('ZREM', KEYS[1], due[1])
+redis.call('ZADD', KEYS[2], now + tonumber(ARGV[2]), due[1])
+return due[1]
+";
+
+impl RetryQueue {
+    pub const fn new(
+        queue: &'static str,
+        dead_letter: &'static str,
+        max_attempts: u32,
+        retry_base_secs: i64,
+        lease_secs: i64,
+    ) -> Self {
+        Self { queue, dead_letter, max_attempts, retry_base_secs, lease_secs }
+    }
+
+    fn running_key(&self) -> String {
+        format!("{}:running", self.queue)
+    }
+
+    // Encola para intentarlo ya
+    pub async fn push<T: Serialize>(&self, con: &mut redis::aio::Connection, item: &T) -> redis::RedisResult<()> {
+        let payload = serde_json::to_string(item).unwrap_or_default();
+        con.zadd(self.queue, payload, Utc::now().timestamp()).await
+    }
+
+    // Encola para intentarlo dentro de `delay_secs`
+    pub async fn schedule<T: Serialize>(&self, con: &mut redis::aio::Connection, item: &T, delay_secs: i64) -> redis::RedisResult<()> {
+        let payload = serde_json::to_string(item).unwrap_or_default();
+        con.zadd(self.queue, payload, Utc::now().timestamp() + delay_secs).await
+    }
+
+    // Siguiente elemento que ya toca, con su lease tomado. Un payload que no se puede
+    // leer se descarta
+    pub async fn take_due<T: DeserializeOwned>(&self, con: &mut redis::aio::Connection) -> redis::RedisResult<Option<Leased<T>>> {
+        loop {
+            let raw: Option<String> = redis::Script::new(TAKE_DUE_SCRIPT)
+                .key(self.queue)
+                .key(self.running_key())
+                .arg(Utc::now().timestamp())
+                .arg(self.lease_secs)
+                .invoke_async(con)
+                .await?;
+            let raw = match raw {
+                Some(raw) => raw,
+                None => return Ok(None),
+            };
+
+            match serde_json::from_str(&raw) {
+                Ok(item) => return Ok(Some(Leased { item, raw })),
+                Err(_) => {
+                    let _: () = con.zrem(self.running_key(), &raw).await?;
+                },
+            }
+        }
+    }
+
+    // El intento salió bien
+    pub async fn complete<T>(&self, con: &mut redis::aio::Connection, leased: &Leased<T>) -> redis::RedisResult<()> {
+        con.zrem(self.running_key(), &leased.raw).await
+    }
+
+    // Devuelve el elemento a la cola dentro de `delay_secs` sin contar un intento
+    pub async fn postpone<T>(&self, con: &mut redis::aio::Connection, leased: &Leased<T>, delay_secs: i64) -> redis::RedisResult<()> {
+        redis::pipe()
+            .atomic()
+            .zrem(self.running_key(), &leased.raw)
+            .ignore()
+            .zadd(self.queue, &leased.raw, Utc::now().timestamp() + delay_secs)
+            .ignore()
+            .query_async(con)
+            .await
+    }
+
+    // Reprograma `item` tras su intento número `attempts` o, agotados los intentos,
+    // lo manda a dead letter; suelta el lease de `leased` si venía de la cola.
+    // Devuelve true en el caso de dead letter
+    pub async fn fail<T: Serialize>(
+        &self,
+        con: &mut redis::aio::Connection,
+        leased: Option<&Leased<T>>,
+        item: &T,
+        attempts: u32,
+    ) -> redis::RedisResult<bool> {
+        let payload = serde_json::to_string(item).unwrap_or_default();
+        let dead = attempts >= self.max_attempts;
+
+        let mut pipe = redis::pipe();
+        pipe.atomic();
+        if let Some(leased) = leased {
+            pipe.zrem(self.running_key(), &leased.raw).ignore();
+        }
+        if dead {
+            pipe.lpush(self.dead_letter, &payload).ignore()
+                .ltrim(self.dead_letter, 0, DEAD_LETTER_MAX - 1).ignore();
+        } else {
+            let next_attempt = Utc::now().timestamp() + self.retry_base_secs * 2i64.pow(attempts.max(1) - 1);
+            pipe.zadd(self.queue, &payload, next_attempt).ignore();
+        }
+        let _: () = pipe.query_async(con).await?;
+        Ok(dead)
+    }
+
+    pub async fn dead_letters<T: DeserializeOwned>(&self, con: &mut redis::aio::Connection) -> redis::RedisResult<Vec<T>> {
+        let stored: Vec<String> = con.lrange(self.dead_letter, 0, -1).await?;
+        Ok(stored.iter().filter_map(|s| serde_json::from_str(s).ok()).collect())
+    }
+
+    // Devuelve todo el dead letter a la cola; `reset` pone los intentos a cero
+    pub async fn requeue_dead_letters<T: Serialize + DeserializeOwned>(
+        &self,
+        con: &mut redis::aio::Connection,
+        reset: impl Fn(&mut T),
+    ) -> redis::RedisResult<usize> {
+        let mut requeued = 0;
+        while let Some(stored) = con.rpop::<_, Option<String>>(self.dead_letter, None).await? {
+            if let Ok(mut item) = serde_json::from_str::<T>(&stored) {
+                reset(&mut item);
+                self.push(con, &item).await?;
+                requeued += 1;
+            }
+        }
+        Ok(requeued)
+    }
+}
@@ -0,0 +1,121 @@
+use anyhow::Result;
+use redis::AsyncCommands;
+use serde::de::DeserializeOwned;
+use tracing::{error, warn};
+
+// Cola fiable para lo que llega por webhook. El consumidor mueve cada entrada a
+// `{cola}:processing` con BLMOVE y solo la quita al confirmarla, así que una caída
+// a mitad de proceso no la pierde: al arrancar se devuelve a la cola
+pub struct Inbox {
+    queue: &'static str,
+    seen_prefix: &'static str,
+    seen_ttl_secs: u64,
+}
+
+// Entrada sacada de la cola; `raw` es el payload tal cual para poder confirmarla
+pub struct Delivery<T> {
+    pub item: T,
+    raw: String,
+}
+
+impl Inbox {
+    pub const fn new(queue: &'static str, seen_prefix: &'static str, seen_ttl_secs: u64) -> Self {
+        Self { queue, seen_prefix, seen_ttl_secs }
+    }
+
+    fn processing_key(&self) -> String {
+        format!("{}:processing", self.queue)
+    }
+
+    // Encola `payload` salvo que `id` ya se haya visto. La marca de visto se pone
+    // después de encolar: si el LPUSH falla el proveedor reintenta y se vuelve a
+    // aceptar. Un duplicado que se cuele por carrera lo frena la marca del pipeline
+    pub async fn push(&self, con: &mut redis::aio::Connection, id: &str, payload: &str) -> Result<bool> {
+        let seen_key = format!("{}{}", self.seen_prefix, id);
+        if con.exists(&seen_key).await? {
+            return Ok(false);
+        }
+
+        let _: () = con.lpush(self.queue, payload).await?;
+        let _: () = redis::cmd("SET")
+            .arg(&seen_key)
+            .arg(1)
+            .arg("EX")
+            .arg(self.seen_ttl_secs)
+            .query_async(con)
+            .await?;
+        Ok(true)
+    }
+
+    // Siguiente entrada, esperando como mucho `timeout_secs`. Un payload que no se
+    // puede leer se descarta para que no bloquee la cola
+    pub async fn next<T: DeserializeOwned>(
+        &self,
+        con: &mut redis::aio::Connection,
+        timeout_secs: usize,
+    ) -> Result<Option<Delivery<T>>> {
+        let raw: Option<String> = redis::cmd("BLMOVE")
+            .arg(self.queue)
+            .arg(self.processing_key())
+            .arg("RIGHT")
+            .arg("LEFT")
+            .arg(timeout_secs)
+            .query_async(con)
+            .await?;
+        let raw = match raw {
+            Some(raw) => raw,
+            None => return Ok(None),
+        };
+
+        match serde_json::from_str(&raw) {
+            Ok(item) => Ok(Some(Delivery { item, raw })),
+            Err(e) => {
+                error!(queue = self.queue, error = %e, "dropping unreadable queue entry");
+                let _: () = con.lrem(self.processing_key(), 1, &raw).await?;
+                Ok(None)
+            },
+        }
+    }
+
+    // La entrada ya se procesó
+    pub async fn ack<T>(&self, con: &mut redis::aio::Connection, delivery: &Delivery<T>) -> Result<()> {
+        let _: () = con.lrem(self.processing_key(), 1, &delivery.raw).await?;
+        Ok(())
+    }
+
+    // Devuelve la entrada al final de la cola para reintentarla más tarde
+    pub async fn requeue<T>(&self, con: &mut redis::aio::Connection, delivery: &Delivery<T>) -> Result<()> {
+        let _: () = redis::pipe()
+            .atomic()
+            .lrem(self.processing_key(), 1, &delivery.raw)
+            .ignore()
+            .lpush(self.queue, &delivery.raw)
+            .ignore()
+            .query_async(con)
+            .await?;
+        Ok(())
+    }
+
+    // Al arrancar: lo que quedó en proceso de una ejecución anterior vuelve a la
+    // cabeza de la cola
+    pub async fn recover(&self, con: &mut redis::aio::Connection) -> Result<usize> {
+        let mut recovered = 0;
+        loop {
+            let moved: Option<String> = redis::cmd("LMOVE")
+                .arg(self.processing_key())
+                .arg(self.queue)
+                .arg("RIGHT")
+                .arg("RIGHT")
+                .query_async(con)
+                .await?;
+            if moved.is_none() {
+                break;
+            }
+            recovered += 1;
+        }
+        if recovered > 0 {
+            warn!(queue = self.queue, recovered, "requeued entries left in processing");
+        }
+        Ok(recovered)
+    }
+}
@@ -0,0 +1,83 @@
+use anyhow::Result;
+use async_trait::async_trait;
+
+pub mod inbox;
+pub mod pipeline;
+pub mod wallet;
+
+pub use pipeline::{AgentPipeline, Outcome};
+
+// Autor de un mensaje en su red: id estable y @handle si se conoce
+#[derive(Debug, Clone)]
+pub struct Author {
+    pub id: String,
+    pub handle: Option<String>,
+}
+
+impl Author {
+    pub fn display(&self) -> String {
+        match &self.handle {
+            Some(handle) if !handle.is_empty() => handle.clone(),
+            _ => format!("id:{}", self.id),
+        }
+    }
+}
+
+// Mensaje entrante normalizado; `event` conserva el original de la red para
+// poder contestarlo
+#[derive(Debug, Clone)]
+pub struct InboundMessage<E> {
+    pub id: String,
+    pub author: Author,
+    pub text: String,
+    pub event: E,
+}
+
+// Turno anterior de un hilo público
+#[derive(Debug, Clone)]
+pub struct ThreadEntry {
+    pub author: Author,
+    pub from_bot: bool,
+    pub text: String,
+}
+
+// Mensajes leídos de la red, del más antiguo al más reciente, y cursor a guardar
+// con `commit` cuando el lote esté procesado
+pub struct Batch<E> {
+    pub messages: Vec<InboundMessage<E>>,
+    pub cursor: Option<String>,
+}
+
+// Una red social por la que habla el agente. El resto del flujo (moderación,
+// contexto, LLM, historial) lo hace AgentPipeline
+#[async_trait]
+pub trait SocialChannel: Send + Sync {
+    type Event: Send + Sync;
+
+    // Nombre del canal en métricas, logs y claves de Redis
+    fn name(&self) -> &'static str;
+
+    // Instrucciones propias del canal que se añaden al contexto narrativo
+    fn channel_prompt(&self) -> String;
+
+    // Los canales públicos pasan el filtro de spam y usan el hilo como contexto;
+    // los privados guardan un historial por usuario
+    fn is_public(&self) -> bool {
+        true
+    }
+
+    fn history_key(&self, author: &Author) -> String {
+        format!("conversation:{}:{}", self.name(), author.id)
+    }
+
+    async fn fetch(&self) -> Result<Batch<Self::Event>>;
+
+    async fn commit(&self, cursor: &str) -> Result<()>;
+
+    // Turnos anteriores del hilo del mensaje, sin el propio mensaje
+    async fn thread(&self, _message: &InboundMessage<Self::Event>) -> Result<Vec<ThreadEntry>> {
+        Ok(Vec::new())
+    }
+
+    async fn send_reply(&self, message: &InboundMessage<Self::Event>, text: &str) -> Result<()>;
+}
@@ -0,0 +1,229 @@
+use anyhow::Result;
+use lazy_static::lazy_static;
+use redis::AsyncCommands;
+use regex::Regex;
+use std::collections::HashSet;
+use std::env;
+use crate::metrics;
+use crate::openai_methods::get_text::{get_chat_completion, ChatMessage};
+use crate::telemetry;
+use tracing::{debug, info, warn};
+use super::{InboundMessage, SocialChannel};
+
+// Marca por mensaje: {canal}:handled:{id}
+const HANDLED_TTL_SECS: u64 = 30 * 24 * 60 * 60;
+// Intentos por mensaje antes de dejarlo marcado sin contestar: {canal}:attempts:{id}
+const MAX_ATTEMPTS: u32 = 3;
+const ATTEMPTS_TTL_SECS: usize = 24 * 60 * 60;
+// Turnos que se conservan del historial de cada autor, sin contar el system
+const MAX_HISTORY_MESSAGES: usize = 40;
+const MAX_MENTIONS: usize = 3;
+
+lazy_static! {
+    static ref SPAM_PATTERNS: Vec<Regex> = vec![
+        Regex::new(r"(?i)crypto").unwrap(),
+        Regex::new(r"(?i)pump").unwrap(),
+        Regex::new(r"(?i)airdrop").unwrap(),
+        Regex::new(r"(?i)blast").unwrap(),
+        Regex::new(r"(?i)token").unwrap(),
+        Regex::new(r"https?://").unwrap(),
+        Regex::new(r"(?i)drop").unwrap(),
+    ];
+    // Etiqueta de hablante que el modelo a veces copia del formato "@usuario: texto"
+    static ref SPEAKER_LABEL: Regex = Regex::new(r"^(?i)(qawakun|@\w+)\s*:\s*").unwrap();
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Replied(String),
+    Spam,
+}
+
+// Contexto narrativo actualizado por /context, o context.md si no hay
+pub async fn narrative_context(con: &mut redis::aio::Connection) -> Result<String> {
+    let stored: Option<String> = con.get("context-text").await?;
+    let content = match stored {
+        Some(content) => content,
+        None => std::fs::read_to_string("context.md")
+            .map_err(|e| anyhow::anyhow!("Failed to read context.md: {}", e))?,
+    };
+    Ok(format!("You are Qawakun, a narrative guide in this interactive experience.\n{}", content))
+}
+
+pub fn is_spam(text: &str) -> bool {
+    if text.matches('@').count() > MAX_MENTIONS {
+        debug!("too many mentions in message");
+        return true;
+    }
+    SPAM_PATTERNS.iter().any(|pattern| pattern.is_match(text))
+}
+
+fn clean_reply(text: &str) -> String {
+    let text = text.trim().trim_matches('"').trim();
+    SPEAKER_LABEL.replace(text, "").trim().to_string()
+}
+
+// Flujo común a todos los canales: moderación, identidad, contexto, LLM,
+// limpieza de la respuesta, envío y persistencia del historial
+#[derive(Clone)]
+pub struct AgentPipeline {
+    redis_client: redis::Client,
+}
+
+impl AgentPipeline {
+    pub fn new(redis_client: redis::Client) -> Self {
+        Self { redis_client }
+    }
+
+    // Falso si el mensaje ya fue tomado antes, aunque sea por otro proceso
+    pub async fn claim<C: SocialChannel>(&self, channel: &C, id: &str) -> Result<bool> {
+        let mut con = self.redis_client.get_async_connection().await?;
+        let claimed: Option<String> = redis::cmd("SET")
+            .arg(format!("{}:handled:{}", channel.name(), id))
+            .arg(1)
+            .arg("NX")
+            .arg("EX")
+            .arg(HANDLED_TTL_SECS)
+            .query_async(&mut con)
+            .await?;
+        Ok(claimed.is_some())
+    }
+
+    // Libera la marca para que el mensaje se vuelva a tomar
+    pub async fn unclaim<C: SocialChannel>(&self, channel: &C, id: &str) -> Result<()> {
+        let mut con = self.redis_client.get_async_connection().await?;
+        let _: () = con.del(format!("{}:handled:{}", channel.name(), id)).await?;
+        Ok(())
+    }
+
+    // Tras un fallo al contestar: libera la marca para reintentarlo y devuelve true,
+    // o false si ya agotó los intentos y se queda marcado
+    pub async fn release<C: SocialChannel>(&self, channel: &C, id: &str) -> Result<bool> {
+        let mut con = self.redis_client.get_async_connection().await?;
+        let attempts_key = format!("{}:attempts:{}", channel.name(), id);
+        let attempts: u32 = con.incr(&attempts_key, 1).await?;
+        let _: () = con.expire(&attempts_key, ATTEMPTS_TTL_SECS).await?;
+        if attempts >= MAX_ATTEMPTS {
+            warn!(channel = channel.name(), id, attempts, "giving up on message");
+            metrics::mention(channel.name(), "dead_letter");
+            return Ok(false);
+        }
+        self.unclaim(channel, id).await?;
+        Ok(true)
+    }
+
+    // Cada mensaje lleva su propio correlation ID (o el de la petición que lo trajo)
+    pub async fn respond<C: SocialChannel>(&self, channel: &C, message: &InboundMessage<C::Event>) -> Result<Outcome> {
+        telemetry::traced("message", None, self.process(channel, message)).await
+    }
+
+    async fn process<C: SocialChannel>(&self, channel: &C, message: &InboundMessage<C::Event>) -> Result<Outcome> {
+        let name = channel.name();
+        if channel.is_public() && is_spam(&message.text) {
+            info!(channel = name, id = %message.id, "spam detected, ignoring message");
+            metrics::mention(name, "spam");
+            return Ok(Outcome::Spam);
+        }
+
+        let identity = format!("{}:{}", name, message.author.id);
+        info!(channel = name, id = %message.id, identity = %identity, chars = message.text.len(), "processing message");
+
+        let mut con = self.redis_client.get_async_connection().await?;
+        let system = system_message(channel, &mut con).await?;
+
+        // El historial por autor (p. ej. el avance de su historia) se guarda en todos
+        // los canales; el system se renueva en cada turno para que los cambios de
+        // contexto apliquen
+        let history_key = channel.history_key(&message.author);
+        let mut history = load_history(&mut con, &history_key).await?;
+        debug!(channel = name, identity = %identity, turns = history.len(), "conversation history loaded");
+
+        let mut messages = vec![system.clone()];
+        if channel.is_public() {
+            let thread = channel.thread(message).await?;
+            debug!(channel = name, id = %message.id, thread_messages = thread.len(), "thread context loaded");
+
+            // Lo que ya está en el hilo no se repite desde el historial
+            let in_thread: HashSet<&str> = thread.iter().map(|entry| entry.text.as_str()).collect();
+            messages.extend(history.iter().filter(|m| !in_thread.contains(m.content.as_str())).cloned());
+            for entry in &thread {
+                messages.push(if entry.from_bot {
+                    ChatMessage { role: "assistant".to_string(), content: entry.text.clone() }
+                } else {
+                    ChatMessage { role: "user".to_string(), content: format!("@{}: {}", entry.author.display(), entry.text) }
+                });
+            }
+            messages.push(ChatMessage {
+                role: "user".to_string(),
+                content: format!("@{}: {}", message.author.display(), message.text),
+            });
+        } else {
+            messages.extend(history.iter().cloned());
+            messages.push(ChatMessage { role: "user".to_string(), content: message.text.clone() });
+        }
+
+        let api_key = env::var("OPENAI_API_KEY")?;
+        let response = get_chat_completion(&api_key, name, messages)
+            .await
+            .map_err(|e| anyhow::anyhow!("OpenAI API error: {}", e))?;
+        let reply = response["choices"][0]["message"]["content"]
+            .as_str()
+            .map(clean_reply)
+            .filter(|reply| !reply.is_empty())
+            .ok_or_else(|| anyhow::anyhow!("No response content in OpenAI result"))?;
+
+        channel.send_reply(message, &reply).await?;
+        metrics::mention(name, "processed");
+
+        save_history(&mut con, name, &history_key, &system, &mut history, &message.text, &reply).await?;
+        Ok(Outcome::Replied(reply))
+    }
+
+    // Guarda en el historial del autor una respuesta que el canal terminó de enviar
+    // fuera de `respond` (p. ej. al reanudar un envío en varias partes)
+    pub async fn remember<C: SocialChannel>(&self, channel: &C, message: &InboundMessage<C::Event>, reply: &str) -> Result<()> {
+        let mut con = self.redis_client.get_async_connection().await?;
+        let system = system_message(channel, &mut con).await?;
+        let history_key = channel.history_key(&message.author);
+        let mut history = load_history(&mut con, &history_key).await?;
+        save_history(&mut con, channel.name(), &history_key, &system, &mut history, &message.text, reply).await
+    }
+}
+
+async fn system_message<C: SocialChannel>(channel: &C, con: &mut redis::aio::Connection) -> Result<ChatMessage> {
+    Ok(ChatMessage {
+        role: "system".to_string(),
+        content: format!("{}\n\n{}", narrative_context(con).await?, channel.channel_prompt()),
+    })
+}
+
+async fn load_history(con: &mut redis::aio::Connection, key: &str) -> Result<Vec<ChatMessage>> {
+    let stored: Option<String> = con.get(key).await?;
+    let mut history: Vec<ChatMessage> = stored
+        .and_then(|stored| serde_json::from_str(&stored).ok())
+        .unwrap_or_default();
+    history.retain(|m| m.role != "system");
+    Ok(history)
+}
+
+// Añade el turno y guarda los últimos MAX_HISTORY_MESSAGES detrás del system. Un
+// fallo al guardar solo se registra: la respuesta ya salió
+async fn save_history(
+    con: &mut redis::aio::Connection,
+    channel: &str,
+    key: &str,
+    system: &ChatMessage,
+    history: &mut Vec<ChatMessage>,
+    text: &str,
+    reply: &str,
+) -> Result<()> {
+    history.push(ChatMessage { role: "user".to_string(), content: text.to_string() });
+    history.push(ChatMessage { role: "assistant".to_string(), content: reply.to_string() });
+    let skip = history.len().saturating_sub(MAX_HISTORY_MESSAGES);
+    let history: Vec<&ChatMessage> = std::iter::once(system).chain(history.iter().skip(skip)).collect();
+    if let Err(e) = con.set::<_, _, ()>(key, serde_json::to_string(&history)?).await {
+        warn!(channel, error = %e, "error saving conversation");
+        metrics::redis_error("conversation");
+    }
+    Ok(())
+}
@@ -0,0 +1,85 @@
+use anyhow::Result;
+use ethers::types::{Address, Signature};
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+// Vinculación de wallets para los canales de chat, donde no hay Frame que la
+// aporte. Claves por canal: hash {canal}:wallets (user id -> wallet verificada)
+// y reto pendiente en {canal}:link:{user_id}
+const CHALLENGE_TTL_SECS: u64 = 10 * 60;
+
+#[derive(Serialize, Deserialize)]
+struct Challenge {
+    wallet: String,
+    message: String,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Linked {
+    Wallet(String),
+    NoChallenge,
+    InvalidSignature,
+}
+
+// Texto que el usuario firma con personal_sign; el nonce evita reutilizar firmas
+fn challenge_message(channel: &str, user_id: &str, wallet: &str, nonce: &str) -> String {
+    format!(
+        "Link wallet {} to Qawakun on {} (user {}).\nNonce: {}",
+        wallet, channel, user_id, nonce
+    )
+}
+
+fn wallets_key(channel: &str) -> String {
+    format!("{}:wallets", channel)
+}
+
+fn challenge_key(channel: &str, user_id: &str) -> String {
+    format!("{}:link:{}", channel, user_id)
+}
+
+// Guarda un reto nuevo para `wallet` y devuelve el mensaje a firmar
+pub async fn start_link(redis_client: &redis::Client, channel: &str, user_id: &str, wallet: &str) -> Result<String> {
+    let address: Address = wallet.parse()?;
+    let wallet = format!("{:?}", address);
+    let nonce = hex::encode(rand::random::<[u8; 16]>());
+    let message = challenge_message(channel, user_id, &wallet, &nonce);
+
+    let mut con = redis_client.get_async_connection().await?;
+    let challenge = Challenge { wallet, message: message.clone() };
+    let _: () = con.set_ex(
+        challenge_key(channel, user_id),
+        serde_json::to_string(&challenge)?,
+        CHALLENGE_TTL_SECS as usize,
+    ).await?;
+    Ok(message)
+}
+
+// Comprueba la firma del reto pendiente (EIP-191) y, si la firmó la wallet del
+// reto, la vincula al usuario
+pub async fn verify_link(redis_client: &redis::Client, channel: &str, user_id: &str, signature: &str) -> Result<Linked> {
+    let mut con = redis_client.get_async_connection().await?;
+    let key = challenge_key(channel, user_id);
+    let stored: Option<String> = con.get(&key).await?;
+    let challenge: Challenge = match stored {
+        Some(stored) => serde_json::from_str(&stored)?,
+        None => return Ok(Linked::NoChallenge),
+    };
+
+    let address: Address = challenge.wallet.parse()?;
+    let valid = Signature::from_str(signature.trim())
+        .map(|signature| signature.verify(challenge.message.as_str(), address).is_ok())
+        .unwrap_or(false);
+    if !valid {
+        return Ok(Linked::InvalidSignature);
+    }
+
+    let _: () = con.hset(wallets_key(channel), user_id, &challenge.wallet).await?;
+    let _: () = con.del(&key).await?;
+    Ok(Linked::Wallet(challenge.wallet))
+}
+
+pub async fn linked_wallet(redis_client: &redis::Client, channel: &str, user_id: &str) -> Result<Option<String>> {
+    let mut con = redis_client.get_async_connection().await?;
+    Ok(con.hget(wallets_key(channel), user_id).await?)
+}
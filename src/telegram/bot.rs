@@ -0,0 +1,379 @@
+use anyhow::Result;
+use chrono::Utc;
+use ethers::types::Address;
+use redis::AsyncCommands;
+use std::env;
+use std::sync::Arc;
+use tokio::time::Duration;
+use crate::api::admin::{is_paused, Control};
+use crate::api::cdp::nfts::{NftManager, UserData};
+use crate::api::nft_claim::{check_wallet_has_nft, enqueue_claim, REQUIRED_INTERACTIONS};
+use crate::api::proposals::Proposal;
+use crate::events::{self, Event};
+use crate::jobs::JobStatus;
+use crate::metrics;
+use crate::openai_methods::get_text::ChatMessage;
+use crate::social::wallet::{self, Linked};
+use crate::social::{AgentPipeline, Author, InboundMessage, SocialChannel};
+use crate::telemetry;
+use crate::workers::Shutdown;
+use tracing::{debug, error, info, warn};
+use super::channel::{TelegramChannel, TelegramEvent};
+use super::client::{TelegramClient, Update};
+use super::webhook;
+
+const PAUSED_RETRY: Duration = Duration::from_secs(30);
+const ERROR_BACKOFF: Duration = Duration::from_secs(15);
+const PROPOSAL_TYPES: [&str; 3] = ["WORLD", "CHARACTERS", "LAWS"];
+// Flexibilidad por defecto (1-10) de las propuestas enviadas desde el chat
+const DEFAULT_FLEXIBILITY: i32 = 5;
+
+// Comandos que se registran en Telegram con setMyCommands
+const COMMANDS: [(&str, &str); 6] = [
+    ("start", "Meet Qawakun"),
+    ("story", "Start or continue your story"),
+    ("link", "Link your wallet: /link 0xYourWallet"),
+    ("verify", "Confirm the link: /verify <signature>"),
+    ("claim", "Claim your Qawakun NFT"),
+    ("propose", "Shape the world: /propose WORLD|CHARACTERS|LAWS <idea>"),
+];
+
+const WELCOME: &str = "I am Qawakun, guide of this story. Send /story to begin, or simply write to me.\n\n\
+    When your journey is done, link your wallet with /link to /claim your Qawakun NFT, \
+    and use /propose to suggest new places, characters or laws for this world.";
+const STORY_PROMPT: &str = "Start my story, or continue it from where we left off, and give me my choices.";
+
+// Mensajes escritos por el usuario; el prompt que inyecta /story no cuenta
+fn user_messages(history: &[ChatMessage]) -> impl Iterator<Item = &ChatMessage> {
+    history.iter().filter(|m| m.role == "user" && m.content != STORY_PROMPT)
+}
+const FAILED_REPLY: &str = "Qawakun lost the thread for a moment. Please try again.";
+
+// Webhook si hay URL pública y secreto; si no, long polling
+pub fn webhook_mode() -> bool {
+    env::var("TELEGRAM_WEBHOOK_URL").map(|url| !url.is_empty()).unwrap_or(false)
+        && webhook::webhook_secret().is_some()
+}
+
+// "/claim" o "/propose@QawakunBot WORLD ..." -> ("claim", "") o ("propose", "WORLD ...")
+fn parse_command(text: &str) -> Option<(String, String)> {
+    let text = text.trim().strip_prefix('/')?;
+    let (command, args) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let command = command.split('@').next().unwrap_or("").to_lowercase();
+    if command.is_empty() {
+        return None;
+    }
+    Some((command, args.trim().to_string()))
+}
+
+// Comandos, wallet y flujos de claim y propuestas encima del canal; el resto de
+// mensajes va al pipeline común
+pub struct TelegramBot {
+    channel: TelegramChannel,
+    pipeline: AgentPipeline,
+    redis_client: redis::Client,
+    nft_manager: Option<Arc<NftManager>>,
+}
+
+impl TelegramBot {
+    pub fn new(client: TelegramClient, redis_client: redis::Client, nft_manager: Option<Arc<NftManager>>) -> Self {
+        Self {
+            channel: TelegramChannel::new(client, redis_client.clone()),
+            pipeline: AgentPipeline::new(redis_client.clone()),
+            redis_client,
+            nft_manager,
+        }
+    }
+
+    // Registra los comandos y deja la Bot API en el modo de entrega elegido
+    pub async fn configure(&self) -> Result<()> {
+        let client = self.channel.client();
+        client.set_commands(&COMMANDS).await?;
+
+        match (env::var("TELEGRAM_WEBHOOK_URL"), webhook::webhook_secret()) {
+            (Ok(url), Some(secret)) if webhook_mode() => {
+                client.set_webhook(&url, &secret).await?;
+                info!(url = %url, "Telegram webhook registered");
+            },
+            _ => {
+                client.delete_webhook().await?;
+                info!("Telegram long polling enabled");
+            },
+        }
+        Ok(())
+    }
+
+    async fn replies_paused(&self) -> bool {
+        is_paused(&self.redis_client, Control::TelegramReplies).await
+    }
+
+    // Un update se atiende una sola vez aunque llegue por poll y por webhook
+    // Quien llama comprueba antes la pausa de respuestas y, pausado, no lee el update
+    pub async fn process(&self, message: &InboundMessage<TelegramEvent>) -> Result<()> {
+        if !self.pipeline.claim(&self.channel, &message.id).await? {
+            debug!(update_id = %message.id, "Telegram update already handled");
+            return Ok(());
+        }
+
+        if let Some(callback_id) = &message.event.callback_id {
+            if let Err(e) = self.channel.client().answer_callback_query(callback_id).await {
+                warn!(update_id = %message.id, error = %e, "error answering Telegram callback");
+            }
+        }
+
+        let result = match parse_command(&message.text) {
+            Some((command, args)) => self.command(message, &command, &args).await,
+            None => self.pipeline.respond(&self.channel, message).await.map(|_| ()),
+        };
+
+        if let Err(e) = result {
+            error!(update_id = %message.id, error = %e, "failed to answer Telegram message");
+            metrics::mention("telegram", "failed");
+            if let Err(e) = self.channel.send_text(message.event.chat_id, FAILED_REPLY).await {
+                warn!(update_id = %message.id, error = %e, "error sending Telegram failure reply");
+            }
+        }
+        Ok(())
+    }
+
+    async fn command(&self, message: &InboundMessage<TelegramEvent>, command: &str, args: &str) -> Result<()> {
+        debug!(update_id = %message.id, command, "Telegram command");
+        metrics::mention("telegram", "command");
+
+        let reply = match command {
+            "start" => WELCOME.to_string(),
+            "story" => {
+                let prompt = InboundMessage { text: STORY_PROMPT.to_string(), ..message.clone() };
+                self.pipeline.respond(&self.channel, &prompt).await?;
+                return Ok(());
+            },
+            "link" => self.link(&message.author, args).await?,
+            "verify" => self.verify(&message.author, args).await?,
+            "claim" => self.claim(&message.author).await?,
+            "propose" => self.propose(&message.author, args).await?,
+            _ => {
+                let commands: Vec<String> = COMMANDS
+                    .iter()
+                    .map(|(command, description)| format!("/{} - {}", command, description))
+                    .collect();
+                format!("I don't know that command. Try one of these:\n{}", commands.join("\n"))
+            },
+        };
+        self.channel.send_text(message.event.chat_id, &reply).await
+    }
+
+    async fn link(&self, author: &Author, args: &str) -> Result<String> {
+        if args.parse::<Address>().is_err() {
+            return Ok("Send /link followed by your wallet address, e.g. /link 0x1234...".to_string());
+        }
+
+        let challenge = wallet::start_link(&self.redis_client, self.channel.name(), &author.id, args).await?;
+        Ok(format!(
+            "Sign this exact message with your wallet (personal_sign) and send /verify followed by the signature \
+             within 10 minutes:\n\n{}",
+            challenge
+        ))
+    }
+
+    async fn verify(&self, author: &Author, args: &str) -> Result<String> {
+        Ok(match wallet::verify_link(&self.redis_client, self.channel.name(), &author.id, args).await? {
+            Linked::Wallet(wallet) => {
+                info!(user = %author.id, wallet = %telemetry::redact_wallet(&wallet), "Telegram wallet linked");
+                format!("Wallet {} linked. You can now /claim your NFT and /propose ideas.", wallet)
+            },
+            Linked::NoChallenge => "There is no pending link. Start with /link 0xYourWallet.".to_string(),
+            Linked::InvalidSignature => "That signature does not match the wallet. Sign the exact message and try again.".to_string(),
+        })
+    }
+
+    // Historial del chat privado que guarda el pipeline
+    async fn history(&self, author: &Author) -> Result<Vec<ChatMessage>> {
+        let mut con = self.redis_client.get_async_connection().await?;
+        let stored: Option<String> = con.get(self.channel.history_key(author)).await?;
+        Ok(stored
+            .and_then(|stored| serde_json::from_str(&stored).ok())
+            .unwrap_or_default())
+    }
+
+    // Mismos requisitos que POST /nft-claim, contando los mensajes de este chat
+    async fn claim(&self, author: &Author) -> Result<String> {
+        let wallet = match wallet::linked_wallet(&self.redis_client, self.channel.name(), &author.id).await? {
+            Some(wallet) => wallet,
+            None => return Ok("Link your wallet first with /link 0xYourWallet.".to_string()),
+        };
+        let nft_manager = match &self.nft_manager {
+            Some(nft_manager) => nft_manager,
+            None => return Ok("NFT claims are not available right now.".to_string()),
+        };
+        if is_paused(&self.redis_client, Control::Minting).await {
+            return Ok("NFT minting is paused right now. Please try again later.".to_string());
+        }
+        if check_wallet_has_nft(nft_manager, &wallet).await? {
+            return Ok("This wallet already holds a Qawakun NFT.".to_string());
+        }
+
+        let history = self.history(author).await?;
+        let interactions = user_messages(&history).count();
+        if interactions < REQUIRED_INTERACTIONS {
+            return Ok(format!(
+                "Your story is not finished yet: {} of {} messages so far. Keep talking with me.",
+                interactions, REQUIRED_INTERACTIONS
+            ));
+        }
+
+        let user_data = UserData {
+            username: format!("Telegram User {}", author.display()),
+            email: "".to_string(),
+            wallet_address: wallet.clone(),
+            avatar_url: "".to_string(),
+            additional_data: Some(serde_json::json!({
+                "telegram_id": author.id,
+                "message_count": interactions,
+                "claim_timestamp": Utc::now(),
+                "conversation": serde_json::to_string(&history)?,
+            })),
+        };
+
+        // El mint sale por la cola de jobs; repetir /claim consulta el mismo job
+        let enqueued = enqueue_claim(&self.redis_client, 0, user_data, Utc::now()).await?;
+        let job = enqueued.job();
+        info!(user = %author.id, job_id = %job.id, status = job.status.as_str(), "NFT claim from Telegram");
+        Ok(match job.status {
+            JobStatus::Succeeded => match job.result.as_ref().and_then(|r| r["token_id"].as_u64()) {
+                Some(token_id) => format!("Qawakun NFT #{} is now in your wallet.", token_id),
+                None => "Your Qawakun NFT is now in your wallet.".to_string(),
+            },
+            JobStatus::Queued | JobStatus::Running | JobStatus::Retrying => {
+                "Your Qawakun NFT is being minted. Send /claim again in a minute to check.".to_string()
+            },
+            JobStatus::Failed => "The mint failed. Please try /claim again later.".to_string(),
+        })
+    }
+
+    // Se guarda en el mismo hash `proposals` que POST /proposals, con la wallet vinculada
+    async fn propose(&self, author: &Author, args: &str) -> Result<String> {
+        let usage = "Send /propose followed by WORLD, CHARACTERS or LAWS and your idea, e.g. /propose WORLD A city under the lake.";
+        let (kind, description) = args.split_once(char::is_whitespace).unwrap_or((args, ""));
+        let proposal_type = kind.to_uppercase();
+        if !PROPOSAL_TYPES.contains(&proposal_type.as_str()) || description.trim().is_empty() {
+            return Ok(usage.to_string());
+        }
+
+        let wallet = match wallet::linked_wallet(&self.redis_client, self.channel.name(), &author.id).await? {
+            Some(wallet) => wallet,
+            None => return Ok("Link your wallet first with /link 0xYourWallet.".to_string()),
+        };
+
+        let message_history = user_messages(&self.history(author).await?)
+            .map(|m| m.content.clone())
+            .collect();
+        let proposal = Proposal {
+            wallet: wallet.clone(),
+            fid: 0,
+            proposal_type,
+            description: description.trim().to_string(),
+            flexibility: DEFAULT_FLEXIBILITY,
+            contact: format!("telegram:{}", author.display()),
+            message_history,
+            timestamp: Utc::now().to_rfc3339(),
+            status: 1,
+        };
+
+        let mut con = self.redis_client.get_async_connection().await?;
+        let _: () = con.hset("proposals", &wallet, serde_json::to_string(&proposal)?).await?;
+        info!(user = %author.id, wallet = %telemetry::redact_wallet(&wallet), proposal_type = %proposal.proposal_type, "proposal saved from Telegram");
+        events::publish_in_background(self.redis_client.clone(), Event::ProposalSubmitted {
+            wallet,
+            fid: 0,
+            proposal_type: proposal.proposal_type,
+            channel: self.channel.name().to_string(),
+        });
+        Ok("Your proposal was submitted. The council will review it soon.".to_string())
+    }
+}
+
+pub async fn start_polling(bot: Arc<TelegramBot>, mut shutdown: Shutdown) -> Result<()> {
+    info!("starting Telegram long polling");
+
+    loop {
+        // Con las respuestas pausadas tampoco se piden updates: el offset no avanza
+        // y Telegram los guarda hasta reanudar
+        if is_paused(&bot.redis_client, Control::TelegramWorker).await || bot.replies_paused().await {
+            info!("Telegram paused, skipping poll");
+            if shutdown.sleep(PAUSED_RETRY).await {
+                break;
+            }
+            continue;
+        }
+
+        // getUpdates puede tardar hasta 30 s; el apagado no espera a que vuelva
+        let batch = tokio::select! {
+            batch = bot.channel.fetch() => batch,
+            _ = shutdown.recv() => break,
+        };
+
+        match batch {
+            Ok(batch) => {
+                for message in &batch.messages {
+                    bot.process(message).await?;
+                }
+                if let Some(cursor) = &batch.cursor {
+                    bot.channel.commit(cursor).await?;
+                }
+            },
+            Err(e) => {
+                error!(error = %e, "error getting Telegram updates");
+                if shutdown.sleep(ERROR_BACKOFF).await {
+                    break;
+                }
+            },
+        }
+    }
+
+    info!("Telegram polling stopped");
+    Ok(())
+}
+
+// Consume los updates que llegan por /webhooks/telegram
+pub async fn start_webhook_consumer(bot: Arc<TelegramBot>, mut shutdown: Shutdown) -> Result<()> {
+    info!("starting Telegram webhook consumer");
+    let mut con = bot.redis_client.get_async_connection().await?;
+    let inbox = &webhook::UPDATE_INBOX;
+    inbox.recover(&mut con).await?;
+
+    while !shutdown.is_shutdown() {
+        // Pausado, los updates se quedan en la cola hasta reanudar
+        if is_paused(&bot.redis_client, Control::TelegramWorker).await || bot.replies_paused().await {
+            if shutdown.sleep(PAUSED_RETRY).await {
+                break;
+            }
+            continue;
+        }
+
+        // El timeout corto permite ver la señal de apagado entre esperas
+        // El update sale de la lista de proceso solo cuando ya se atendió
+        if let Some(delivery) = inbox.next::<Update>(&mut con, 5).await? {
+            let result = match bot.channel.normalize(delivery.item.clone()).await {
+                Ok(Some(message)) => bot.process(&message).await,
+                Ok(None) => Ok(()),
+                Err(e) => {
+                    error!(error = %e, "failed to read queued Telegram update");
+                    Ok(())
+                },
+            };
+            match result {
+                Ok(()) => inbox.ack(&mut con, &delivery).await?,
+                Err(e) => {
+                    error!(error = %e, "failed to process queued Telegram update");
+                    inbox.requeue(&mut con, &delivery).await?;
+                    if shutdown.sleep(PAUSED_RETRY).await {
+                        break;
+                    }
+                },
+            }
+        }
+    }
+
+    info!("Telegram webhook consumer stopped");
+    Ok(())
+}
@@ -0,0 +1,220 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use lazy_static::lazy_static;
+use redis::AsyncCommands;
+use regex::Regex;
+use std::env;
+use crate::social::{Author, Batch, InboundMessage, SocialChannel};
+use crate::text::split_numbered;
+use tracing::debug;
+use super::client::{InlineButton, TelegramClient, Update};
+
+const UPDATE_OFFSET_KEY: &str = "telegram:update_offset";
+// Opciones de la última respuesta por chat, para resolver los botones: telegram:choices:{chat_id}
+const CHOICES_PREFIX: &str = "telegram:choices:";
+const CHOICES_TTL_SECS: usize = 24 * 60 * 60;
+const CHOICE_DATA_PREFIX: &str = "choice:";
+const MAX_CHOICES: usize = 4;
+const MAX_BUTTON_CHARS: usize = 60;
+// Límite de caracteres de un mensaje de Telegram
+const MAX_MESSAGE_CHARS: usize = 4096;
+const LONG_POLL_TIMEOUT_SECS: u64 = 30;
+
+lazy_static! {
+    // Opción numerada al final de la respuesta: "1. Seguir el río" o "2) ..."
+    static ref CHOICE_LINE: Regex = Regex::new(r"^\s*\d{1,2}[.)]\s+(.+?)\s*$").unwrap();
+}
+
+// Chat al que se contesta y, si el mensaje vino de un botón, la consulta a cerrar
+#[derive(Debug, Clone)]
+pub struct TelegramEvent {
+    pub chat_id: i64,
+    pub callback_id: Option<String>,
+}
+
+// Opciones numeradas con las que termina la respuesta, si hay al menos dos
+fn trailing_choices(text: &str) -> Vec<String> {
+    let mut choices: Vec<String> = text
+        .lines()
+        .rev()
+        .skip_while(|line| line.trim().is_empty())
+        .map_while(|line| CHOICE_LINE.captures(line).map(|c| c[1].to_string()))
+        .collect();
+    choices.reverse();
+    if choices.len() < 2 || choices.len() > MAX_CHOICES {
+        return Vec::new();
+    }
+    choices
+}
+
+fn button_label(choice: &str) -> String {
+    if choice.chars().count() <= MAX_BUTTON_CHARS {
+        return choice.to_string();
+    }
+    let mut label: String = choice.chars().take(MAX_BUTTON_CHARS - 1).collect();
+    label.push('…');
+    label
+}
+
+// Conversaciones privadas con el bot de Telegram; los grupos se ignoran
+pub struct TelegramChannel {
+    client: TelegramClient,
+    redis_client: redis::Client,
+}
+
+impl TelegramChannel {
+    pub fn new(client: TelegramClient, redis_client: redis::Client) -> Self {
+        Self { client, redis_client }
+    }
+
+    pub fn client(&self) -> &TelegramClient {
+        &self.client
+    }
+
+    // Convierte un update en mensaje. Un botón pulsado se convierte en el texto de
+    // la opción que representa; lo que no sea texto de un chat privado se descarta
+    pub async fn normalize(&self, update: Update) -> Result<Option<InboundMessage<TelegramEvent>>> {
+        let id = update.update_id.to_string();
+
+        if let Some(query) = update.callback_query {
+            let chat_id = match query.message.as_ref() {
+                Some(message) if message.chat.kind == "private" => message.chat.id,
+                _ => return Ok(None),
+            };
+            let index = query.data
+                .as_deref()
+                .and_then(|data| data.strip_prefix(CHOICE_DATA_PREFIX))
+                .and_then(|index| index.parse::<usize>().ok());
+
+            let mut con = self.redis_client.get_async_connection().await?;
+            let stored: Option<String> = con.get(format!("{}{}", CHOICES_PREFIX, chat_id)).await?;
+            let choices: Vec<String> = stored
+                .and_then(|stored| serde_json::from_str(&stored).ok())
+                .unwrap_or_default();
+            // Los botones de respuestas antiguas ya no tienen opciones guardadas
+            let text = match index.and_then(|index| choices.get(index)) {
+                Some(choice) => choice.clone(),
+                None => {
+                    debug!(chat_id, "stale Telegram choice button");
+                    self.client.answer_callback_query(&query.id).await?;
+                    return Ok(None);
+                }
+            };
+
+            return Ok(Some(InboundMessage {
+                id,
+                author: Author { id: query.from.id.to_string(), handle: query.from.username.clone() },
+                text,
+                event: TelegramEvent { chat_id, callback_id: Some(query.id) },
+            }));
+        }
+
+        let message = match update.message {
+            Some(message) if message.chat.kind == "private" => message,
+            _ => return Ok(None),
+        };
+        let (from, text) = match (message.from, message.text) {
+            (Some(from), Some(text)) if !from.is_bot && !text.trim().is_empty() => (from, text),
+            _ => return Ok(None),
+        };
+
+        Ok(Some(InboundMessage {
+            id,
+            author: Author { id: from.id.to_string(), handle: from.username },
+            text,
+            event: TelegramEvent { chat_id: message.chat.id, callback_id: None },
+        }))
+    }
+
+    // Envía texto sin pasar por el LLM (respuestas a comandos)
+    pub async fn send_text(&self, chat_id: i64, text: &str) -> Result<()> {
+        for part in split_numbered(text, MAX_MESSAGE_CHARS, |text| text.chars().count()) {
+            self.client.send_message(chat_id, &part, None).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl SocialChannel for TelegramChannel {
+    type Event = TelegramEvent;
+
+    fn name(&self) -> &'static str {
+        "telegram"
+    }
+
+    // Además del contexto narrativo, la historia avanza paso a paso con opciones
+    // numeradas que se convierten en botones
+    fn channel_prompt(&self) -> String {
+        let claim = match env::var("FRAME_URL") {
+            Ok(frame_url) if !frame_url.is_empty() => format!(
+                "link their wallet with /link and then send /claim, or open the Qawakun Frame on Farcaster ({})", frame_url
+            ),
+            _ => "link their wallet with /link and then send /claim".to_string(),
+        };
+        format!(
+            "You are talking privately on Telegram with a single person. \
+             Guide them through the story one step at a time, remembering what they already discovered. \
+             When the story reaches a decision, end your reply with two to four short options, one per line, \
+             numbered like \"1. ...\". \
+             When they reach the end of the story or ask how to claim their Qawakun NFT, tell them to {}. \
+             If they want to shape the world, tell them they can send /propose followed by WORLD, CHARACTERS or LAWS and their idea.",
+            claim
+        )
+    }
+
+    fn is_public(&self) -> bool {
+        false
+    }
+
+    async fn fetch(&self) -> Result<Batch<TelegramEvent>> {
+        let mut con = self.redis_client.get_async_connection().await?;
+        let offset: Option<i64> = con.get(UPDATE_OFFSET_KEY).await.ok();
+        let updates = self.client.get_updates(offset, LONG_POLL_TIMEOUT_SECS).await?;
+
+        let cursor = updates.last().map(|update| (update.update_id + 1).to_string());
+        let mut messages = Vec::new();
+        for update in updates {
+            if let Some(message) = self.normalize(update).await? {
+                messages.push(message);
+            }
+        }
+        Ok(Batch { messages, cursor })
+    }
+
+    async fn commit(&self, cursor: &str) -> Result<()> {
+        let mut con = self.redis_client.get_async_connection().await?;
+        let _: () = con.set(UPDATE_OFFSET_KEY, cursor.parse::<i64>()?).await?;
+        Ok(())
+    }
+
+    // Las opciones del final van también como botones en el último mensaje
+    async fn send_reply(&self, message: &InboundMessage<TelegramEvent>, text: &str) -> Result<()> {
+        let chat_id = message.event.chat_id;
+        let choices = trailing_choices(text);
+        let mut con = self.redis_client.get_async_connection().await?;
+        let choices_key = format!("{}{}", CHOICES_PREFIX, chat_id);
+        if choices.is_empty() {
+            let _: () = con.del(&choices_key).await?;
+        } else {
+            let _: () = con.set_ex(&choices_key, serde_json::to_string(&choices)?, CHOICES_TTL_SECS).await?;
+        }
+
+        let buttons: Vec<InlineButton> = choices
+            .iter()
+            .enumerate()
+            .map(|(index, choice)| InlineButton {
+                text: button_label(choice),
+                callback_data: format!("{}{}", CHOICE_DATA_PREFIX, index),
+            })
+            .collect();
+
+        let parts = split_numbered(text, MAX_MESSAGE_CHARS, |text| text.chars().count());
+        let last = parts.len() - 1;
+        for (index, part) in parts.iter().enumerate() {
+            let keyboard = if index == last { Some(buttons.as_slice()) } else { None };
+            self.client.send_message(chat_id, part, keyboard).await?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,166 @@
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::env;
+use tracing::{debug, info};
+
+const API_ROOT: &str = "https://api.telegram.org";
+// Tipos de update que pide el bot, tanto en long polling como en webhook
+const ALLOWED_UPDATES: [&str; 2] = ["message", "callback_query"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Update {
+    pub update_id: i64,
+    #[serde(default)]
+    pub message: Option<Message>,
+    #[serde(default)]
+    pub callback_query: Option<CallbackQuery>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Message {
+    pub message_id: i64,
+    #[serde(default)]
+    pub from: Option<User>,
+    pub chat: Chat,
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct User {
+    pub id: i64,
+    #[serde(default)]
+    pub is_bot: bool,
+    #[serde(default)]
+    pub username: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Chat {
+    pub id: i64,
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CallbackQuery {
+    pub id: String,
+    pub from: User,
+    #[serde(default)]
+    pub message: Option<Message>,
+    #[serde(default)]
+    pub data: Option<String>,
+}
+
+// Botón de un teclado inline; `callback_data` admite como mucho 64 bytes
+#[derive(Debug, Clone, Serialize)]
+pub struct InlineButton {
+    pub text: String,
+    pub callback_data: String,
+}
+
+// Todas las respuestas de la Bot API vienen envueltas en {ok, result, description}
+#[derive(Debug, Deserialize)]
+struct ApiResponse<T> {
+    ok: bool,
+    result: Option<T>,
+    description: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Me {
+    username: String,
+}
+
+#[derive(Clone)]
+pub struct TelegramClient {
+    http: reqwest::Client,
+    token: String,
+    pub username: String,
+}
+
+impl TelegramClient {
+    // El @username del bot sale de getMe, así que un token inválido falla aquí
+    pub async fn from_env() -> Result<Self> {
+        let token = env::var("TELEGRAM_BOT_TOKEN")?;
+        let mut client = Self { http: reqwest::Client::new(), token, username: String::new() };
+        let me: Me = client.call("getMe", json!({})).await?;
+        client.username = me.username;
+        info!(username = %client.username, "Telegram bot identity resolved");
+        Ok(client)
+    }
+
+    async fn call<T: DeserializeOwned>(&self, method: &str, body: serde_json::Value) -> Result<T> {
+        let response = self.http
+            .post(format!("{}/bot{}/{}", API_ROOT, self.token, method))
+            .json(&body)
+            .send()
+            .await?;
+        let status = response.status();
+        let response: ApiResponse<T> = response.json().await?;
+        debug!(method, status = status.as_u16(), ok = response.ok, "Telegram API response");
+
+        match (response.ok, response.result) {
+            (true, Some(result)) => Ok(result),
+            _ => Err(anyhow::anyhow!(
+                "Telegram {} failed ({}): {}",
+                method,
+                status,
+                response.description.unwrap_or_default()
+            )),
+        }
+    }
+
+    // Long polling: espera hasta `timeout_secs` a que haya updates. Pedir un
+    // `offset` confirma a Telegram todos los anteriores
+    pub async fn get_updates(&self, offset: Option<i64>, timeout_secs: u64) -> Result<Vec<Update>> {
+        self.call("getUpdates", json!({
+            "offset": offset,
+            "timeout": timeout_secs,
+            "allowed_updates": ALLOWED_UPDATES,
+        })).await
+    }
+
+    pub async fn send_message(&self, chat_id: i64, text: &str, keyboard: Option<&[InlineButton]>) -> Result<()> {
+        let mut body = json!({ "chat_id": chat_id, "text": text });
+        if let Some(buttons) = keyboard.filter(|buttons| !buttons.is_empty()) {
+            // Un botón por fila: las opciones de la historia suelen ser frases largas
+            let rows: Vec<Vec<&InlineButton>> = buttons.iter().map(|button| vec![button]).collect();
+            body["reply_markup"] = json!({ "inline_keyboard": rows });
+        }
+        let _: serde_json::Value = self.call("sendMessage", body).await?;
+        Ok(())
+    }
+
+    // Quita el indicador de carga del botón pulsado
+    pub async fn answer_callback_query(&self, callback_id: &str) -> Result<()> {
+        let _: bool = self.call("answerCallbackQuery", json!({ "callback_query_id": callback_id })).await?;
+        Ok(())
+    }
+
+    pub async fn set_webhook(&self, url: &str, secret: &str) -> Result<()> {
+        let _: bool = self.call("setWebhook", json!({
+            "url": url,
+            "secret_token": secret,
+            "allowed_updates": ALLOWED_UPDATES,
+        })).await?;
+        Ok(())
+    }
+
+    // Con un webhook activo getUpdates devuelve error, así que se borra antes del long polling
+    pub async fn delete_webhook(&self) -> Result<()> {
+        let _: bool = self.call("deleteWebhook", json!({})).await?;
+        Ok(())
+    }
+
+    pub async fn set_commands(&self, commands: &[(&str, &str)]) -> Result<()> {
+        let commands: Vec<serde_json::Value> = commands
+            .iter()
+            .map(|(command, description)| json!({ "command": command, "description": description }))
+            .collect();
+        let _: bool = self.call("setMyCommands", json!({ "commands": commands })).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,6 @@
+pub mod bot;
+pub mod channel;
+pub mod client;
+pub mod webhook;
+
+pub use bot::TelegramBot;
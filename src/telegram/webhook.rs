@@ -0,0 +1,22 @@
+use anyhow::Result;
+use crate::social::inbox::Inbox;
+use super::client::Update;
+
+// Cola de updates recibidos por webhook, consumida por el worker de Telegram
+pub const UPDATE_INBOX: Inbox = Inbox::new("telegram:update_queue", "telegram:webhook:", 24 * 60 * 60);
+
+// Telegram repite en esta cabecera el `secret_token` que se pasó a setWebhook
+pub const SECRET_HEADER: &str = "X-Telegram-Bot-Api-Secret-Token";
+
+pub fn webhook_secret() -> Option<String> {
+    std::env::var("TELEGRAM_WEBHOOK_SECRET").ok().filter(|secret| !secret.is_empty())
+}
+
+// Telegram reintenta los updates que no recibieron 200: se deduplica por update_id.
+// Devuelve false si el update ya estaba encolado
+pub async fn ingest(redis_client: &redis::Client, body: &[u8]) -> Result<bool> {
+    let update: Update = serde_json::from_slice(body)?;
+
+    let mut con = redis_client.get_async_connection().await?;
+    UPDATE_INBOX.push(&mut con, &update.update_id.to_string(), &serde_json::to_string(&update)?).await
+}
@@ -0,0 +1,110 @@
+use std::env;
+use std::future::Future;
+use tracing::Instrument;
+use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer};
+
+pub const CORRELATION_HEADER: &str = "X-Request-Id";
+
+tokio::task_local! {
+    static CORRELATION_ID: String;
+}
+
+// RUST_LOG define niveles por módulo (por defecto info), LOG_FORMAT=json emite JSON
+// y con la feature `otlp` OTEL_EXPORTER_OTLP_ENDPOINT exporta los spans
+pub fn init() {
+    let filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new("info"));
+
+    let json = env::var("LOG_FORMAT").map(|f| f.eq_ignore_ascii_case("json")).unwrap_or(false);
+    let fmt_layer = if json {
+        fmt::layer().json().with_current_span(true).with_span_list(false).boxed()
+    } else {
+        fmt::layer().with_target(true).boxed()
+    };
+
+    let registry = tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt_layer);
+
+    #[cfg(feature = "otlp")]
+    let registry = registry.with(otlp::layer());
+
+    registry.init();
+}
+
+pub fn new_correlation_id() -> String {
+    let id: u128 = rand::random();
+    format!("{:032x}", id)
+}
+
+// Ejecuta `fut` con `id` como correlation ID visible para las llamadas salientes
+pub async fn with_correlation_id<F: Future>(id: String, fut: F) -> F::Output {
+    CORRELATION_ID.scope(id, fut).await
+}
+
+pub fn correlation_id() -> Option<String> {
+    CORRELATION_ID.try_with(|id| id.clone()).ok()
+}
+
+// Valor para el campo `correlation_id` de los spans; vacío fuera de una petición o tarea
+pub fn correlation_label() -> String {
+    correlation_id().unwrap_or_default()
+}
+
+// Para trabajo fuera de una petición HTTP (un job, una entrega, un mensaje de un
+// worker): conserva el correlation ID actual o usa `id` o uno nuevo, y abre un span
+// `task` con él para que las llamadas a la cadena y a las APIs queden enlazadas
+pub async fn traced<F: Future>(task: &'static str, id: Option<String>, fut: F) -> F::Output {
+    let id = correlation_id().or(id).unwrap_or_else(new_correlation_id);
+    let span = tracing::info_span!("task", task, correlation_id = %id);
+    with_correlation_id(id, fut.instrument(span)).await
+}
+
+// Añade el correlation ID actual (si existe) a una petición HTTP saliente
+pub fn propagate(builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    match correlation_id() {
+        Some(id) => builder.header(CORRELATION_HEADER, id),
+        None => builder,
+    }
+}
+
+// Conserva solo lo justo de la wallet para correlacionar logs
+pub fn redact_wallet(wallet: &str) -> String {
+    let chars: Vec<char> = wallet.trim().chars().collect();
+    if chars.len() <= 10 {
+        return "0x…".to_string();
+    }
+    let head: String = chars[..6].iter().collect();
+    let tail: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}…{}", head, tail)
+}
+
+#[cfg(feature = "otlp")]
+mod otlp {
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::{runtime, trace, Resource};
+    use tracing_subscriber::{registry::LookupSpan, Layer};
+
+    pub fn layer<S>() -> Option<Box<dyn Layer<S> + Send + Sync>>
+    where
+        S: tracing::Subscriber + for<'span> LookupSpan<'span> + Send + Sync,
+    {
+        let endpoint = std::env::var("OTEL_EXPORTER_OTLP_ENDPOINT").ok()?;
+
+        let tracer = opentelemetry_otlp::new_pipeline()
+            .tracing()
+            .with_exporter(
+                opentelemetry_otlp::new_exporter()
+                    .tonic()
+                    .with_endpoint(endpoint),
+            )
+            .with_trace_config(trace::config().with_resource(Resource::new(vec![
+                KeyValue::new("service.name", "qawakun-backend"),
+            ])))
+            .install_batch(runtime::Tokio)
+            .ok()?;
+
+        Some(tracing_opentelemetry::layer().with_tracer(tracer).boxed())
+    }
+}
@@ -0,0 +1,185 @@
+// Longitud de un texto según el límite de cada red (bytes en Farcaster, peso en X)
+pub type Measure = fn(&str) -> usize;
+
+// Divide `text` en trozos de como mucho `max` según `measure`, numerados " (n/m)"
+// si hace falta más de uno. Corta por frases, luego por palabras y en último caso
+// dentro de la palabra, siempre en un límite de carácter UTF-8. `measure` tiene que
+// ser aditiva: la longitud de un texto es la suma de la de sus caracteres
+pub fn split_numbered(text: &str, max: usize, measure: Measure) -> Vec<String> {
+    let text = text.trim();
+    if measure(text) <= max {
+        return vec![text.to_string()];
+    }
+
+    // El sufijo depende de cuántos trozos salgan; se recalcula hasta que sea estable
+    let mut digits = 1;
+    loop {
+        let suffix_len = measure(&format!(" ({}/{})", "9".repeat(digits), "9".repeat(digits)));
+        let chunks = chunk(text, max.saturating_sub(suffix_len).max(1), measure);
+        if chunks.len().to_string().len() <= digits {
+            let total = chunks.len();
+            return chunks
+                .into_iter()
+                .enumerate()
+                .map(|(i, chunk)| format!("{} ({}/{})", chunk, i + 1, total))
+                .collect();
+        }
+        digits += 1;
+    }
+}
+
+fn chunk(text: &str, limit: usize, measure: Measure) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+
+    for sentence in sentences(text) {
+        if fits(&current, sentence, limit, measure) {
+            append(&mut current, sentence);
+            continue;
+        }
+        if !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+        }
+        if measure(sentence) <= limit {
+            current.push_str(sentence);
+            continue;
+        }
+
+        for word in sentence.split_whitespace() {
+            if fits(&current, word, limit, measure) {
+                append(&mut current, word);
+                continue;
+            }
+            if !current.is_empty() {
+                chunks.push(std::mem::take(&mut current));
+            }
+
+            let mut word = word;
+            while measure(word) > limit {
+                let cut = cut_at(word, limit, measure);
+                chunks.push(word[..cut].to_string());
+                word = &word[cut..];
+            }
+            current.push_str(word);
+        }
+    }
+
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+// Mayor prefijo que cabe en `limit`; al menos un carácter para avanzar siempre
+fn cut_at(word: &str, limit: usize, measure: Measure) -> usize {
+    let mut used = 0;
+    let mut cut = 0;
+    for (i, c) in word.char_indices() {
+        let end = i + c.len_utf8();
+        used += measure(&word[i..end]);
+        if used > limit {
+            break;
+        }
+        cut = end;
+    }
+    if cut == 0 {
+        cut = word.chars().next().map(char::len_utf8).unwrap_or(word.len());
+    }
+    cut
+}
+
+fn fits(current: &str, piece: &str, limit: usize, measure: Measure) -> bool {
+    let separator = if current.is_empty() { 0 } else { 1 };
+    measure(current) + separator + measure(piece) <= limit
+}
+
+fn append(current: &mut String, piece: &str) {
+    if !current.is_empty() {
+        current.push(' ');
+    }
+    current.push_str(piece);
+}
+
+// Frases terminadas en . ! ? … o salto de línea
+fn sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        let ends_sentence = matches!(c, '.' | '!' | '?' | '…' | '\n')
+            && chars.peek().map(|(_, next)| next.is_whitespace()).unwrap_or(true);
+        if ends_sentence {
+            let end = i + c.len_utf8();
+            sentences.push(text[start..end].trim());
+            start = end;
+        }
+    }
+    sentences.push(text[start..].trim());
+
+    sentences.into_iter().filter(|s| !s.is_empty()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes(text: &str) -> usize {
+        text.len()
+    }
+
+    // Como el peso de X: ASCII 1, el resto 2
+    fn weight(text: &str) -> usize {
+        text.chars().map(|c| if c.is_ascii() { 1 } else { 2 }).sum()
+    }
+
+    fn body(part: &str) -> &str {
+        part.rsplit_once(" (").map(|(body, _)| body).unwrap_or(part)
+    }
+
+    fn words(text: &str) -> String {
+        text.split_whitespace().collect::<Vec<_>>().join(" ")
+    }
+
+    #[test]
+    fn short_text_is_not_numbered() {
+        assert_eq!(split_numbered("  hola mundo  ", 20, bytes), vec!["hola mundo"]);
+    }
+
+    #[test]
+    fn parts_are_numbered_and_within_limit() {
+        let text = "Primera frase corta. Segunda frase algo más larga que la primera. Tercera y última frase.";
+        let parts = split_numbered(text, 40, bytes);
+        assert!(parts.len() > 1);
+        for (i, part) in parts.iter().enumerate() {
+            assert!(part.len() <= 40, "{:?} is {} bytes", part, part.len());
+            assert!(part.ends_with(&format!(" ({}/{})", i + 1, parts.len())));
+        }
+        let joined: Vec<&str> = parts.iter().map(|p| body(p)).collect();
+        assert_eq!(words(&joined.join(" ")), words(text));
+    }
+
+    #[test]
+    fn multibyte_text_is_cut_on_char_boundaries() {
+        let text = "ñandú ".repeat(10) + &"🦙".repeat(30) + " 漢字漢字漢字漢字漢字漢字漢字漢字";
+        for max in [12, 17, 25, 40] {
+            for measure in [bytes as Measure, weight as Measure] {
+                let parts = split_numbered(&text, max, measure);
+                for part in &parts {
+                    assert!(measure(part) <= max, "{:?} measures {} > {}", part, measure(part), max);
+                }
+                let joined: String = parts.iter().map(|p| body(p)).collect::<Vec<_>>().concat();
+                assert_eq!(joined.replace(' ', ""), text.replace(' ', ""));
+            }
+        }
+    }
+
+    #[test]
+    fn suffix_width_grows_with_part_count() {
+        let text = "palabra ".repeat(200);
+        let parts = split_numbered(&text, 30, bytes);
+        assert!(parts.len() >= 10);
+        assert!(parts.iter().all(|part| part.len() <= 30));
+        assert!(parts.last().unwrap().ends_with(&format!("({}/{})", parts.len(), parts.len())));
+    }
+}
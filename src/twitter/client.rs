@@ -1,19 +1,42 @@
-use twitter_v2::authorization::Oauth1aToken;
-use twitter_v2::TwitterApi;
+use twitter_v2::api_result::{ApiError, ApiPayload};
+use twitter_v2::authorization::{Authorization, Oauth1aToken};
+use twitter_v2::meta::TweetsMeta;
+use twitter_v2::query::{TweetExpansion, TweetField, UserField};
+use twitter_v2::{Tweet, TwitterApi};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::env;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::error::Error;
-use std::time::Duration;
-use tokio::time::sleep;
+use tracing::{debug, info, warn};
+use super::ratelimit::RateLimit;
+
+const API_ROOT: &str = "https://api.twitter.com/2";
+// Páginas máximas de menciones por poll (100 por página)
+const MAX_MENTION_PAGES: usize = 5;
+// Límite de un post en caracteres ponderados (ver tweet_weight)
+pub const MAX_TWEET_WEIGHT: usize = 280;
+
+// Mención con el @username del autor, resuelto con la expansión author_id
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Mention {
+    pub tweet: Tweet,
+    pub username: Option<String>,
+}
 
 pub struct TwitterClient {
     pub api: Arc<TwitterApi<Oauth1aToken>>,
     pub user_id: u64,
+    pub username: String,
+    // twitter-v2 no expone las cabeceras, así que las menciones se piden con un
+    // cliente propio firmado con el mismo token
+    http: reqwest::Client,
+    mentions_rate_limit: Arc<Mutex<Option<RateLimit>>>,
 }
 
 impl TwitterClient {
     pub async fn new() -> Result<Self, Box<dyn Error + Send + Sync>> {
-        println!("🔑 Starting OAuth authentication...");
+        info!("starting X OAuth authentication");
         
         let api_key = env::var("TWITTER_API_KEY")?;
         let api_secret = env::var("TWITTER_API_SECRET")?;
@@ -29,21 +52,23 @@ impl TwitterClient {
 
         let api = Arc::new(TwitterApi::new(auth));
 
-        println!("👤 Fetching user information...");
         let me = api.get_users_me()
             .send()
             .await?;
         
-        let user_id = me.data
+        let user = me.data
             .as_ref()
-            .ok_or("No user data found")?
-            .id
-            .as_u64();
+            .ok_or("No user data found")?;
+        let user_id = user.id.as_u64();
+        let username = user.username.clone();
         
-        println!("✅ Twitter client started (ID: {})", user_id);
+        info!(user_id, username = %username, "X client started");
         Ok(Self { 
             api,
-            user_id
+            user_id,
+            username,
+            http: reqwest::Client::new(),
+            mentions_rate_limit: Arc::new(Mutex::new(None)),
         })
     }
 
@@ -58,52 +83,172 @@ impl TwitterClient {
             .as_u64())
     }
 
-    pub async fn post_reply(&self, tweet_id: &str, text: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
-        self.api
-            .post_tweet()
-            .in_reply_to_tweet_id(tweet_id.parse::<u64>()?)
-            .text(text.to_string())
-            .send()
-            .await?;
-        Ok(())
+    // Devuelve el id del post publicado, para encadenar la siguiente parte del hilo
+    pub async fn post_tweet(
+        &self,
+        text: &str,
+        in_reply_to: Option<&str>,
+        media_ids: &[String],
+    ) -> Result<String, Box<dyn Error + Send + Sync>> {
+        let mut request = self.api.post_tweet();
+        request.text(text.to_string());
+        if let Some(tweet_id) = in_reply_to {
+            request.in_reply_to_tweet_id(tweet_id.parse::<u64>()?);
+        }
+        if !media_ids.is_empty() {
+            let media_ids = media_ids.iter().map(|id| id.parse::<u64>()).collect::<Result<Vec<_>, _>>()?;
+            request.add_media(media_ids, Vec::<u64>::new());
+        }
+        let posted = request.send().await?;
+        Ok(posted.data
+            .as_ref()
+            .ok_or("No tweet data in post response")?
+            .id
+            .to_string())
     }
 
-    pub async fn get_mentions_since(&self, since_id: Option<u64>) -> Result<Vec<twitter_v2::Tweet>, Box<dyn Error + Send + Sync>> {
-        let mut request = self.api.get_user_mentions(self.user_id);
-        
-        let request = if let Some(id) = since_id {
-            request.since_id(id)
-        } else {
-            &mut request 
-        };
-
-        let mentions = request.send().await;
-
-        match mentions {
-            Ok(response) => {
-                Ok(response.data.as_ref().map(|d| d.clone()).unwrap_or_default())
-            },
-            Err(err) => {
-                println!("Error fetching mentions: {:?}", err);
-                Ok(vec![]) 
-            },
+    // Posts de la conversación: el post raíz y las respuestas de los últimos 7 días
+    // (ventana de la búsqueda reciente), del más antiguo al más reciente
+    pub async fn get_conversation(&self, conversation_id: u64) -> Result<Vec<Mention>, Box<dyn Error + Send + Sync>> {
+        let mut root_request = self.api.get_tweet(conversation_id);
+        root_request
+            .expansions([TweetExpansion::AuthorId])
+            .user_fields([UserField::Username])
+            .tweet_fields([TweetField::AuthorId, TweetField::ConversationId, TweetField::CreatedAt]);
+        let root = root_request.send().await?;
+
+        let mut search = self.api.get_tweets_search_recent(format!("conversation_id:{}", conversation_id));
+        search
+            .max_results(100)
+            .expansions([TweetExpansion::AuthorId])
+            .user_fields([UserField::Username])
+            .tweet_fields([TweetField::AuthorId, TweetField::ConversationId, TweetField::CreatedAt]);
+        let replies = search.send().await?;
+
+        let usernames: HashMap<u64, String> = [root.includes(), replies.includes()]
+            .into_iter()
+            .flatten()
+            .filter_map(|includes| includes.users.as_ref())
+            .flatten()
+            .map(|u| (u.id.as_u64(), u.username.clone()))
+            .collect();
+
+        let mut tweets: Vec<Tweet> = replies.into_data().unwrap_or_default();
+        tweets.extend(root.into_data());
+        tweets.sort_by_key(|tweet| tweet.id.as_u64());
+        tweets.dedup_by_key(|tweet| tweet.id.as_u64());
+
+        Ok(tweets
+            .into_iter()
+            .map(|tweet| {
+                let username = tweet.author_id.and_then(|id| usernames.get(&id.as_u64()).cloned());
+                Mention { tweet, username }
+            })
+            .collect())
+    }
+
+    // Límite del endpoint de menciones según la última respuesta, con éxito o no
+    pub fn mentions_rate_limit(&self) -> Option<RateLimit> {
+        self.mentions_rate_limit.lock().unwrap().clone()
+    }
+
+    // Petición firmada con el token OAuth 1.0a de la cuenta. La firma solo cubre la
+    // query, así que los parámetros no pueden ir en un cuerpo form-urlencoded
+    pub(super) async fn send_signed(&self, request: reqwest::RequestBuilder) -> Result<reqwest::Response, Box<dyn Error + Send + Sync>> {
+        let mut request = request.build()?;
+        let authorization = self.api.auth().header(&request).await?;
+        request.headers_mut().insert(reqwest::header::AUTHORIZATION, authorization);
+        Ok(self.http.execute(request).await?)
+    }
+
+    pub(super) fn http(&self) -> &reqwest::Client {
+        &self.http
+    }
+
+    async fn get_mentions_page(
+        &self,
+        since_id: Option<u64>,
+        until_id: Option<u64>,
+        pagination_token: Option<&str>,
+    ) -> Result<ApiPayload<Vec<Tweet>, TweetsMeta>, Box<dyn Error + Send + Sync>> {
+        let mut url = reqwest::Url::parse(&format!("{}/users/{}/mentions", API_ROOT, self.user_id))?;
+        url.query_pairs_mut()
+            .append_pair("max_results", "100")
+            .append_pair("expansions", "author_id")
+            .append_pair("user.fields", "username")
+            .append_pair("tweet.fields", "author_id,conversation_id,created_at");
+        if let Some(id) = since_id {
+            url.query_pairs_mut().append_pair("since_id", &id.to_string());
+        }
+        if let Some(id) = until_id {
+            url.query_pairs_mut().append_pair("until_id", &id.to_string());
+        }
+        if let Some(token) = pagination_token {
+            url.query_pairs_mut().append_pair("pagination_token", token);
+        }
+
+        let response = self.send_signed(self.http.get(url)).await?;
+        if let Some(rate_limit) = RateLimit::from_headers(response.headers()) {
+            debug!(remaining = rate_limit.remaining, reset_at = rate_limit.reset_at, "mentions rate limit");
+            *self.mentions_rate_limit.lock().unwrap() = Some(rate_limit);
+        }
+
+        let status = response.status();
+        let text = response.text().await?;
+        if !status.is_success() {
+            let mut error = serde_json::from_str::<ApiError>(&text).unwrap_or_else(|_| ApiError {
+                detail: text,
+                ..Default::default()
+            });
+            error.status = status;
+            return Err(error.into());
         }
+        Ok(serde_json::from_str(&text)?)
     }
 
-    pub async fn process_mentions(&self, since_id: Option<u64>) {
-        loop {
-            match self.get_mentions_since(since_id).await {
-                Ok(mentions) => {
-                    for mention in mentions {
-                        println!("Mention: {:?}", mention);
-                    }
-                }
-                Err(err) => {
-                    println!("Error processing mentions: {:?}", err);
-                }
+    // Menciones posteriores a `since_id` (y anteriores a `until_id` si se da), de la
+    // más antigua a la más reciente. Sin `since_id` (primer arranque) solo se lee la
+    // primera página para no contestar todo el historial. El booleano indica que
+    // quedaron páginas más antiguas sin leer por el tope de páginas. Los errores de
+    // la API se propagan para no mover el cursor
+    pub async fn get_mentions_since(
+        &self,
+        since_id: Option<u64>,
+        until_id: Option<u64>,
+    ) -> Result<(Vec<Mention>, bool), Box<dyn Error + Send + Sync>> {
+        let mut mentions = Vec::new();
+        let mut pagination_token: Option<String> = None;
+        let mut truncated = false;
+
+        for page in 0..MAX_MENTION_PAGES {
+            let response = self.get_mentions_page(since_id, until_id, pagination_token.as_deref()).await?;
+            let usernames: HashMap<u64, String> = response
+                .includes()
+                .and_then(|includes| includes.users.as_ref())
+                .map(|users| users.iter().map(|u| (u.id.as_u64(), u.username.clone())).collect())
+                .unwrap_or_default();
+            let next_token = response.meta().and_then(|meta| meta.next_token.clone());
+
+            let tweets = response.into_data().unwrap_or_default();
+            debug!(page, count = tweets.len(), "mention page retrieved");
+            for tweet in tweets {
+                let username = tweet.author_id.and_then(|id| usernames.get(&id.as_u64()).cloned());
+                mentions.push(Mention { tweet, username });
+            }
+
+            match next_token {
+                Some(token) if since_id.is_some() => pagination_token = Some(token),
+                _ => break,
+            }
+            if page + 1 == MAX_MENTION_PAGES {
+                warn!(pages = MAX_MENTION_PAGES, "mention backlog larger than the page limit, older mentions left for the next poll");
+                truncated = true;
             }
-            sleep(Duration::from_secs(901)).await;
         }
+
+        // La API devuelve primero las más recientes
+        mentions.reverse();
+        Ok((mentions, truncated))
     }
 }
 
@@ -112,6 +257,21 @@ impl Clone for TwitterClient {
         Self {
             api: Arc::clone(&self.api),
             user_id: self.user_id,
+            username: self.username.clone(),
+            http: self.http.clone(),
+            mentions_rate_limit: Arc::clone(&self.mentions_rate_limit),
         }
     }
-}
\ No newline at end of file
+}
+
+// Peso de un texto según X: los caracteres latinos y la puntuación común cuentan 1
+// y el resto (CJK, emoji...) cuenta 2. X cuenta cada URL como 23; aquí cuentan
+// por sus caracteres, las respuestas del bot no llevan enlaces
+pub fn tweet_weight(text: &str) -> usize {
+    text.chars()
+        .map(|c| match c as u32 {
+            0..=0x10FF | 0x2000..=0x200D | 0x2010..=0x201F | 0x2032..=0x2037 => 1,
+            _ => 2,
+        })
+        .sum()
+}
@@ -0,0 +1,319 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::env;
+use std::error::Error;
+use std::sync::Mutex;
+use crate::api::admin::{is_paused, Control};
+use crate::metrics;
+use crate::social::{AgentPipeline, Author, Batch, InboundMessage, SocialChannel};
+use crate::text::split_numbered;
+use crate::workers::Shutdown;
+use tracing::{debug, error, info, warn};
+use super::client::TwitterClient;
+use super::ratelimit::{self, RateLimit};
+
+const API_ROOT: &str = "https://api.twitter.com/2";
+const LAST_DM_EVENT_KEY: &str = "twitter:last_dm_event_id";
+// Respuesta en varias partes a medio enviar: twitter:dm:progress:{id}
+const DM_PROGRESS_PREFIX: &str = "twitter:dm:progress:";
+const DM_PROGRESS_TTL_SECS: usize = 24 * 60 * 60;
+const MAX_DM_PAGES: usize = 3;
+// Límite de caracteres de un mensaje directo
+const MAX_DM_CHARS: usize = 10_000;
+
+// Texto de la respuesta y cuántas de sus partes ya se enviaron
+#[derive(Debug, Serialize, Deserialize)]
+struct DmProgress {
+    text: String,
+    sent: usize,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DmEvent {
+    pub id: String,
+    #[serde(default)]
+    pub text: String,
+    pub sender_id: Option<String>,
+    pub dm_conversation_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DmEventsRoot {
+    #[serde(default)]
+    data: Vec<DmEvent>,
+    meta: Option<DmEventsMeta>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DmEventsMeta {
+    next_token: Option<String>,
+}
+
+pub fn dms_enabled() -> bool {
+    env::var("TWITTER_DMS").map(|v| v == "true").unwrap_or(false)
+}
+
+fn event_id(event: &DmEvent) -> u64 {
+    event.id.parse().unwrap_or(0)
+}
+
+// Mensajes recibidos posteriores a `since_id`, del más antiguo al más reciente.
+// La API no admite since_id en este endpoint: se pagina hasta encontrar uno ya
+// visto. Sin `since_id` solo se lee la primera página
+async fn get_dm_events_since(
+    client: &TwitterClient,
+    since_id: Option<u64>,
+) -> Result<(Vec<DmEvent>, Option<RateLimit>), Box<dyn Error + Send + Sync>> {
+    let mut events = Vec::new();
+    let mut rate_limit = None;
+    let mut pagination_token: Option<String> = None;
+
+    for _ in 0..MAX_DM_PAGES {
+        let mut request = client.http().get(format!("{}/dm_events", API_ROOT)).query(&[
+            ("event_types", "MessageCreate"),
+            ("dm_event.fields", "id,text,sender_id,dm_conversation_id,created_at"),
+            ("max_results", "100"),
+        ]);
+        if let Some(token) = &pagination_token {
+            request = request.query(&[("pagination_token", token)]);
+        }
+
+        let response = client.send_signed(request).await?;
+        rate_limit = RateLimit::from_headers(response.headers()).or(rate_limit);
+        let status = response.status();
+        if !status.is_success() {
+            return Err(format!("X DM events returned {}: {}", status, response.text().await?).into());
+        }
+
+        let root: DmEventsRoot = response.json().await?;
+        let mut reached_seen = false;
+        for event in root.data {
+            if since_id.map(|since| event_id(&event) <= since).unwrap_or(false) {
+                reached_seen = true;
+                break;
+            }
+            events.push(event);
+        }
+
+        match root.meta.and_then(|meta| meta.next_token) {
+            Some(token) if since_id.is_some() && !reached_seen => pagination_token = Some(token),
+            _ => break,
+        }
+    }
+
+    // Solo los mensajes de otros; los del bot también aparecen en el feed
+    let own_id = client.user_id.to_string();
+    events.retain(|event| event.sender_id.as_deref() != Some(own_id.as_str()));
+    events.sort_by_key(event_id);
+    Ok((events, rate_limit))
+}
+
+async fn send_dm(client: &TwitterClient, conversation_id: &str, text: &str) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let url = format!("{}/dm_conversations/{}/messages", API_ROOT, conversation_id);
+    let response = client.send_signed(client.http().post(url).json(&json!({ "text": text }))).await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("X DM send returned {}: {}", status, response.text().await?).into());
+    }
+    Ok(())
+}
+
+// Mensajes directos de X: canal privado con historial por remitente
+pub struct DmChannel {
+    client: TwitterClient,
+    redis_client: redis::Client,
+    rate_limit: Mutex<Option<RateLimit>>,
+}
+
+impl DmChannel {
+    pub fn new(client: TwitterClient, redis_client: redis::Client) -> Self {
+        Self { client, redis_client, rate_limit: Mutex::new(None) }
+    }
+
+    // Límite del endpoint de mensajes según la última lectura correcta
+    fn rate_limit(&self) -> Option<RateLimit> {
+        self.rate_limit.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl SocialChannel for DmChannel {
+    type Event = DmEvent;
+
+    fn name(&self) -> &'static str {
+        "twitter_dm"
+    }
+
+    // Además del contexto narrativo, la historia avanza paso a paso y al final se
+    // explica cómo reclamar el NFT
+    fn channel_prompt(&self) -> String {
+        let claim = match env::var("FRAME_URL") {
+            Ok(frame_url) if !frame_url.is_empty() => format!(
+                "open the Qawakun Frame on Farcaster ({}) and connect their wallet there", frame_url
+            ),
+            _ => "open the Qawakun Frame on Farcaster and connect their wallet there".to_string(),
+        };
+        format!(
+            "You are talking privately by direct message on X with a single person. \
+             Guide them through the story one step at a time, remembering what they already discovered. \
+             When they reach the end of the story or ask how to claim their Qawakun NFT, tell them to {}.",
+            claim
+        )
+    }
+
+    fn is_public(&self) -> bool {
+        false
+    }
+
+    // Historial propio por remitente, separado del de menciones y del de la API
+    fn history_key(&self, author: &Author) -> String {
+        format!("conversation:x_dm:{}", author.id)
+    }
+
+    async fn fetch(&self) -> Result<Batch<DmEvent>> {
+        let mut con = self.redis_client.get_async_connection().await?;
+        let since_id: Option<u64> = con.get(LAST_DM_EVENT_KEY).await.ok();
+        let (events, rate_limit) = get_dm_events_since(&self.client, since_id).await
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        if rate_limit.is_some() {
+            *self.rate_limit.lock().unwrap() = rate_limit;
+        }
+
+        let cursor = events.last().map(|event| event.id.clone());
+        let messages = events
+            .into_iter()
+            .filter(|event| !event.text.trim().is_empty())
+            .filter_map(|event| Some(InboundMessage {
+                id: event.id.clone(),
+                author: Author { id: event.sender_id.clone()?, handle: None },
+                text: event.text.clone(),
+                event,
+            }))
+            .collect();
+        Ok(Batch { messages, cursor })
+    }
+
+    async fn commit(&self, cursor: &str) -> Result<()> {
+        let mut con = self.redis_client.get_async_connection().await?;
+        let _: () = con.set(LAST_DM_EVENT_KEY, cursor.parse::<u64>()?).await?;
+        Ok(())
+    }
+
+    // Cada parte enviada queda registrada; si el envío se corta, el reintento
+    // sigue por la primera parte pendiente en lugar de mandarlo todo otra vez
+    async fn send_reply(&self, message: &InboundMessage<DmEvent>, text: &str) -> Result<()> {
+        let conversation_id = message.event.dm_conversation_id.as_deref()
+            .ok_or_else(|| anyhow::anyhow!("DM event without conversation"))?;
+        let mut con = self.redis_client.get_async_connection().await?;
+        let progress_key = format!("{}{}", DM_PROGRESS_PREFIX, message.id);
+        let mut progress = pending_reply(&mut con, &message.id).await?
+            .unwrap_or_else(|| DmProgress { text: text.to_string(), sent: 0 });
+
+        let parts = split_numbered(&progress.text, MAX_DM_CHARS, |text| text.chars().count());
+        for part in parts.iter().skip(progress.sent) {
+            send_dm(&self.client, conversation_id, part).await
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
+            progress.sent += 1;
+            let _: () = con.set_ex(&progress_key, serde_json::to_string(&progress)?, DM_PROGRESS_TTL_SECS).await?;
+        }
+        let _: () = con.del(&progress_key).await?;
+        Ok(())
+    }
+}
+
+async fn pending_reply(con: &mut redis::aio::Connection, event_id: &str) -> Result<Option<DmProgress>> {
+    let stored: Option<String> = con.get(format!("{}{}", DM_PROGRESS_PREFIX, event_id)).await?;
+    Ok(stored.and_then(|stored| serde_json::from_str(&stored).ok()))
+}
+
+// Falso si hay que parar el lote y reintentar el mensaje en el siguiente poll.
+// Una respuesta que quedó a medias se termina de enviar sin volver a llamar al LLM
+async fn process_dm(
+    channel: &DmChannel,
+    pipeline: &AgentPipeline,
+    con: &mut redis::aio::Connection,
+    message: &InboundMessage<DmEvent>,
+) -> Result<bool> {
+    if !pipeline.claim(channel, &message.id).await? {
+        debug!(event_id = %message.id, "DM already handled");
+        return Ok(true);
+    }
+
+    info!(event_id = %message.id, sender = %message.author.id, chars = message.text.len(), "DM received");
+    let result = match pending_reply(con, &message.id).await? {
+        Some(progress) => {
+            info!(event_id = %message.id, sent = progress.sent, "resuming partially sent DM reply");
+            match channel.send_reply(message, &progress.text).await {
+                Ok(()) => pipeline.remember(channel, message, &progress.text).await,
+                Err(e) => Err(e),
+            }
+        },
+        None => pipeline.respond(channel, message).await.map(|_| ()),
+    };
+
+    match result {
+        Ok(()) => {
+            info!(event_id = %message.id, "DM answered");
+            Ok(true)
+        },
+        Err(e) => {
+            metrics::mention("twitter_dm", "failed");
+            if !pipeline.release(channel, &message.id).await? {
+                error!(event_id = %message.id, error = %e, "giving up on DM");
+                return Ok(true);
+            }
+            warn!(event_id = %message.id, error = %e, "error answering DM, retrying next poll");
+            Ok(false)
+        },
+    }
+}
+
+pub async fn start_dm_polling(client: TwitterClient, mut shutdown: Shutdown) -> Result<(), Box<dyn Error + Send + Sync>> {
+    info!("starting X DM monitoring");
+    let redis_client = redis::Client::open(env::var("REDIS_URL")?)?;
+    let mut con = redis_client.get_async_connection().await?;
+    let channel = DmChannel::new(client, redis_client.clone());
+    let pipeline = AgentPipeline::new(redis_client.clone());
+
+    loop {
+        // Con las respuestas pausadas no se lee nada, los DMs esperan tras el cursor
+        if is_paused(&redis_client, Control::TwitterWorker).await {
+            info!("X monitoring paused, skipping DM poll");
+        } else if is_paused(&redis_client, Control::TwitterReplies).await {
+            info!("X replies paused, skipping DM poll");
+        } else {
+            match channel.fetch().await {
+                Ok(batch) => {
+                    let mut completed = true;
+                    for message in &batch.messages {
+                        if shutdown.is_shutdown() {
+                            completed = false;
+                            break;
+                        }
+                        // El cursor no pasa de un mensaje que hay que reintentar
+                        if !process_dm(&channel, &pipeline, &mut con, message).await? {
+                            completed = false;
+                            break;
+                        }
+                        channel.commit(&message.id).await?;
+                    }
+                    // Incluye los mensajes vacíos o del bot que no llegan al lote
+                    if let (true, Some(cursor)) = (completed, &batch.cursor) {
+                        channel.commit(cursor).await?;
+                    }
+                },
+                Err(e) => error!(error = %e, "error getting DMs"),
+            }
+        }
+
+        let delay = ratelimit::next_poll_in(channel.rate_limit().as_ref());
+        debug!(next_poll_secs = delay.as_secs(), "next DM poll scheduled");
+        if shutdown.sleep(delay).await {
+            info!("X DM monitoring stopped");
+            return Ok(());
+        }
+    }
+}
@@ -1,91 +1,258 @@
-use super::client::TwitterClient;
-use crate::openai_methods::get_text::handle_conversation;
-use twitter_v2::Tweet;
-use std::env;
+use anyhow::Result;
+use async_trait::async_trait;
+use redis::AsyncCommands;
+use super::client::{tweet_weight, Mention, TwitterClient, MAX_TWEET_WEIGHT};
+use super::media;
+use super::ratelimit::{PostBudget, PostKind};
+use crate::social::{Author, Batch, InboundMessage, SocialChannel, ThreadEntry};
+use crate::text::split_numbered;
 use std::error::Error;
-use std::collections::HashSet;
-use lazy_static::lazy_static;
-use regex::Regex;
-use std::sync::Mutex;
-
-lazy_static! {
-    static ref REPLIED_TWEETS: Mutex<HashSet<String>> = Mutex::new(HashSet::new());
-    static ref SPAM_PATTERNS: Vec<Regex> = vec![
-        Regex::new(r"(?i)crypto").unwrap(),
-        Regex::new(r"(?i)pump").unwrap(),
-        Regex::new(r"(?i)airdrop").unwrap(),
-        Regex::new(r"(?i)blast").unwrap(),
-        Regex::new(r"(?i)token").unwrap(),
-        Regex::new(r"https?://").unwrap(),
-        Regex::new(r"(?i)drop").unwrap(),
-    ];
+use crate::metrics;
+use tracing::{debug, info, warn};
+
+pub const LAST_MENTION_KEY: &str = "twitter:last_mention_id";
+// Atraso que no cupo en un poll: `until` es la mención más antigua ya leída y
+// `newest` la más reciente, que pasa a ser el cursor cuando se vacía el hueco
+const MENTION_BACKLOG_KEY: &str = "twitter:mention_backlog";
+// Posts de la conversación que se pasan como contexto
+const MAX_THREAD_MESSAGES: usize = 20;
+// media_id subido para la respuesta a una mención; X los caduca a las 24 h
+const REPLY_MEDIA_PREFIX: &str = "twitter:reply_media:";
+const REPLY_MEDIA_TTL_SECS: usize = 12 * 60 * 60;
+
+// Otros posts de la conversación, sin la mención, para dar contexto al LLM. Si
+// no se pueden leer se contesta solo con la mención
+async fn load_thread(client: &TwitterClient, mention: &Mention) -> Vec<Mention> {
+    let tweet = &mention.tweet;
+    let conversation_id = match tweet.conversation_id {
+        Some(id) if id != tweet.id => id.as_u64(),
+        _ => return Vec::new(),
+    };
+
+    match client.get_conversation(conversation_id).await {
+        Ok(thread) => {
+            let thread: Vec<Mention> = thread
+                .into_iter()
+                .filter(|entry| entry.tweet.id.as_u64() < tweet.id.as_u64())
+                .collect();
+            let skip = thread.len().saturating_sub(MAX_THREAD_MESSAGES);
+            thread.into_iter().skip(skip).collect()
+        },
+        Err(e) => {
+            warn!(tweet_id = %tweet.id, conversation_id, error = %e, "could not load conversation");
+            Vec::new()
+        }
+    }
 }
 
-fn is_spam(text: &str) -> bool {
-    let mention_count = text.matches('@').count();
-    if mention_count > 3 {
-        return true;
+fn author(mention: &Mention) -> Author {
+    Author {
+        id: mention.tweet.author_id
+            .map(|id| id.as_u64().to_string())
+            .unwrap_or_else(|| "unknown".to_string()),
+        handle: mention.username.clone(),
     }
+}
 
-    for pattern in SPAM_PATTERNS.iter() {
-        if pattern.is_match(text) {
-            return true;
-        }
+pub fn inbound(mention: Mention) -> InboundMessage<Mention> {
+    InboundMessage {
+        id: mention.tweet.id.to_string(),
+        author: author(&mention),
+        text: mention.tweet.text.clone(),
+        event: mention,
     }
+}
 
-    false
+// Reserva el cupo de `kind` para `count` posts; falso si no alcanza, y entonces no
+// queda nada reservado
+pub async fn reserve_posts(
+    con: &mut redis::aio::Connection,
+    kind: PostKind,
+    count: usize,
+) -> Result<bool, Box<dyn Error + Send + Sync>> {
+    let budget = PostBudget::from_env();
+    for reserved in 0..count {
+        if !budget.try_consume(con, kind).await? {
+            for _ in 0..reserved {
+                budget.release(con, kind).await?;
+            }
+            if kind == PostKind::Reply {
+                metrics::mention("twitter", "over_budget");
+            }
+            return Ok(false);
+        }
+    }
+    Ok(true)
 }
 
-pub async fn handle_mention(
-    client: &TwitterClient, 
-    tweet: Tweet
-) -> Result<(), Box<dyn Error + Send + Sync>> {
-    let tweet_id = tweet.id.to_string();
-
-    {
-        let mut replied = REPLIED_TWEETS.lock().unwrap();
-        if replied.contains(&tweet_id) {
-            println!("🔄 Tweet already replied to, ignoring...");
-            return Ok(());
+// Publica las partes ya reservadas; las que no se llegan a publicar devuelven su cupo
+pub async fn publish_parts(
+    client: &TwitterClient,
+    con: &mut redis::aio::Connection,
+    kind: PostKind,
+    in_reply_to: Option<&str>,
+    parts: &[String],
+    media_ids: &[String],
+) -> Result<Vec<String>, Box<dyn Error + Send + Sync>> {
+    let budget = PostBudget::from_env();
+    let mut posted: Vec<String> = Vec::new();
+    for (i, part) in parts.iter().enumerate() {
+        let reply_to = posted.last().map(String::as_str).or(in_reply_to);
+        let media: &[String] = if i == 0 { media_ids } else { &[] };
+        match client.post_tweet(part, reply_to, media).await {
+            Ok(posted_id) => posted.push(posted_id),
+            Err(e) => {
+                for _ in i..parts.len() {
+                    budget.release(con, kind).await?;
+                }
+                // Con parte del hilo ya publicada no se reintenta, se duplicaría
+                if i == 0 {
+                    return Err(e);
+                }
+                warn!(in_reply_to = ?in_reply_to, part = i + 1, parts = parts.len(), error = %e, "X thread left incomplete");
+                return Ok(posted);
+            }
         }
-        
-        if replied.len() > 1000 {
-            replied.clear();
+    }
+
+    debug!(in_reply_to = ?in_reply_to, parts = parts.len(), media = media_ids.len(), "X thread posted");
+    Ok(posted)
+}
+
+// Imagen del momento narrado para adjuntar a la respuesta; si falla la respuesta
+// sale solo con texto. El media_id se guarda por mención para que un reintento no
+// genere (ni cuente en la cuota) otra imagen
+async fn reply_image(
+    client: &TwitterClient,
+    con: &mut redis::aio::Connection,
+    tweet_id: &str,
+    author: &str,
+    message: &str,
+) -> Vec<String> {
+    if !media::reply_images_enabled() {
+        return Vec::new();
+    }
+
+    let cache_key = format!("{}{}", REPLY_MEDIA_PREFIX, tweet_id);
+    if let Ok(Some(media_id)) = con.get::<_, Option<String>>(&cache_key).await {
+        return vec![media_id];
+    }
+
+    match media::generate_attachment(client, con, author, message).await {
+        Ok(Some(media_id)) => {
+            if let Err(e) = con.set_ex::<_, _, ()>(&cache_key, &media_id, REPLY_MEDIA_TTL_SECS).await {
+                warn!(tweet_id = %tweet_id, error = %e, "could not cache reply media");
+            }
+            vec![media_id]
+        },
+        Ok(None) => Vec::new(),
+        Err(e) => {
+            warn!(author = %author, error = %e, "could not attach image to reply");
+            Vec::new()
         }
-        
-        replied.insert(tweet_id);
     }
+}
 
-    let author = tweet.author_id
-        .map(|id| id.as_u64().to_string())
-        .unwrap_or_else(|| "unknown".to_string());
-    let content = tweet.text;
-    
-    // Check for spam
-    if is_spam(&content) {
-        println!("🚫 Spam detected, ignoring tweet");
-        return Ok(());
+// Menciones públicas de X; el cursor es el id de la última mención procesada
+pub struct MentionChannel {
+    client: TwitterClient,
+    redis_client: redis::Client,
+}
+
+impl MentionChannel {
+    pub fn new(client: TwitterClient, redis_client: redis::Client) -> Self {
+        Self { client, redis_client }
+    }
+
+    pub fn client(&self) -> &TwitterClient {
+        &self.client
+    }
+}
+
+#[async_trait]
+impl SocialChannel for MentionChannel {
+    type Event = Mention;
+
+    fn name(&self) -> &'static str {
+        "twitter"
+    }
+
+    fn channel_prompt(&self) -> String {
+        format!(
+            "You are @{} replying inside an X thread with several participants. \
+             Messages from other people are prefixed with their @username. Reply only to the last message, \
+             addressing its author by their handle and staying consistent with what you already said in the thread.",
+            self.client.username
+        )
     }
 
-    println!("👤 From: @{}", author);
-    println!("💭 Message: {}", content);
+    // Si el atraso no cabe en un poll, el cursor no se mueve: se contestan las
+    // páginas leídas y los siguientes polls leen hacia atrás (`until_id`) hasta
+    // llegar al cursor. Sin cursor en el lote no se confirma nada
+    async fn fetch(&self) -> Result<Batch<Mention>> {
+        let mut con = self.redis_client.get_async_connection().await?;
+        let since_id: Option<u64> = con.get(LAST_MENTION_KEY).await.ok();
+        let backlog_until: Option<u64> = con.hget(MENTION_BACKLOG_KEY, "until").await?;
+        let backlog_newest: Option<u64> = con.hget(MENTION_BACKLOG_KEY, "newest").await?;
+
+        let (mentions, truncated) = self.client.get_mentions_since(since_id, backlog_until).await
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
 
-    let redis_client = redis::Client::open(env::var("REDIS_URL")?)?;
-    let api_key = env::var("OPENAI_API_KEY")?;
-    let context = std::fs::read_to_string("context.md")?;
+        let oldest = mentions.first().map(|mention| mention.tweet.id.as_u64());
+        let newest = mentions.last().map(|mention| mention.tweet.id.as_u64());
+        let cursor = match (truncated, oldest) {
+            (true, Some(oldest)) => {
+                let newest = backlog_newest.or(newest).unwrap_or(oldest);
+                let _: () = con.hset_multiple(MENTION_BACKLOG_KEY, &[("until", oldest), ("newest", newest)]).await?;
+                info!(until = oldest, newest, "mention backlog pending, cursor kept");
+                None
+            },
+            _ => backlog_newest.or(newest).map(|id| id.to_string()),
+        };
+        Ok(Batch { messages: mentions.into_iter().map(inbound).collect(), cursor })
+    }
 
-    let response = handle_conversation(
-        &redis_client,
-        &api_key,
-        &author,
-        &context,
-        &content
-    ).await?;
+    async fn commit(&self, cursor: &str) -> Result<()> {
+        let cursor = cursor.parse::<u64>()?;
+        let mut con = self.redis_client.get_async_connection().await?;
+        let _: () = con.set(LAST_MENTION_KEY, cursor).await?;
 
-    if let Some(message) = response["choices"][0]["message"]["content"].as_str() {
-        client.post_reply(&tweet.id.to_string(), message).await?;
-        println!("✅ Reply sent");
+        let backlog_newest: Option<u64> = con.hget(MENTION_BACKLOG_KEY, "newest").await?;
+        if backlog_newest.is_some_and(|newest| cursor >= newest) {
+            let _: () = con.del(MENTION_BACKLOG_KEY).await?;
+        }
+        Ok(())
     }
 
-    Ok(())
-}
\ No newline at end of file
+    async fn thread(&self, message: &InboundMessage<Mention>) -> Result<Vec<ThreadEntry>> {
+        Ok(load_thread(&self.client, &message.event)
+            .await
+            .iter()
+            .map(|entry| ThreadEntry {
+                author: author(entry),
+                from_bot: entry.tweet.author_id.map(|id| id.as_u64()) == Some(self.client.user_id),
+                text: entry.tweet.text.clone(),
+            })
+            .collect())
+    }
+
+    // Sin cupo de posts el error lleva la mención a la cola de reintentos. El cupo
+    // se comprueba antes de generar la imagen para no gastarla en una respuesta que
+    // no se va a publicar
+    async fn send_reply(&self, message: &InboundMessage<Mention>, text: &str) -> Result<()> {
+        let mut con = self.redis_client.get_async_connection().await?;
+        let parts = split_numbered(text, MAX_TWEET_WEIGHT, tweet_weight);
+        let reserved = reserve_posts(&mut con, PostKind::Reply, parts.len())
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        if !reserved {
+            return Err(anyhow::anyhow!("monthly X post budget exhausted"));
+        }
+        let media_ids = reply_image(&self.client, &mut con, &message.id, &message.author.id, text).await;
+        publish_parts(&self.client, &mut con, PostKind::Reply, Some(&message.id), &parts, &media_ids)
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        debug!(tweet_id = %message.id, "reply sent");
+        Ok(())
+    }
+}
@@ -0,0 +1,200 @@
+use base64::Engine;
+use chrono::Utc;
+use lazy_static::lazy_static;
+use redis::AsyncCommands;
+use regex::Regex;
+use serde::Deserialize;
+use std::env;
+use std::error::Error;
+use tokio::time::{sleep, Duration};
+use crate::openai_methods::get_image::generate_image;
+use tracing::{debug, info};
+use super::client::TwitterClient;
+
+// Subida por partes de la API v1.1; cada APPEND lleva como mucho 5 MB
+const UPLOAD_URL: &str = "https://upload.twitter.com/1.1/media/upload.json";
+const CHUNK_SIZE: usize = 1024 * 1024;
+const MAX_STATUS_CHECKS: usize = 10;
+
+// Imágenes generadas por usuario y día: twitter:media_quota:{YYYY-MM-DD}:{user}
+const QUOTA_PREFIX: &str = "twitter:media_quota:";
+const QUOTA_TTL_SECS: usize = 2 * 24 * 60 * 60;
+const DEFAULT_DAILY_QUOTA: u32 = 3;
+const MAX_PROMPT_CHARS: usize = 400;
+
+lazy_static! {
+    // Temas que no se mandan a DALL-E aunque salgan en la narración
+    static ref UNSAFE_PATTERNS: Vec<Regex> = vec![
+        Regex::new(r"(?i)\b(nude|naked|nsfw|sex\w*|porn\w*|erotic)\b").unwrap(),
+        Regex::new(r"(?i)\b(gore|blood\w*|corpse|behead\w*|mutilat\w*|tortur\w*|suicide)\b").unwrap(),
+        Regex::new(r"(?i)\b(gun|rifle|bomb|explosive|weapon)s?\b").unwrap(),
+        Regex::new(r"(?i)\b(cocaine|heroin|meth)\b").unwrap(),
+        Regex::new(r"(?i)\b(nazi|swastika|terroris\w*)\b").unwrap(),
+    ];
+    static ref HANDLES_AND_LINKS: Regex = Regex::new(r"(@\w+|https?://\S+|#\w+)").unwrap();
+}
+
+#[derive(Debug, Deserialize)]
+struct UploadResponse {
+    media_id_string: String,
+    processing_info: Option<ProcessingInfo>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ProcessingInfo {
+    state: String,
+    check_after_secs: Option<u64>,
+    error: Option<serde_json::Value>,
+}
+
+pub fn reply_images_enabled() -> bool {
+    env::var("TWITTER_REPLY_IMAGES").map(|v| v == "true").unwrap_or(false)
+}
+
+// Prompt para DALL-E a partir del momento narrado: sin @handles, enlaces ni
+// hashtags, recortado, y descartado si toca temas no permitidos
+pub fn safe_prompt(narration: &str) -> Option<String> {
+    let cleaned = HANDLES_AND_LINKS.replace_all(narration, " ");
+    let cleaned: String = cleaned.split_whitespace().collect::<Vec<_>>().join(" ");
+    if cleaned.is_empty() || UNSAFE_PATTERNS.iter().any(|pattern| pattern.is_match(&cleaned)) {
+        return None;
+    }
+
+    let moment: String = cleaned.chars().take(MAX_PROMPT_CHARS).collect();
+    Some(format!(
+        "Qawakun artwork, an Andean mythic illustration of this moment, no text, no real people: {}",
+        moment
+    ))
+}
+
+fn quota_key(user: &str) -> String {
+    format!("{}{}:{}", QUOTA_PREFIX, Utc::now().format("%Y-%m-%d"), user)
+}
+
+fn daily_quota() -> u32 {
+    env::var("TWITTER_MEDIA_DAILY_QUOTA")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_DAILY_QUOTA)
+}
+
+// Falso si el usuario ya agotó sus imágenes del día
+async fn take_quota(con: &mut redis::aio::Connection, user: &str) -> redis::RedisResult<bool> {
+    let key = quota_key(user);
+    let count: u32 = con.incr(&key, 1).await?;
+    if count == 1 {
+        let _: () = con.expire(&key, QUOTA_TTL_SECS).await?;
+    }
+    if count > daily_quota() {
+        let _: () = con.decr(&key, 1).await?;
+        return Ok(false);
+    }
+    Ok(true)
+}
+
+async fn release_quota(con: &mut redis::aio::Connection, user: &str) -> redis::RedisResult<()> {
+    con.decr(quota_key(user), 1).await
+}
+
+async fn upload_command(
+    client: &TwitterClient,
+    request: reqwest::RequestBuilder,
+) -> Result<Option<UploadResponse>, Box<dyn Error + Send + Sync>> {
+    let response = client.send_signed(request).await?;
+    let status = response.status();
+    let text = response.text().await?;
+    if !status.is_success() {
+        return Err(format!("X media upload returned {}: {}", status, text).into());
+    }
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(serde_json::from_str(&text)?))
+}
+
+// Sube una imagen con INIT / APPEND / FINALIZE y espera a que X la procese si
+// hace falta. Devuelve el media_id para adjuntarlo a un post
+pub async fn upload(client: &TwitterClient, bytes: &[u8], media_type: &str) -> Result<String, Box<dyn Error + Send + Sync>> {
+    let init = upload_command(client, client.http().post(UPLOAD_URL).query(&[
+        ("command", "INIT"),
+        ("total_bytes", &bytes.len().to_string()),
+        ("media_type", media_type),
+        ("media_category", "tweet_image"),
+    ])).await?.ok_or("Empty response to media INIT")?;
+    let media_id = init.media_id_string;
+
+    for (segment_index, chunk) in bytes.chunks(CHUNK_SIZE).enumerate() {
+        let form = reqwest::multipart::Form::new()
+            .part("media", reqwest::multipart::Part::bytes(chunk.to_vec()).file_name("media"));
+        upload_command(client, client.http().post(UPLOAD_URL)
+            .query(&[
+                ("command", "APPEND"),
+                ("media_id", media_id.as_str()),
+                ("segment_index", &segment_index.to_string()),
+            ])
+            .multipart(form)).await?;
+        debug!(media_id = %media_id, segment_index, bytes = chunk.len(), "media segment uploaded");
+    }
+
+    let mut processing = upload_command(client, client.http().post(UPLOAD_URL).query(&[
+        ("command", "FINALIZE"),
+        ("media_id", media_id.as_str()),
+    ])).await?.and_then(|finalized| finalized.processing_info);
+
+    let mut checks = 0;
+    while let Some(info) = processing.take() {
+        match info.state.as_str() {
+            "succeeded" => break,
+            "failed" => return Err(format!("X media processing failed: {:?}", info.error).into()),
+            _ => {},
+        }
+        if checks == MAX_STATUS_CHECKS {
+            return Err("X media processing did not finish in time".into());
+        }
+        checks += 1;
+
+        sleep(Duration::from_secs(info.check_after_secs.unwrap_or(1))).await;
+        processing = upload_command(client, client.http().get(UPLOAD_URL).query(&[
+            ("command", "STATUS"),
+            ("media_id", media_id.as_str()),
+        ])).await?.and_then(|status| status.processing_info);
+    }
+
+    info!(media_id = %media_id, bytes = bytes.len(), "media uploaded to X");
+    Ok(media_id)
+}
+
+// Genera con DALL-E una imagen del momento narrado y la sube a X. Devuelve None
+// si el prompt no pasa el filtro o si `user` ya agotó su cupo diario
+pub async fn generate_attachment(
+    client: &TwitterClient,
+    con: &mut redis::aio::Connection,
+    user: &str,
+    narration: &str,
+) -> Result<Option<String>, Box<dyn Error + Send + Sync>> {
+    let prompt = match safe_prompt(narration) {
+        Some(prompt) => prompt,
+        None => {
+            info!(user = %user, "narration rejected by the image prompt filter");
+            return Ok(None);
+        }
+    };
+    if !take_quota(con, user).await? {
+        info!(user = %user, quota = daily_quota(), "daily image quota reached");
+        return Ok(None);
+    }
+
+    let result = async {
+        let image = generate_image(&prompt).await.map_err(|e| e.to_string())?;
+        let bytes = base64::engine::general_purpose::STANDARD.decode(image)?;
+        upload(client, &bytes, "image/png").await
+    }.await;
+
+    match result {
+        Ok(media_id) => Ok(Some(media_id)),
+        Err(e) => {
+            release_quota(con, user).await?;
+            Err(e)
+        }
+    }
+}
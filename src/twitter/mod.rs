@@ -1,3 +1,8 @@
 pub mod client;
+pub mod dm;
 pub mod handlers;
+pub mod media;
+pub mod posts;
+pub mod queue;
+pub mod ratelimit;
 pub mod stream; 
\ No newline at end of file
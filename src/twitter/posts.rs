@@ -0,0 +1,142 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use super::client::{tweet_weight, TwitterClient, MAX_TWEET_WEIGHT};
+use super::handlers::{publish_parts, reserve_posts};
+use super::media;
+use super::ratelimit::{PostBudget, PostKind};
+use crate::jobs::{self, Enqueued, JobError, JobHandler};
+use crate::text::split_numbered;
+use tracing::{info, warn};
+
+pub const POST_JOB: &str = "twitter_post";
+// Ids publicados por cada post programado, para que un job que se vuelve a ejecutar
+// tras publicar (p. ej. lease vencido) no lo publique dos veces
+const POSTED_PREFIX: &str = "twitter:scheduled_post:";
+const POSTED_TTL_SECS: usize = 7 * 24 * 60 * 60;
+// media_id subido para el post; X los caduca a las 24 h
+const POST_MEDIA_PREFIX: &str = "twitter:post_media:";
+const POST_MEDIA_TTL_SECS: usize = 12 * 60 * 60;
+
+#[derive(Serialize, Deserialize)]
+struct PostPayload {
+    post_id: String,
+    text: String,
+    #[serde(default)]
+    reply_to: Option<String>,
+    #[serde(default)]
+    image: bool,
+    // Usuario admin que lo pidió; la cuota diaria de imágenes es suya
+    requested_by: String,
+}
+
+// Encola un post programado de POST /admin/twitter/posts. Con idempotency key,
+// repetir la petición devuelve el mismo job
+pub async fn enqueue_post(
+    redis_client: &redis::Client,
+    text: &str,
+    reply_to: Option<String>,
+    image: bool,
+    requested_by: &str,
+    idempotency_key: Option<&str>,
+) -> Result<Enqueued> {
+    let payload = serde_json::to_value(PostPayload {
+        post_id: format!("{:016x}", rand::random::<u64>()),
+        text: text.to_string(),
+        reply_to,
+        image,
+        requested_by: requested_by.to_string(),
+    })?;
+    let idempotency_key = idempotency_key.map(|key| format!("twitter-post:{}", key));
+    jobs::enqueue(redis_client, POST_JOB, payload, idempotency_key).await
+}
+
+// Genera la imagen, la sube y publica el post (o el hilo) fuera de la petición HTTP
+pub struct TwitterPostJob {
+    redis_client: redis::Client,
+    client: TwitterClient,
+}
+
+impl TwitterPostJob {
+    pub fn new(redis_client: redis::Client, client: TwitterClient) -> Self {
+        Self { redis_client, client }
+    }
+
+    // Imagen del post, reutilizando la subida en un intento anterior
+    async fn media(&self, con: &mut redis::aio::Connection, post: &PostPayload) -> std::result::Result<Vec<String>, JobError> {
+        if !post.image {
+            return Ok(Vec::new());
+        }
+
+        let cache_key = format!("{}{}", POST_MEDIA_PREFIX, post.post_id);
+        if let Ok(Some(media_id)) = con.get::<_, Option<String>>(&cache_key).await {
+            return Ok(vec![media_id]);
+        }
+
+        match media::generate_attachment(&self.client, con, &post.requested_by, &post.text).await {
+            Ok(Some(media_id)) => {
+                if let Err(e) = con.set_ex::<_, _, ()>(&cache_key, &media_id, POST_MEDIA_TTL_SECS).await {
+                    warn!(post_id = %post.post_id, error = %e, "could not cache post media");
+                }
+                Ok(vec![media_id])
+            },
+            Ok(None) => Err(JobError::Fatal(anyhow::anyhow!("image rejected by the prompt filter or daily image quota reached"))),
+            Err(e) => Err(JobError::Retry(anyhow::anyhow!("error generating image: {}", e))),
+        }
+    }
+}
+
+#[async_trait]
+impl JobHandler for TwitterPostJob {
+    fn kind(&self) -> &'static str {
+        POST_JOB
+    }
+
+    // El cupo se reserva antes de generar la imagen para no gastarla en un post que
+    // no se va a publicar. Solo se reintenta si no salió ninguna parte
+    async fn run(&self, payload: &serde_json::Value) -> std::result::Result<serde_json::Value, JobError> {
+        let post: PostPayload = serde_json::from_value(payload.clone())
+            .map_err(|e| JobError::Fatal(anyhow::anyhow!("invalid post payload: {}", e)))?;
+        let mut con = self.redis_client.get_async_connection().await
+            .map_err(|e| JobError::Retry(e.into()))?;
+
+        let posted_key = format!("{}{}", POSTED_PREFIX, post.post_id);
+        let already_posted: Option<String> = con.get(&posted_key).await
+            .map_err(|e| JobError::Retry(e.into()))?;
+        if let Some(posted) = already_posted {
+            let posted: Vec<String> = serde_json::from_str(&posted).unwrap_or_default();
+            return Ok(serde_json::json!({ "tweet_ids": posted }));
+        }
+
+        let parts = split_numbered(&post.text, MAX_TWEET_WEIGHT, tweet_weight);
+        let reserved = reserve_posts(&mut con, PostKind::Scheduled, parts.len()).await
+            .map_err(|e| JobError::Retry(anyhow::anyhow!("{}", e)))?;
+        if !reserved {
+            return Err(JobError::Fatal(anyhow::anyhow!("monthly X post budget exhausted")));
+        }
+
+        let media_ids = match self.media(&mut con, &post).await {
+            Ok(media_ids) => media_ids,
+            Err(e) => {
+                let budget = PostBudget::from_env();
+                for _ in 0..parts.len() {
+                    if let Err(e) = budget.release(&mut con, PostKind::Scheduled).await {
+                        warn!(post_id = %post.post_id, error = %e, "could not release X post budget");
+                    }
+                }
+                return Err(e);
+            },
+        };
+
+        let posted = publish_parts(&self.client, &mut con, PostKind::Scheduled, post.reply_to.as_deref(), &parts, &media_ids)
+            .await
+            .map_err(|e| JobError::Retry(anyhow::anyhow!("error publishing post: {}", e)))?;
+        if let Err(e) = con.set_ex::<_, _, ()>(&posted_key, serde_json::to_string(&posted).unwrap_or_default(), POSTED_TTL_SECS).await {
+            warn!(post_id = %post.post_id, error = %e, "could not save published post");
+        }
+
+        info!(by = %post.requested_by, parts = posted.len(), media = media_ids.len(), "scheduled X post published");
+        Ok(serde_json::json!({ "tweet_ids": posted }))
+    }
+}
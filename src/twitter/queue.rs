@@ -0,0 +1,53 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use crate::retry_queue::{Leased, RetryQueue};
+use super::client::Mention;
+
+// Reintentos de menciones: 15 min la primera vez y el doble tras cada fallo; tras
+// cinco intentos pasan a twitter:dead_letter
+pub const RETRY_QUEUE: RetryQueue = RetryQueue::new("twitter:retry_queue", "twitter:dead_letter", 5, 15 * 60, 10 * 60);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FailedMention {
+    pub mention: Mention,
+    pub attempts: u32,
+    pub last_error: String,
+    pub failed_at: i64,
+}
+
+// Reprograma la mención o, agotados los intentos, la manda a dead letter. `retry`
+// es el reintento que acaba de fallar, si venía de la cola. Devuelve true en el
+// caso de dead letter
+pub async fn record_failure(
+    con: &mut redis::aio::Connection,
+    retry: Option<&Leased<FailedMention>>,
+    mention: Mention,
+    attempts: u32,
+    error: &str,
+) -> redis::RedisResult<bool> {
+    let failed = FailedMention {
+        mention,
+        attempts,
+        last_error: error.to_string(),
+        failed_at: Utc::now().timestamp(),
+    };
+    RETRY_QUEUE.fail(con, retry, &failed, attempts).await
+}
+
+// Saca de la cola el siguiente reintento que ya toca, si lo hay
+pub async fn take_due(con: &mut redis::aio::Connection) -> redis::RedisResult<Option<Leased<FailedMention>>> {
+    RETRY_QUEUE.take_due(con).await
+}
+
+pub async fn complete(con: &mut redis::aio::Connection, retry: &Leased<FailedMention>) -> redis::RedisResult<()> {
+    RETRY_QUEUE.complete(con, retry).await
+}
+
+pub async fn dead_letters(con: &mut redis::aio::Connection) -> redis::RedisResult<Vec<FailedMention>> {
+    RETRY_QUEUE.dead_letters(con).await
+}
+
+// Devuelve todo el dead letter a la cola de reintentos con los intentos a cero
+pub async fn requeue_dead_letters(con: &mut redis::aio::Connection) -> redis::RedisResult<usize> {
+    RETRY_QUEUE.requeue_dead_letters(con, |failed: &mut FailedMention| failed.attempts = 0).await
+}
@@ -0,0 +1,197 @@
+use chrono::{Datelike, Utc};
+use redis::AsyncCommands;
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::env;
+use tokio::time::Duration;
+
+// Último estado del límite de la API de menciones y momento del siguiente poll
+pub const POLL_SCHEDULE_KEY: &str = "twitter:poll_schedule";
+// Intervalo sin cabeceras de límite (ventana de 15 minutos de la API) y mínimo entre polls
+pub const POLL_INTERVAL: Duration = Duration::from_secs(15*60 + 1);
+pub const MIN_POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+// Contadores mensuales de posts por tipo: twitter:posts:{YYYY-MM}:{kind}
+const POSTS_PREFIX: &str = "twitter:posts:";
+const POSTS_TTL_SECS: i64 = 40 * 24 * 60 * 60;
+const DEFAULT_MONTHLY_POST_CAP: u32 = 500;
+
+// Cabeceras x-rate-limit-* de la última respuesta del endpoint de menciones
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateLimit {
+    pub limit: Option<u32>,
+    pub remaining: u32,
+    pub reset_at: i64,
+}
+
+impl RateLimit {
+    pub fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let header = |name: &str| headers.get(name)?.to_str().ok()?.trim().parse::<i64>().ok();
+
+        Some(Self {
+            limit: header("x-rate-limit-limit").map(|limit| limit.max(0) as u32),
+            remaining: header("x-rate-limit-remaining")?.max(0) as u32,
+            reset_at: header("x-rate-limit-reset")?,
+        })
+    }
+
+    // Reparte las peticiones que quedan en lo que falta de ventana; agotadas, se
+    // espera al reset
+    pub fn next_poll_in(&self, now: i64) -> Duration {
+        let window = (self.reset_at - now).max(0) as u64;
+        if self.remaining == 0 {
+            return Duration::from_secs(window + 1).max(MIN_POLL_INTERVAL);
+        }
+        Duration::from_secs(window / self.remaining as u64).clamp(MIN_POLL_INTERVAL, POLL_INTERVAL)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PollSchedule {
+    pub rate_limit: Option<RateLimit>,
+    pub next_poll_at: i64,
+}
+
+pub fn next_poll_in(rate_limit: Option<&RateLimit>) -> Duration {
+    rate_limit
+        .map(|rate_limit| rate_limit.next_poll_in(Utc::now().timestamp()))
+        .unwrap_or(POLL_INTERVAL)
+}
+
+pub async fn save_schedule(
+    con: &mut redis::aio::Connection,
+    rate_limit: Option<RateLimit>,
+    delay: Duration,
+) -> redis::RedisResult<()> {
+    let schedule = PollSchedule {
+        rate_limit,
+        next_poll_at: Utc::now().timestamp() + delay.as_secs() as i64,
+    };
+    con.set(POLL_SCHEDULE_KEY, serde_json::to_string(&schedule).unwrap_or_default()).await
+}
+
+pub async fn schedule(con: &mut redis::aio::Connection) -> redis::RedisResult<Option<PollSchedule>> {
+    let payload: Option<String> = con.get(POLL_SCHEDULE_KEY).await?;
+    Ok(payload.and_then(|payload| serde_json::from_str(&payload).ok()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PostKind {
+    Reply,
+    Scheduled,
+}
+
+impl PostKind {
+    const ALL: [PostKind; 2] = [PostKind::Reply, PostKind::Scheduled];
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            PostKind::Reply => "reply",
+            PostKind::Scheduled => "scheduled",
+        }
+    }
+}
+
+// Cupo mensual de posts de la cuenta. Una parte queda reservada para los posts
+// programados; las respuestas solo pueden usar el resto
+#[derive(Debug, Clone, Copy)]
+pub struct PostBudget {
+    cap: u32,
+    scheduled_reserve: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PostUsage {
+    used: u32,
+    limit: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PostQuota {
+    month: String,
+    cap: u32,
+    used: u32,
+    remaining: u32,
+    scheduled_reserve: u32,
+    by_kind: std::collections::BTreeMap<&'static str, PostUsage>,
+}
+
+impl PostBudget {
+    pub fn from_env() -> Self {
+        let cap = env::var("TWITTER_MONTHLY_POST_CAP")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(DEFAULT_MONTHLY_POST_CAP);
+        let scheduled_reserve = env::var("TWITTER_SCHEDULED_POST_RESERVE")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(cap / 10)
+            .min(cap);
+        Self { cap, scheduled_reserve }
+    }
+
+    fn limit(&self, kind: PostKind) -> u32 {
+        match kind {
+            PostKind::Reply => self.cap - self.scheduled_reserve,
+            PostKind::Scheduled => self.cap,
+        }
+    }
+
+    fn key(kind: PostKind) -> String {
+        let now = Utc::now();
+        format!("{}{:04}-{:02}:{}", POSTS_PREFIX, now.year(), now.month(), kind.as_str())
+    }
+
+    async fn used(con: &mut redis::aio::Connection) -> redis::RedisResult<Vec<(PostKind, u32)>> {
+        let mut used = Vec::new();
+        for kind in PostKind::ALL {
+            let count: Option<u32> = con.get(Self::key(kind)).await?;
+            used.push((kind, count.unwrap_or(0)));
+        }
+        Ok(used)
+    }
+
+    // Reserva un post del cupo del mes; falso si el tipo ya no tiene margen
+    pub async fn try_consume(&self, con: &mut redis::aio::Connection, kind: PostKind) -> redis::RedisResult<bool> {
+        let key = Self::key(kind);
+        let count: u32 = con.incr(&key, 1).await?;
+        if count == 1 {
+            let _: () = con.expire(&key, POSTS_TTL_SECS as usize).await?;
+        }
+
+        let total: u32 = Self::used(con).await?.iter().map(|(_, used)| used).sum();
+        if count > self.limit(kind) || total > self.cap {
+            let _: () = con.decr(&key, 1).await?;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
+    // Devuelve al cupo un post que no llegó a publicarse
+    pub async fn release(&self, con: &mut redis::aio::Connection, kind: PostKind) -> redis::RedisResult<()> {
+        let key = Self::key(kind);
+        let count: i64 = con.decr(&key, 1).await?;
+        if count < 0 {
+            let _: () = con.set(&key, 0).await?;
+        }
+        Ok(())
+    }
+
+    pub async fn quota(&self, con: &mut redis::aio::Connection) -> redis::RedisResult<PostQuota> {
+        let used = Self::used(con).await?;
+        let total: u32 = used.iter().map(|(_, used)| used).sum();
+        let now = Utc::now();
+
+        Ok(PostQuota {
+            month: format!("{:04}-{:02}", now.year(), now.month()),
+            cap: self.cap,
+            used: total,
+            remaining: self.cap.saturating_sub(total),
+            scheduled_reserve: self.scheduled_reserve,
+            by_kind: used
+                .into_iter()
+                .map(|(kind, used)| (kind.as_str(), PostUsage { used, limit: self.limit(kind) }))
+                .collect(),
+        })
+    }
+}
@@ -1,38 +1,174 @@
-use super::client::TwitterClient;
-use super::handlers::handle_mention;
-use tokio::time::{sleep, Duration};
+use super::client::{Mention, TwitterClient};
+use super::handlers::{inbound, MentionChannel};
+use super::queue;
+use super::ratelimit::{self, POLL_INTERVAL};
 use std::error::Error;
 use redis::AsyncCommands;
 use std::env;
+use crate::api::admin::{is_paused, Control};
+use crate::api::health::TWITTER_LAST_POLL_KEY;
+use crate::metrics;
+use crate::social::{AgentPipeline, InboundMessage, Outcome, SocialChannel};
+use crate::workers::Shutdown;
+use tracing::{debug, error, info, warn};
 
-const LAST_MENTION_KEY: &str = "twitter:last_mention_id";
-
-pub async fn start_streams(client: TwitterClient) -> Result<(), Box<dyn Error + Send + Sync>> {
-    println!("📡 Starting X monitoring");
+pub async fn start_streams(client: TwitterClient, mut shutdown: Shutdown) -> Result<(), Box<dyn Error + Send + Sync>> {
+    info!("starting X monitoring");
     let redis_url = env::var("REDIS_URL")?;
     let redis_client = redis::Client::open(redis_url)?;
     let mut con = redis_client.get_async_connection().await?;
+    let channel = MentionChannel::new(client, redis_client.clone());
+    let pipeline = AgentPipeline::new(redis_client.clone());
     
     loop {
-        println!("👂 Looking for mentions...");
-        
-
-        let since_id: Option<u64> = con.get(LAST_MENTION_KEY).await.ok();
-        
-        match client.get_mentions_since(since_id).await {
-            Ok(tweets) => {
-                for tweet in tweets.iter().rev() {
-                    println!("📨 Mention received");
-                    if let Err(e) = handle_mention(&client, tweet.clone()).await {
-                        println!("❌ Error processing mention: {}", e);
-                    }
-                    let _: () = con.set(LAST_MENTION_KEY, tweet.id.as_u64()).await?;
-                    println!("💾 Last mention ID saved");
+        if is_paused(&redis_client, Control::TwitterWorker).await {
+            info!("X monitoring paused, skipping poll");
+            if shutdown.sleep(POLL_INTERVAL).await {
+                info!("X monitoring stopped");
+                return Ok(());
+            }
+            continue;
+        }
+
+        // Con las respuestas pausadas no se lee nada: las menciones no se marcan, el
+        // cursor no se mueve y los reintentos esperan en la cola
+        if is_paused(&redis_client, Control::TwitterReplies).await {
+            info!("X replies paused, skipping poll");
+            if shutdown.sleep(POLL_INTERVAL).await {
+                info!("X monitoring stopped");
+                return Ok(());
+            }
+            continue;
+        }
+
+        // Un error de Redis corta esta pasada pero no el worker: se registra, se
+        // reconecta y se sigue en el siguiente poll
+        if let Err(e) = poll(&channel, &pipeline, &mut con, &shutdown).await {
+            error!(error = %e, "error processing X mentions");
+            metrics::redis_error("twitter");
+            match redis_client.get_async_connection().await {
+                Ok(reconnected) => con = reconnected,
+                Err(e) => error!(error = %e, "could not reconnect to Redis"),
+            }
+        }
+
+        // El siguiente poll se adapta a las peticiones que quedan en la ventana
+        let rate_limit = channel.client().mentions_rate_limit();
+        let delay = ratelimit::next_poll_in(rate_limit.as_ref());
+        info!(
+            next_poll_secs = delay.as_secs(),
+            remaining = ?rate_limit.as_ref().map(|rate_limit| rate_limit.remaining),
+            "next mention poll scheduled"
+        );
+        if let Err(e) = ratelimit::save_schedule(&mut con, rate_limit, delay).await {
+            warn!(error = %e, "could not save X poll schedule");
+        }
+
+        if shutdown.sleep(delay).await {
+            info!("X monitoring stopped");
+            return Ok(());
+        }
+    }
+}
+
+async fn poll(
+    channel: &MentionChannel,
+    pipeline: &AgentPipeline,
+    con: &mut redis::aio::Connection,
+    shutdown: &Shutdown,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    retry_failed(channel, pipeline, con, shutdown).await?;
+
+    info!("looking for mentions");
+
+    match channel.fetch().await {
+        Ok(batch) => {
+            let mut interrupted = false;
+            for message in batch.messages {
+                // El cursor se guarda por tweet, así que se puede cortar entre uno y otro
+                if shutdown.is_shutdown() {
+                    interrupted = true;
+                    break;
+                }
+                let tweet_id = message.id.clone();
+                process_mention(channel, pipeline, con, message).await?;
+                // Las fallidas ya están en la cola de reintentos, el cursor puede avanzar
+                // salvo que quede atraso sin leer por detrás
+                if batch.cursor.is_some() {
+                    channel.commit(&tweet_id).await?;
+                }
+            }
+            if let (false, Some(cursor)) = (interrupted, &batch.cursor) {
+                channel.commit(cursor).await?;
+            }
+            let _: () = con.set(TWITTER_LAST_POLL_KEY, chrono::Utc::now().timestamp()).await?;
+        },
+        Err(e) => error!(error = %e, "error getting mentions")
+    }
+    Ok(())
+}
+
+async fn process_mention(
+    channel: &MentionChannel,
+    pipeline: &AgentPipeline,
+    con: &mut redis::aio::Connection,
+    message: InboundMessage<Mention>,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    if !pipeline.claim(channel, &message.id).await? {
+        debug!(tweet_id = %message.id, "mention already handled");
+        return Ok(());
+    }
+
+    info!(tweet_id = %message.id, "mention received");
+
+    match pipeline.respond(channel, &message).await {
+        Ok(Outcome::Replied(_)) => info!(tweet_id = %message.id, "mention answered"),
+        Ok(Outcome::Spam) => {},
+        Err(e) => {
+            error!(tweet_id = %message.id, error = %e, "error processing mention, scheduling retry");
+            metrics::mention("twitter", "failed");
+            // Sin reintento encolado se suelta la marca: el cursor no pasa de este
+            // tweet y el siguiente poll lo vuelve a tomar
+            if let Err(e) = queue::record_failure(con, None, message.event, 1, &e.to_string()).await {
+                if let Err(e) = pipeline.unclaim(channel, &message.id).await {
+                    warn!(tweet_id = %message.id, error = %e, "could not release mention");
+                }
+                return Err(e.into());
+            }
+        },
+    }
+    Ok(())
+}
+
+async fn retry_failed(
+    channel: &MentionChannel,
+    pipeline: &AgentPipeline,
+    con: &mut redis::aio::Connection,
+    shutdown: &Shutdown,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
+    while !shutdown.is_shutdown() {
+        let retry = match queue::take_due(con).await? {
+            Some(retry) => retry,
+            None => break,
+        };
+
+        let message = inbound(retry.item.mention.clone());
+        let attempts = retry.item.attempts + 1;
+        match pipeline.respond(channel, &message).await {
+            Ok(_) => {
+                info!(tweet_id = %message.id, attempts, "mention answered on retry");
+                queue::complete(con, &retry).await?;
+            },
+            Err(e) => {
+                metrics::mention("twitter", "failed");
+                if queue::record_failure(con, Some(&retry), message.event, attempts, &e.to_string()).await? {
+                    warn!(tweet_id = %message.id, attempts, error = %e, "mention moved to dead letter");
+                    metrics::mention("twitter", "dead_letter");
+                } else {
+                    warn!(tweet_id = %message.id, attempts, error = %e, "mention retry failed");
                 }
             },
-            Err(e) => println!("❌ Error getting mentions: {}", e)
         }
-        
-        sleep(Duration::from_secs(15*60 + 1)).await;
     }
-}
\ No newline at end of file
+    Ok(())
+}
@@ -0,0 +1,204 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Display;
+use std::future::Future;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::{watch, Notify};
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, timeout, Duration};
+use tracing::{error, info, warn, Instrument};
+
+const INITIAL_BACKOFF: Duration = Duration::from_secs(5);
+const MAX_BACKOFF: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkerState {
+    Running,
+    Backoff,
+    Stopped,
+}
+
+#[derive(Serialize, Debug, Clone)]
+pub struct WorkerStatus {
+    pub state: WorkerState,
+    pub restarts: u32,
+    pub last_error: Option<String>,
+    pub last_started: Option<DateTime<Utc>>,
+    pub next_restart: Option<DateTime<Utc>>,
+}
+
+impl WorkerStatus {
+    fn new() -> Self {
+        Self {
+            state: WorkerState::Stopped,
+            restarts: 0,
+            last_error: None,
+            last_started: None,
+            next_restart: None,
+        }
+    }
+}
+
+// Señal de apagado compartida por todos los workers, más el aviso de poll inmediato
+// propio de cada worker
+#[derive(Clone)]
+pub struct Shutdown {
+    rx: watch::Receiver<bool>,
+    wake: Arc<Notify>,
+}
+
+impl Shutdown {
+    pub fn is_shutdown(&self) -> bool {
+        *self.rx.borrow()
+    }
+
+    // Se resuelve cuando se pide el apagado
+    pub async fn recv(&mut self) {
+        while !*self.rx.borrow() {
+            if self.rx.changed().await.is_err() {
+                return;
+            }
+        }
+    }
+
+    // Duerme `duration`, hasta que se pida el apagado o hasta que se pida un poll
+    // inmediato; devuelve true si hay que salir
+    pub async fn sleep(&mut self, duration: Duration) -> bool {
+        let wake = Arc::clone(&self.wake);
+        tokio::select! {
+            _ = sleep(duration) => self.is_shutdown(),
+            _ = wake.notified() => self.is_shutdown(),
+            _ = self.recv() => true,
+        }
+    }
+}
+
+pub struct Supervisor {
+    statuses: Arc<RwLock<BTreeMap<String, WorkerStatus>>>,
+    shutdown_tx: watch::Sender<bool>,
+    handles: Mutex<Vec<JoinHandle<()>>>,
+    wakers: Mutex<HashMap<String, Arc<Notify>>>,
+}
+
+impl Supervisor {
+    pub fn new() -> Self {
+        let (shutdown_tx, _) = watch::channel(false);
+        Self {
+            statuses: Arc::new(RwLock::new(BTreeMap::new())),
+            shutdown_tx,
+            handles: Mutex::new(Vec::new()),
+            wakers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn shutdown_signal(&self, wake: Arc<Notify>) -> Shutdown {
+        Shutdown {
+            rx: self.shutdown_tx.subscribe(),
+            wake,
+        }
+    }
+
+    // Despierta al worker para que haga un poll sin esperar su intervalo;
+    // devuelve false si no hay ningún worker con ese nombre
+    pub fn trigger(&self, name: &str) -> bool {
+        match self.wakers.lock().unwrap().get(name) {
+            Some(wake) => {
+                wake.notify_one();
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn statuses(&self) -> BTreeMap<String, WorkerStatus> {
+        self.statuses.read().unwrap().clone()
+    }
+
+    // Lanza un worker y lo reinicia con backoff exponencial si falla o hace panic
+    pub fn spawn<F, Fut, E>(&self, name: &str, factory: F)
+    where
+        F: Fn(Shutdown) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), E>> + Send + 'static,
+        E: Display + Send + 'static,
+    {
+        let worker_name = name.to_string();
+        let statuses = Arc::clone(&self.statuses);
+        let wake = Arc::new(Notify::new());
+        self.wakers.lock().unwrap().insert(worker_name.clone(), Arc::clone(&wake));
+        let mut shutdown = self.shutdown_signal(wake);
+        let update = {
+            let worker_name = worker_name.clone();
+            move |statuses: &RwLock<BTreeMap<String, WorkerStatus>>, f: &dyn Fn(&mut WorkerStatus)| {
+                let mut map = statuses.write().unwrap();
+                f(map.entry(worker_name.clone()).or_insert_with(WorkerStatus::new));
+            }
+        };
+        update(&self.statuses, &|_| {});
+
+        let handle = tokio::spawn(async move {
+            let mut backoff = INITIAL_BACKOFF;
+
+            loop {
+                update(&statuses, &|s| {
+                    s.state = WorkerState::Running;
+                    s.last_started = Some(Utc::now());
+                    s.next_restart = None;
+                });
+                info!(worker = %worker_name, "worker started");
+
+                let started = std::time::Instant::now();
+                let span = tracing::info_span!("worker", worker = %worker_name);
+                let result = tokio::spawn(factory(shutdown.clone()).instrument(span)).await;
+
+                if shutdown.is_shutdown() {
+                    update(&statuses, &|s| s.state = WorkerState::Stopped);
+                    info!(worker = %worker_name, "worker stopped");
+                    return;
+                }
+
+                let error = match result {
+                    Ok(Ok(())) => "worker exited".to_string(),
+                    Ok(Err(e)) => e.to_string(),
+                    Err(join_error) => format!("worker panicked: {}", join_error),
+                };
+
+                // Un worker que estuvo sano un buen rato vuelve al backoff inicial
+                if started.elapsed() > MAX_BACKOFF {
+                    backoff = INITIAL_BACKOFF;
+                }
+
+                error!(worker = %worker_name, error = %error, backoff_secs = backoff.as_secs(), "worker failed, restarting");
+                let next_restart = Utc::now() + chrono::Duration::from_std(backoff).unwrap_or_default();
+                update(&statuses, &|s| {
+                    s.state = WorkerState::Backoff;
+                    s.restarts += 1;
+                    s.last_error = Some(error.clone());
+                    s.next_restart = Some(next_restart);
+                });
+
+                if shutdown.sleep(backoff).await {
+                    update(&statuses, &|s| s.state = WorkerState::Stopped);
+                    return;
+                }
+                backoff = (backoff * 2).min(MAX_BACKOFF);
+            }
+        });
+
+        self.handles.lock().unwrap().push(handle);
+    }
+
+    // Pide el apagado y espera a que los workers terminen su ciclo actual
+    pub async fn shutdown(&self, grace: Duration) {
+        let _ = self.shutdown_tx.send(true);
+        let handles: Vec<_> = self.handles.lock().unwrap().drain(..).collect();
+
+        for handle in handles {
+            if timeout(grace, handle).await.is_err() {
+                warn!("worker did not stop within the grace period");
+            }
+        }
+        info!("all workers stopped");
+    }
+}